@@ -3,7 +3,7 @@
 use rustc_hir::def_id::LocalDefId;
 use rustc_index::{IndexSlice, IndexVec};
 use rustc_infer::infer::TyCtxtInferExt;
-use rustc_middle::mir::{Body, Promoted};
+use rustc_middle::mir::{Body, BorrowCheckResult, Promoted};
 use rustc_middle::traits::DefiningAnchor;
 use rustc_middle::ty::TyCtxt;
 use std::rc::Rc;
@@ -111,3 +111,18 @@ pub fn get_body_with_borrowck_facts(
     let promoted: &IndexSlice<_, _> = &promoted.borrow();
     *super::do_mir_borrowck(&infcx, input_body, promoted, Some(options)).1.unwrap()
 }
+
+/// Borrow checks a body like the `mir_borrowck` query does, and also returns its borrowck facts.
+/// This can be used by an override of `mir_borrowck` that keeps the facts, without borrow
+/// checking the body a second time and reporting its errors twice.
+///
+/// Like the query, this doesn't check bodies that are tainted by errors or that should be
+/// skipped, and no facts are returned for them.
+pub fn mir_borrowck_with_facts<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def: LocalDefId,
+    options: ConsumerOptions,
+) -> (&'tcx BorrowCheckResult<'tcx>, Option<BodyWithBorrowckFacts<'tcx>>) {
+    let (result, facts) = super::borrowck_with_options(tcx, def, Some(options));
+    (result, facts.map(|facts| *facts))
+}
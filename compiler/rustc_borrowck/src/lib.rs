@@ -108,6 +108,16 @@ pub fn provide(providers: &mut Providers) {
 }
 
 fn mir_borrowck(tcx: TyCtxt<'_>, def: LocalDefId) -> &BorrowCheckResult<'_> {
+    borrowck_with_options(tcx, def, None).0
+}
+
+/// Borrow checks `def` like the `mir_borrowck` query, and returns the facts requested by
+/// `consumer_options`, if the body is checked.
+fn borrowck_with_options<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def: LocalDefId,
+    consumer_options: Option<ConsumerOptions>,
+) -> (&'tcx BorrowCheckResult<'tcx>, Option<Box<BodyWithBorrowckFacts<'tcx>>>) {
     let (input_body, promoted) = tcx.mir_promoted(def);
     debug!("run query mir_borrowck: {}", tcx.def_path_str(def));
 
@@ -122,7 +132,7 @@ fn mir_borrowck(tcx: TyCtxt<'_>, def: LocalDefId) -> &BorrowCheckResult<'_> {
             used_mut_upvars: SmallVec::new(),
             tainted_by_errors: input_body.tainted_by_errors,
         };
-        return tcx.arena.alloc(result);
+        return (tcx.arena.alloc(result), None);
     }
 
     let hir_owner = tcx.local_def_id_to_hir_id(def).owner;
@@ -130,10 +140,11 @@ fn mir_borrowck(tcx: TyCtxt<'_>, def: LocalDefId) -> &BorrowCheckResult<'_> {
     let infcx =
         tcx.infer_ctxt().with_opaque_type_inference(DefiningAnchor::Bind(hir_owner.def_id)).build();
     let promoted: &IndexSlice<_, _> = &promoted.borrow();
-    let opt_closure_req = do_mir_borrowck(&infcx, input_body, promoted, None).0;
+    let (opt_closure_req, facts) =
+        do_mir_borrowck(&infcx, input_body, promoted, consumer_options);
     debug!("mir_borrowck done");
 
-    tcx.arena.alloc(opt_closure_req)
+    (tcx.arena.alloc(opt_closure_req), facts)
 }
 
 /// Perform the actual borrow checking.
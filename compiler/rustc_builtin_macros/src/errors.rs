@@ -821,6 +821,13 @@ pub(crate) struct TestRunnerNargs {
     pub(crate) span: Span,
 }
 
+#[derive(Diagnostic)]
+#[diag(builtin_macros_test_timeout_invalid)]
+pub(crate) struct TestTimeoutInvalid {
+    #[primary_span]
+    pub(crate) span: Span,
+}
+
 #[derive(Diagnostic)]
 #[diag(builtin_macros_expected_register_class_or_explicit_register)]
 pub(crate) struct ExpectedRegisterClassOrExplicitRegister {
@@ -339,6 +339,15 @@ pub fn expand_test_or_bench(
                                             ),
                                         },
                                     ),
+                                    // timeout: Some(...) | None
+                                    field(
+                                        "timeout",
+                                        if let Some(secs) = test_timeout(cx, &item) {
+                                            cx.expr_some(sp, cx.expr_u64(sp, secs))
+                                        } else {
+                                            cx.expr_none(sp)
+                                        },
+                                    ),
                                     // test_type: ...
                                     field(
                                         "test_type",
@@ -512,6 +521,18 @@ fn should_panic(cx: &ExtCtxt<'_>, i: &ast::Item) -> ShouldPanic {
     }
 }
 
+fn test_timeout(cx: &ExtCtxt<'_>, i: &ast::Item) -> Option<u64> {
+    let attr = attr::find_by_name(&i.attrs, sym::test_timeout)?;
+    // Handle #[test_timeout = "seconds"]
+    match attr.value_str().and_then(|secs| secs.as_str().parse().ok()) {
+        Some(secs) if secs > 0 => Some(secs),
+        _ => {
+            cx.dcx().emit_err(errors::TestTimeoutInvalid { span: attr.span });
+            None
+        }
+    }
+}
+
 enum TestType {
     UnitTest,
     IntegrationTest,
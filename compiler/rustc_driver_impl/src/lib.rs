@@ -30,10 +30,13 @@
 use rustc_interface::util::{self, collect_crate_types, get_codegen_backend};
 use rustc_interface::{interface, Queries};
 use rustc_lint::unerased_lint_store;
-use rustc_metadata::creader::MetadataLoader;
+use rustc_metadata::creader::{CStore, MetadataLoader};
 use rustc_metadata::locator;
+use rustc_middle::ty::TyCtxt;
 use rustc_session::config::{nightly_options, CG_OPTIONS, Z_OPTIONS};
-use rustc_session::config::{ErrorOutputType, Input, OutFileName, OutputType};
+use rustc_session::config::{
+    CodeStatsFormat, ErrorOutputType, Input, OutFileName, OutputType, PrintKind,
+};
 use rustc_session::getopts::{self, Matches};
 use rustc_session::lint::{Lint, LintId};
 use rustc_session::{config, EarlyDiagCtxt, Session};
@@ -94,8 +97,8 @@ pub(super) fn install() {}
 }
 
 use crate::session_diagnostics::{
-    RLinkEmptyVersionNumber, RLinkEncodingVersionMismatch, RLinkRustcVersionMismatch,
-    RLinkWrongFileType, RlinkNotAFile, RlinkUnableToRead,
+    CodeStatsWriteFail, RLinkEmptyVersionNumber, RLinkEncodingVersionMismatch,
+    RLinkRustcVersionMismatch, RLinkWrongFileType, RlinkNotAFile, RlinkUnableToRead,
 };
 
 rustc_fluent_macro::fluent_messages! { "../messages.ftl" }
@@ -266,6 +269,7 @@ pub fn run(self) -> interface::Result<()> {
             self.file_loader,
             self.make_codegen_backend,
             self.using_internal_features,
+            1,
         )
     }
 }
@@ -278,8 +282,10 @@ fn run_compiler(
         Box<dyn FnOnce(&config::Options) -> Box<dyn CodegenBackend> + Send>,
     >,
     using_internal_features: Arc<std::sync::atomic::AtomicBool>,
+    pass: usize,
 ) -> interface::Result<()> {
     let mut default_early_dcx = EarlyDiagCtxt::new(ErrorOutputType::default());
+    let all_args = at_args;
 
     // Throw away the first argument, the name of the binary.
     // In case of at_args being empty, as might be the case by
@@ -344,8 +350,18 @@ fn run_compiler(
 
     callbacks.config(&mut config);
 
-    interface::run_compiler(config, |compiler| {
+    // With `-Z apply-suggestions=N`, the crate is compiled again while suggestions keep being
+    // applied, up to `N` times in total. Later passes read the fixed files from disk, so this
+    // isn't done for drivers with a custom file loader or codegen backend.
+    let may_compile_again =
+        config.opts.unstable_opts.apply_suggestions.is_some_and(|passes| pass < passes.get())
+            && config.file_loader.is_none()
+            && config.make_codegen_backend.is_none();
+    let mut applied_suggestions = None;
+
+    let res = interface::run_compiler(config, |compiler| {
         let sess = &compiler.sess;
+        applied_suggestions = Some(Arc::clone(&sess.applied_suggestions));
         let codegen_backend = &*compiler.codegen_backend;
 
         // This is used for early exits unrelated to errors. E.g. when just
@@ -419,6 +435,15 @@ fn run_compiler(
             // Make sure name resolution and macro expansion is run.
             queries.global_ctxt()?.enter(|tcx| tcx.resolver_for_lowering(()));
 
+            // All crates have been loaded at this point. Unless they were requested together with
+            // prints that need linking, these are the last things to do.
+            if sess.opts.prints.iter().any(|p| is_crate_loading_print(p.kind)) {
+                queries.global_ctxt()?.enter(print_crate_loading_info);
+                if sess.opts.prints.iter().all(|p| is_crate_loading_print(p.kind)) {
+                    return early_exit();
+                }
+            }
+
             if callbacks.after_expansion(compiler, queries) == Compilation::Stop {
                 return early_exit();
             }
@@ -445,14 +470,9 @@ fn run_compiler(
 
             // This must run after monomorphization so that all generic types
             // have been instantiated.
-            if sess.opts.unstable_opts.print_type_sizes {
-                sess.code_stats.print_type_sizes();
-            }
-
-            if sess.opts.unstable_opts.print_vtable_sizes {
-                let crate_name = queries.global_ctxt()?.enter(|tcx| tcx.crate_name(LOCAL_CRATE));
-
-                sess.code_stats.print_vtable_sizes(crate_name);
+            let opts = &sess.opts.unstable_opts;
+            if opts.print_type_sizes.is_some() || opts.print_vtable_sizes.is_some() {
+                queries.global_ctxt()?.enter(report_code_stats);
             }
 
             Ok(Some(linker))
@@ -474,7 +494,47 @@ fn run_compiler(
         }
 
         Ok(())
-    })
+    });
+
+    if may_compile_again
+        && applied_suggestions.is_some_and(|applied| applied.load(Ordering::SeqCst) > 0)
+    {
+        // Errors of this pass still fail the compilation, even if the suggestions fixed them.
+        let next = run_compiler(all_args, callbacks, None, None, using_internal_features, pass + 1);
+        return res.and(next);
+    }
+
+    res
+}
+
+/// Prints the statistics requested with `-Z print-type-sizes` and `-Z print-vtable-sizes`, or
+/// writes them to the output directory if JSON was requested.
+fn report_code_stats(tcx: TyCtxt<'_>) {
+    let sess = tcx.sess;
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let out_dir = &tcx.output_filenames(()).out_directory;
+
+    match sess.opts.unstable_opts.print_type_sizes {
+        Some(CodeStatsFormat::Text) => sess.code_stats.print_type_sizes(),
+        Some(CodeStatsFormat::Json) => {
+            let path = out_dir.join(format!("{crate_name}.type_sizes.json"));
+            if let Err(err) = sess.code_stats.write_type_sizes_json(crate_name, &path) {
+                sess.dcx().emit_err(CodeStatsWriteFail { path, err });
+            }
+        }
+        None => {}
+    }
+
+    match sess.opts.unstable_opts.print_vtable_sizes {
+        Some(CodeStatsFormat::Text) => sess.code_stats.print_vtable_sizes(crate_name),
+        Some(CodeStatsFormat::Json) => {
+            let path = out_dir.join(format!("{crate_name}.vtable_sizes.json"));
+            if let Err(err) = sess.code_stats.write_vtable_sizes_json(crate_name, &path) {
+                sess.dcx().emit_err(CodeStatsWriteFail { path, err });
+            }
+        }
+        None => {}
+    }
 }
 
 // Extract output directory and file from matches.
@@ -699,9 +759,13 @@ fn print_crate_info(
     #[allow(unused_imports)]
     use {do_not_use_safe_print as safe_print, do_not_use_safe_print as safe_println};
 
-    // NativeStaticLibs and LinkArgs are special - printed during linking
+    // NativeStaticLibs and LinkArgs are special - printed during linking,
+    // CrateGraph and ExternResolution are printed after name resolution
     // (empty iterator returns true)
-    if sess.opts.prints.iter().all(|p| p.kind == NativeStaticLibs || p.kind == LinkArgs) {
+    let printed_later = |p: &config::PrintRequest| {
+        matches!(p.kind, NativeStaticLibs | LinkArgs) || is_crate_loading_print(p.kind)
+    };
+    if sess.opts.prints.iter().all(printed_later) {
         return Compilation::Continue;
     }
 
@@ -816,6 +880,8 @@ fn print_crate_info(
             // Any output here interferes with Cargo's parsing of other printed output
             NativeStaticLibs => {}
             LinkArgs => {}
+            // Printed by `print_crate_loading_info`
+            CrateGraph | ExternResolution => {}
             SplitDebuginfo => {
                 use rustc_target::spec::SplitDebuginfo::{Off, Packed, Unpacked};
 
@@ -844,6 +910,25 @@ fn print_crate_info(
     Compilation::Stop
 }
 
+fn is_crate_loading_print(kind: PrintKind) -> bool {
+    matches!(kind, PrintKind::CrateGraph | PrintKind::ExternResolution)
+}
+
+/// Prints the information about how dependencies were resolved, which is only
+/// available once all crates have been loaded.
+fn print_crate_loading_info(tcx: TyCtxt<'_>) {
+    let sess = tcx.sess;
+    let cstore = CStore::from_tcx(tcx);
+    for req in &sess.opts.prints {
+        let info = match req.kind {
+            PrintKind::CrateGraph => cstore.crate_graph_info(sess),
+            PrintKind::ExternResolution => cstore.extern_resolution_info(sess),
+            _ => continue,
+        };
+        req.out.overwrite(&info, sess);
+    }
+}
+
 /// Prints version information
 ///
 /// NOTE: this is a macro to support drivers built at a different time than the main `rustc_driver` crate.
@@ -10,7 +10,7 @@
 use rustc_middle::ty::{self, TyCtxt};
 use rustc_session::config::{OutFileName, PpHirMode, PpMode, PpSourceMode};
 use rustc_session::Session;
-use rustc_smir::rustc_internal::pretty::write_smir_pretty;
+use rustc_smir::rustc_internal::pretty::{write_smir_json, write_smir_pretty, write_smir_snapshot};
 use rustc_span::symbol::Ident;
 use rustc_span::FileName;
 
@@ -333,6 +333,16 @@ pub fn print<'tcx>(sess: &Session, ppm: PpMode, ex: PrintExtra<'tcx>) {
             write_smir_pretty(ex.tcx(), &mut out).unwrap();
             String::from_utf8(out).unwrap()
         }
+        MirJson => {
+            let mut out = Vec::new();
+            write_smir_json(ex.tcx(), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        }
+        SmirSnapshot => {
+            let mut out = Vec::new();
+            write_smir_snapshot(ex.tcx(), &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        }
         ThirTree => {
             let tcx = ex.tcx();
             let mut out = String::new();
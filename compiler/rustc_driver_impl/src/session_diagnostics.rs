@@ -32,6 +32,13 @@ pub(crate) struct RLinkRustcVersionMismatch<'a> {
 #[diag(driver_impl_rlink_no_a_file)]
 pub(crate) struct RlinkNotAFile;
 
+#[derive(Diagnostic)]
+#[diag(driver_impl_code_stats_write_fail)]
+pub(crate) struct CodeStatsWriteFail {
+    pub path: std::path::PathBuf,
+    pub err: std::io::Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(driver_impl_ice)]
 pub(crate) struct Ice;
@@ -0,0 +1,196 @@
+//! Applying machine-applicable suggestions to the source files they were made for.
+//!
+//! This backs `-Z apply-suggestions`, which lets rustc fix up code by itself instead of relying
+//! on an external tool like `rustfix` reading the JSON diagnostics.
+//!
+//! Suggestions are applied as a whole or not at all. A suggestion is skipped if any of its parts
+//! overlaps an edit that was already accepted, if it offers several alternative substitutions, or
+//! if it touches a file that isn't a local source file of the current crate. Skipped suggestions
+//! are usually reported again on the next compilation, once the conflicting edit has been made.
+
+use crate::CodeSuggestion;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::Lrc;
+use rustc_span::source_map::SourceMap;
+use rustc_span::{FileName, SourceFile};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests;
+
+/// The outcome of [`apply_suggestions`].
+#[derive(Debug, Default)]
+pub struct AppliedSuggestions {
+    /// The number of suggestions that were applied.
+    pub applied: usize,
+    /// The number of suggestions that were not applied, see the module docs for why that can
+    /// happen.
+    pub skipped: usize,
+    /// The files that were rewritten.
+    pub files: Vec<PathBuf>,
+    /// The files that could not be rewritten. Suggestions for these files are counted as
+    /// skipped.
+    pub errors: Vec<(PathBuf, ApplySuggestionsError)>,
+}
+
+#[derive(Debug)]
+pub enum ApplySuggestionsError {
+    /// The file no longer has the contents the compiler saw, so the byte offsets of the
+    /// suggestions can't be trusted.
+    Modified,
+    Io(io::Error),
+}
+
+impl fmt::Display for ApplySuggestionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplySuggestionsError::Modified => {
+                f.write_str("the file was modified during compilation")
+            }
+            ApplySuggestionsError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+/// A single replacement, in byte offsets of the file as it is on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit {
+    range: Range<usize>,
+    snippet: String,
+}
+
+impl Edit {
+    fn conflicts_with(&self, other: &Edit) -> bool {
+        if self.range.is_empty() && other.range.is_empty() {
+            // Two insertions at the same point have no well-defined order.
+            self.range.start == other.range.start
+        } else {
+            self.range.start < other.range.end && other.range.start < self.range.end
+        }
+    }
+}
+
+struct FileEdits {
+    file: Lrc<SourceFile>,
+    edits: Vec<Edit>,
+    /// The number of suggestions that contributed to `edits`.
+    suggestions: usize,
+}
+
+/// Applies `suggestions` to the files they point into, in the order they are given.
+pub fn apply_suggestions(sm: &SourceMap, suggestions: &[CodeSuggestion]) -> AppliedSuggestions {
+    let mut result = AppliedSuggestions::default();
+    let mut files: FxIndexMap<PathBuf, FileEdits> = FxIndexMap::default();
+
+    for suggestion in suggestions {
+        let Some(edits) = suggestion_edits(sm, suggestion) else {
+            result.skipped += 1;
+            continue;
+        };
+
+        // The same suggestion is sometimes reported by several diagnostics, e.g. for each
+        // expansion of a macro. Applying it once is enough.
+        let duplicate = edits
+            .iter()
+            .all(|(_, path, edit)| files.get(path).is_some_and(|file| file.edits.contains(edit)));
+        if duplicate {
+            continue;
+        }
+
+        let conflicts = edits.iter().any(|(_, path, edit)| {
+            files.get(path).is_some_and(|file| file.edits.iter().any(|e| e.conflicts_with(edit)))
+        });
+        if conflicts {
+            result.skipped += 1;
+            continue;
+        }
+
+        let mut touched = vec![];
+        for (file, path, edit) in edits {
+            let file_edits = files.entry(path.clone()).or_insert_with(|| FileEdits {
+                file,
+                edits: vec![],
+                suggestions: 0,
+            });
+            file_edits.edits.push(edit);
+            if !touched.contains(&path) {
+                file_edits.suggestions += 1;
+                touched.push(path);
+            }
+        }
+    }
+
+    for (path, FileEdits { file, mut edits, suggestions }) in files {
+        match rewrite_file(&path, &file, &mut edits) {
+            Ok(()) => {
+                result.applied += suggestions;
+                result.files.push(path);
+            }
+            Err(e) => {
+                result.skipped += suggestions;
+                result.errors.push((path, e));
+            }
+        }
+    }
+
+    result
+}
+
+/// Turns a suggestion into edits of files on disk, or returns `None` if it can't be applied.
+fn suggestion_edits(
+    sm: &SourceMap,
+    suggestion: &CodeSuggestion,
+) -> Option<Vec<(Lrc<SourceFile>, PathBuf, Edit)>> {
+    // With several substitutions the user is supposed to pick one, so there's nothing
+    // to apply automatically.
+    let [substitution] = &suggestion.substitutions[..] else { return None };
+
+    let mut edits: Vec<(Lrc<SourceFile>, PathBuf, Edit)> = vec![];
+    for part in &substitution.parts {
+        let span = part.span.data();
+        let file = sm.lookup_source_file(span.lo);
+        // Spans of other crates, or spans crossing from one file into another, can't be fixed.
+        if file.src.is_none() || !file.contains(span.hi) {
+            return None;
+        }
+        let FileName::Real(name) = &file.name else { return None };
+        let path = name.local_path()?.to_path_buf();
+        let lo = file.original_relative_byte_pos(span.lo).0 as usize;
+        let hi = file.original_relative_byte_pos(span.hi).0 as usize;
+        let edit = Edit { range: lo..hi, snippet: part.snippet.clone() };
+
+        // A suggestion whose own parts overlap makes no sense to apply.
+        if edits.iter().any(|(_, p, e)| *p == path && e.conflicts_with(&edit)) {
+            return None;
+        }
+        edits.push((file, path, edit));
+    }
+    Some(edits)
+}
+
+fn rewrite_file(
+    path: &Path,
+    file: &SourceFile,
+    edits: &mut [Edit],
+) -> Result<(), ApplySuggestionsError> {
+    let mut src = fs::read_to_string(path).map_err(ApplySuggestionsError::Io)?;
+    // The hash is computed on the file contents before normalization, so this also
+    // guarantees that the byte offsets computed from the spans line up with `src`.
+    if !file.src_hash.matches(&src) {
+        return Err(ApplySuggestionsError::Modified);
+    }
+
+    // Edits don't overlap, so applying them back to front keeps the earlier offsets valid.
+    // Sorting by the end as well puts an insertion before a replacement starting at the same
+    // offset, so the inserted text ends up in front of the replaced one.
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    for edit in edits.iter().rev() {
+        src.replace_range(edit.range.clone(), &edit.snippet);
+    }
+
+    fs::write(path, src).map_err(ApplySuggestionsError::Io)
+}
@@ -0,0 +1,96 @@
+use super::*;
+
+use crate::{Applicability, Substitution, SubstitutionPart, SuggestionStyle};
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::{BytePos, Span};
+
+/// Writes `code` to a fresh file, applies the suggestions built by `make_suggestions` for it and
+/// returns the outcome together with the new file contents.
+fn apply(
+    name: &str,
+    code: &str,
+    make_suggestions: impl FnOnce(&dyn Fn(u32, u32) -> Span) -> Vec<CodeSuggestion>,
+) -> (AppliedSuggestions, String) {
+    rustc_span::create_default_session_globals_then(|| {
+        let dir = std::env::temp_dir()
+            .join(format!("rustc-apply-suggestions-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lib.rs");
+        fs::write(&path, code).unwrap();
+
+        let sm = SourceMap::new(FilePathMapping::empty());
+        let file = sm.load_file(&path).unwrap();
+        let span = |lo, hi| {
+            Span::with_root_ctxt(file.start_pos + BytePos(lo), file.start_pos + BytePos(hi))
+        };
+        let suggestions = make_suggestions(&span);
+
+        let applied = apply_suggestions(&sm, &suggestions);
+        let result = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        (applied, result)
+    })
+}
+
+fn suggestion(parts: Vec<(Span, &str)>) -> CodeSuggestion {
+    CodeSuggestion {
+        substitutions: vec![Substitution {
+            parts: parts
+                .into_iter()
+                .map(|(span, snippet)| SubstitutionPart { span, snippet: snippet.to_owned() })
+                .collect(),
+        }],
+        msg: "suggestion".into(),
+        style: SuggestionStyle::ShowCode,
+        applicability: Applicability::MachineApplicable,
+    }
+}
+
+#[test]
+fn replacements_and_insertions() {
+    let (applied, result) = apply("basic", "fn main() { let x = 1; }", |span| {
+        vec![
+            suggestion(vec![(span(16, 17), "_x")]),
+            suggestion(vec![(span(20, 21), "1u8"), (span(0, 0), "#[allow(dead_code)]\n")]),
+        ]
+    });
+    assert_eq!(result, "#[allow(dead_code)]\nfn main() { let _x = 1u8; }");
+    assert_eq!(applied.applied, 2);
+    assert_eq!(applied.skipped, 0);
+    assert!(applied.errors.is_empty());
+}
+
+#[test]
+fn overlapping_suggestions_are_skipped() {
+    let (applied, result) = apply("overlap", "fn main() { let x = 1; }", |span| {
+        vec![
+            suggestion(vec![(span(16, 17), "_x")]),
+            // Overlaps the first suggestion, so none of its parts may be applied.
+            suggestion(vec![(span(12, 17), "let mut x"), (span(20, 21), "2")]),
+            // Applying the same suggestion twice is not a conflict.
+            suggestion(vec![(span(16, 17), "_x")]),
+        ]
+    });
+    assert_eq!(result, "fn main() { let _x = 1; }");
+    assert_eq!(applied.applied, 1);
+    assert_eq!(applied.skipped, 1);
+}
+
+#[test]
+fn insertion_before_replacement() {
+    let (applied, result) = apply("adjacent", "fn f(x: u8) {}", |span| {
+        vec![suggestion(vec![(span(5, 6), "y")]), suggestion(vec![(span(5, 5), "mut ")])]
+    });
+    assert_eq!(result, "fn f(mut y: u8) {}");
+    assert_eq!(applied.applied, 2);
+}
+
+#[test]
+fn crlf_and_bom() {
+    // The source map normalizes both away, the edits have to be made in the original file.
+    let (applied, result) = apply("crlf", "\u{feff}fn a() {}\r\nfn b() {}\r\n", |span| {
+        vec![suggestion(vec![(span(13, 14), "c")])]
+    });
+    assert_eq!(result, "\u{feff}fn a() {}\r\nfn c() {}\r\n");
+    assert_eq!(applied.applied, 1);
+}
@@ -0,0 +1,218 @@
+//! Folding of diagnostics that are reported once for every invocation of a macro.
+//!
+//! A lint firing inside a macro is reported once for each place the macro is invoked, so a
+//! macro used in 200 places produces 200 diagnostics that only differ in the invocation they
+//! come from. With `-Z group-diagnostics`, the [`GroupingEmitter`] folds diagnostics that share
+//! their level, code, message, outermost macro and position in that macro into the first of
+//! them, which gets a note pointing at all the invocations. This works with any emitter it wraps.
+//!
+//! Grouping requires holding diagnostics back until all instances have been seen, so they are
+//! only passed on to the wrapped emitter once compilation ends. Buffered diagnostics keep
+//! their relative order. As the error and warning counts still include the folded
+//! diagnostics, a note saying how many were folded follows them.
+
+use crate::emitter::{DynEmitter, Emitter};
+use crate::translation::{to_fluent_args, Translate};
+use crate::{pluralize, DiagInner, ErrCode, FluentBundle, Level, MultiSpan};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::Lrc;
+use rustc_lint_defs::Level as LintLevel;
+use rustc_span::hygiene::{ExpnData, ExpnKind, MacroKind};
+use rustc_span::source_map::SourceMap;
+use rustc_span::symbol::Symbol;
+use rustc_span::Span;
+use std::path::Path;
+
+/// Emitter that groups diagnostics coming from different invocations of the same macro, and
+/// passes them on to another emitter.
+pub struct GroupingEmitter {
+    emitter: Box<DynEmitter>,
+    /// Diagnostics waiting to be emitted, in the order they were first seen.
+    buffered: Vec<Group>,
+    /// Index into `buffered` of the group each key belongs to.
+    groups: FxHashMap<GroupKey, usize>,
+    /// Number of diagnostics that were folded into the first instance of their group.
+    folded: usize,
+}
+
+struct Group {
+    diag: DiagInner,
+    /// The outermost macro the diagnostic comes from, `None` for diagnostics that can't be
+    /// grouped.
+    root: Option<(MacroKind, Symbol)>,
+    /// The invocations of `root` that reported this diagnostic.
+    instances: Vec<Span>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct GroupKey {
+    level: Level,
+    code: Option<ErrCode>,
+    message: String,
+    macro_kind: MacroKind,
+    macro_name: Symbol,
+    macro_def_site: Span,
+    primary_span: RelativeSpan,
+}
+
+/// The position of the primary span of a diagnostic, relative to the macro it comes from.
+/// Spans of the tokens of a macro only differ in their context between invocations.
+#[derive(PartialEq, Eq, Hash)]
+enum RelativeSpan {
+    /// Offsets of the span in the definition of the macro.
+    DefSite(u32, u32),
+    /// Offsets of the span in the invocation, e.g. for a span of a macro argument.
+    CallSite(u32, u32),
+    /// A span anywhere else, which doesn't match another invocation.
+    Other(Span),
+}
+
+impl RelativeSpan {
+    fn new(span: Span, expn_data: &ExpnData) -> RelativeSpan {
+        let offsets = |base: Span| ((span.lo() - base.lo()).0, (span.hi() - base.lo()).0);
+        if expn_data.def_site.contains(span) {
+            let (lo, hi) = offsets(expn_data.def_site);
+            RelativeSpan::DefSite(lo, hi)
+        } else if expn_data.call_site.contains(span) {
+            let (lo, hi) = offsets(expn_data.call_site);
+            RelativeSpan::CallSite(lo, hi)
+        } else {
+            RelativeSpan::Other(span)
+        }
+    }
+}
+
+impl GroupingEmitter {
+    pub fn new(emitter: Box<DynEmitter>) -> GroupingEmitter {
+        GroupingEmitter { emitter, buffered: Vec::new(), groups: FxHashMap::default(), folded: 0 }
+    }
+
+    fn buffer(&mut self, diag: DiagInner) {
+        let primary_span = diag.span.primary_span();
+        let root = primary_span.and_then(macro_backtrace_root);
+        let (Some(primary_span), Some((macro_kind, macro_name, expn_data))) = (primary_span, root)
+        else {
+            self.buffered.push(Group { diag, root: None, instances: vec![] });
+            return;
+        };
+
+        let args = to_fluent_args(diag.args.iter());
+        let key = GroupKey {
+            level: diag.level,
+            code: diag.code,
+            message: self.translate_messages(&diag.messages, &args).into_owned(),
+            macro_kind,
+            macro_name,
+            macro_def_site: expn_data.def_site,
+            primary_span: RelativeSpan::new(primary_span, &expn_data),
+        };
+        let call_site = expn_data.call_site;
+
+        if let Some(&index) = self.groups.get(&key) {
+            self.buffered[index].instances.push(call_site);
+            self.folded += 1;
+        } else {
+            self.groups.insert(key, self.buffered.len());
+            self.buffered.push(Group {
+                diag,
+                root: Some((macro_kind, macro_name)),
+                instances: vec![call_site],
+            });
+        }
+    }
+
+    /// Emits all buffered diagnostics. Diagnostics emitted afterwards start new groups.
+    fn flush(&mut self) {
+        self.groups.clear();
+        for Group { mut diag, root, instances } in std::mem::take(&mut self.buffered) {
+            if let Some((macro_kind, name)) = root
+                && instances.len() > 1
+            {
+                let level = diag.level;
+                let descr = macro_kind.descr();
+                let msg = format!(
+                    "this {level} was reported {} times, once for each of these invocations \
+                    of the {descr} `{name}`",
+                    instances.len(),
+                );
+                diag.sub(Level::Note, msg, MultiSpan::from_spans(instances));
+            }
+            self.emitter.emit_diagnostic(diag);
+        }
+
+        if self.folded > 0 {
+            let folded = std::mem::take(&mut self.folded);
+            let msg = format!(
+                "{folded} diagnostic{s} repeated in other macro invocations {were} folded into \
+                the ones reported above",
+                s = pluralize!(folded),
+                were = pluralize!("was", folded),
+            );
+            self.emitter.emit_diagnostic(DiagInner::new(Level::Note, msg));
+        }
+    }
+}
+
+/// Returns the outermost macro expansion `span` comes from.
+fn macro_backtrace_root(span: Span) -> Option<(MacroKind, Symbol, ExpnData)> {
+    span.macro_backtrace()
+        .filter_map(|expn_data| match expn_data.kind {
+            ExpnKind::Macro(kind, name) => Some((kind, name, expn_data)),
+            ExpnKind::Root | ExpnKind::AstPass(..) | ExpnKind::Desugaring(..) => None,
+        })
+        .last()
+}
+
+impl Drop for GroupingEmitter {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl Translate for GroupingEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        self.emitter.fluent_bundle()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        self.emitter.fallback_fluent_bundle()
+    }
+}
+
+impl Emitter for GroupingEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        match diag.level {
+            // These either end compilation or summarize it, so everything that was held back
+            // has to come out before them.
+            Level::Bug | Level::Fatal | Level::FailureNote => {
+                self.flush();
+                self.emitter.emit_diagnostic(diag);
+            }
+            _ => self.buffer(diag),
+        }
+    }
+
+    fn emit_artifact_notification(&mut self, path: &Path, artifact_type: &str) {
+        self.emitter.emit_artifact_notification(path, artifact_type);
+    }
+
+    fn emit_future_breakage_report(&mut self, diags: Vec<DiagInner>) {
+        self.emitter.emit_future_breakage_report(diags);
+    }
+
+    fn emit_unused_externs(&mut self, lint_level: LintLevel, unused_externs: &[&str]) {
+        self.emitter.emit_unused_externs(lint_level, unused_externs);
+    }
+
+    fn should_show_explain(&self) -> bool {
+        self.emitter.should_show_explain()
+    }
+
+    fn supports_color(&self) -> bool {
+        self.emitter.supports_color()
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        self.emitter.source_map()
+    }
+}
@@ -0,0 +1,339 @@
+//! An emitter that renders the diagnostics of a compilation as a single HTML report.
+//!
+//! The report is meant to be attached to CI runs and read in a browser, so it is
+//! self-contained: the styles are inlined and no scripts are used. Like the SARIF emitter, the
+//! emitter collects diagnostics as they are emitted and writes the whole document out when it
+//! is dropped.
+//!
+//! Snippets are laid out by a [`HumanEmitter`], so they look exactly like the terminal output,
+//! including multi-span labels and suggestions shown as diffs. Colors are turned into CSS
+//! classes instead of ANSI escape codes. Notes and macro backtraces are shown in collapsible
+//! sections below the snippet of the diagnostic they belong to.
+
+use crate::emitter::{Destination, Emitter, HumanEmitter};
+use crate::translation::{to_fluent_args, Translate};
+use crate::{pluralize, DiagInner, FluentBundle, LazyFallbackBundle, Level, Subdiag};
+use derive_setters::Setters;
+use rustc_data_structures::sync::{IntoDynSyncSend, Lrc};
+use rustc_span::source_map::SourceMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use termcolor::{Color, ColorSpec, WriteColor};
+
+#[cfg(test)]
+mod tests;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #24292f; background: #fff; }
+h1 { font-size: 1.4em; }
+pre { font-family: monospace; background: #f6f8fa; padding: 0.8em; overflow-x: auto; }
+details.diagnostic { border: 1px solid #d0d7de; border-radius: 6px; margin: 1em 0; }
+details.diagnostic > summary { padding: 0.5em; font-family: monospace; font-weight: bold; }
+details.diagnostic > :not(summary) { margin: 0 0.5em 0.5em; }
+details.child > summary, details.backtrace > summary { font-family: monospace; }
+ul.notes { font-family: monospace; }
+.error > summary .level, .fg-red { color: #cf222e; }
+.warning > summary .level, .fg-yellow { color: #9a6700; }
+.note > summary .level, .fg-green { color: #1a7f37; }
+.help > summary .level, .fg-cyan { color: #0969da; }
+.fg-blue { color: #0550ae; }
+.fg-magenta { color: #8250df; }
+.bold { font-weight: bold; }
+";
+
+/// Emitter that writes an HTML report of all diagnostics.
+#[derive(Setters)]
+pub struct HtmlEmitter {
+    #[setters(skip)]
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    #[setters(skip)]
+    sm: Lrc<SourceMap>,
+    fluent_bundle: Option<Lrc<FluentBundle>>,
+    #[setters(skip)]
+    fallback_bundle: LazyFallbackBundle,
+    ui_testing: bool,
+    diagnostic_width: Option<usize>,
+    /// Write a report even if no diagnostics were collected. Emitters that are only used for
+    /// early errors leave this unset, so they don't produce a second, empty report.
+    emit_empty_report: bool,
+    #[setters(skip)]
+    diagnostics: Vec<String>,
+    /// Messages summarizing the compilation, like "aborting due to 2 previous errors".
+    #[setters(skip)]
+    summary: Vec<String>,
+    #[setters(skip)]
+    errors: usize,
+    #[setters(skip)]
+    warnings: usize,
+}
+
+impl HtmlEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Lrc<SourceMap>,
+        fallback_bundle: LazyFallbackBundle,
+    ) -> HtmlEmitter {
+        HtmlEmitter {
+            dst: IntoDynSyncSend(dst),
+            sm,
+            fluent_bundle: None,
+            fallback_bundle,
+            ui_testing: false,
+            diagnostic_width: None,
+            emit_empty_report: false,
+            diagnostics: Vec::new(),
+            summary: Vec::new(),
+            errors: 0,
+            warnings: 0,
+        }
+    }
+
+    /// Lays out `diag` the way the human readable output would, and returns it as HTML.
+    fn render(&self, diag: DiagInner, macro_backtrace: bool) -> String {
+        let buf = HtmlBuffer::default();
+        HumanEmitter::new(Box::new(buf.clone()) as Destination, self.fallback_bundle.clone())
+            .sm(Some(self.sm.clone()))
+            .fluent_bundle(self.fluent_bundle.clone())
+            .ui_testing(self.ui_testing)
+            .diagnostic_width(self.diagnostic_width)
+            .macro_backtrace(macro_backtrace)
+            .emit_diagnostic(diag);
+        let mut inner = buf.0.lock().unwrap();
+        inner.push_text();
+        std::mem::take(&mut inner.html)
+    }
+
+    /// Renders a child of `parent` as if it was a diagnostic of its own.
+    fn render_child(&self, parent: &DiagInner, child: &Subdiag) -> String {
+        let mut diag = DiagInner::new_with_messages(child.level, child.messages.clone());
+        diag.span = child.span.clone();
+        diag.args = parent.args.clone();
+        self.render(diag, false)
+    }
+
+    fn render_diagnostic(&self, mut diag: DiagInner) -> String {
+        let args = to_fluent_args(diag.args.iter());
+        let class = match diag.level {
+            Level::Bug | Level::DelayedBug | Level::Fatal | Level::Error => "error",
+            Level::ForceWarning(_) | Level::Warning => "warning",
+            Level::Note | Level::OnceNote => "note",
+            Level::Help | Level::OnceHelp => "help",
+            Level::FailureNote | Level::Allow | Level::Expect(_) => unreachable!(),
+        };
+
+        let mut out = format!("<details class=\"diagnostic {class}\" open>\n<summary>");
+        write!(out, "<span class=\"level\">{}", escape(diag.level.to_str())).unwrap();
+        if let Some(code) = diag.code {
+            write!(out, "[{code}]").unwrap();
+        }
+        let message = self.translate_messages(&diag.messages, &args);
+        write!(out, "</span>: {}", escape(&message)).unwrap();
+        if let Some(span) = diag.span.primary_span()
+            && !span.is_dummy()
+        {
+            let location = self.sm.span_to_diagnostic_string(span);
+            write!(out, " <span class=\"location\">{}</span>", escape(&location)).unwrap();
+        }
+        writeln!(out, "</summary>").unwrap();
+
+        // Children are rendered separately so that they can be collapsed. Suggestions stay
+        // with the snippet they apply to.
+        let children = std::mem::take(&mut diag.children);
+        writeln!(out, "<pre>{}</pre>", self.render(diag.clone(), false)).unwrap();
+
+        let mut spanless = String::new();
+        for child in &children {
+            let level = child.level.to_str();
+            let message = escape(&self.translate_messages(&child.messages, &args));
+            if child.span.primary_spans().is_empty() {
+                writeln!(spanless, "<li>{level}: {message}</li>").unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "<details class=\"child\" open><summary>{level}: {message}</summary>\
+                    <pre>{}</pre></details>",
+                    self.render_child(&diag, child),
+                )
+                .unwrap();
+            }
+        }
+        if !spanless.is_empty() {
+            writeln!(out, "<ul class=\"notes\">\n{spanless}</ul>").unwrap();
+        }
+
+        let from_expansion =
+            diag.span.primary_spans().iter().any(|span| span.macro_backtrace().next().is_some());
+        if from_expansion {
+            diag.suggestions = Ok(vec![]);
+            writeln!(
+                out,
+                "<details class=\"backtrace\"><summary>macro backtrace</summary>\
+                <pre>{}</pre></details>",
+                self.render(diag, true),
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "</details>").unwrap();
+        out
+    }
+
+    fn emit_report(&mut self) -> io::Result<()> {
+        let (errors, warnings) = (self.errors, self.warnings);
+        let title = format!(
+            "rustc: {errors} error{}, {warnings} warning{}",
+            pluralize!(errors),
+            pluralize!(warnings),
+        );
+
+        let dst = &mut *self.dst;
+        writeln!(dst, "<!DOCTYPE html>")?;
+        writeln!(dst, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(dst, "<title>{title}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>")?;
+        writeln!(dst, "<h1>{title}</h1>")?;
+        for diagnostic in &self.diagnostics {
+            dst.write_all(diagnostic.as_bytes())?;
+        }
+        if !self.summary.is_empty() {
+            writeln!(dst, "<pre class=\"summary\">{}</pre>", self.summary.join("\n"))?;
+        }
+        writeln!(dst, "</body>\n</html>")?;
+        dst.flush()
+    }
+}
+
+impl Translate for HtmlEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        self.fluent_bundle.as_ref()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for HtmlEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        match diag.level {
+            Level::FailureNote => {
+                let summary = self.render(diag, false);
+                self.summary.push(summary);
+                return;
+            }
+            Level::Bug | Level::DelayedBug | Level::Fatal | Level::Error => self.errors += 1,
+            Level::ForceWarning(_) | Level::Warning => self.warnings += 1,
+            _ => {}
+        }
+        let diagnostic = self.render_diagnostic(diag);
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+}
+
+impl Drop for HtmlEmitter {
+    fn drop(&mut self) {
+        if self.diagnostics.is_empty() && !self.emit_empty_report {
+            return;
+        }
+        if let Err(e) = self.emit_report() {
+            panic!("failed to print HTML report: {e:?}");
+        }
+    }
+}
+
+/// Escapes `text` for use in HTML text and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A destination for a `HumanEmitter` that escapes the text written to it and turns colors
+/// into `<span>`s with CSS classes.
+#[derive(Default, Clone)]
+struct HtmlBuffer(Arc<Mutex<HtmlBufferInner>>);
+
+#[derive(Default)]
+struct HtmlBufferInner {
+    html: String,
+    /// The bytes written since the last color change. They are only decoded once the color
+    /// changes or the output is taken, since a character may be split across several writes.
+    text: Vec<u8>,
+    /// Whether a `<span>` was opened by the last call to `set_color`.
+    in_span: bool,
+}
+
+impl HtmlBufferInner {
+    /// Decodes and escapes the text written since the last color change.
+    fn push_text(&mut self) {
+        let text = String::from_utf8_lossy(&self.text);
+        self.html.push_str(&escape(&text));
+        self.text.clear();
+    }
+}
+
+impl Write for HtmlBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WriteColor for HtmlBuffer {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.reset()?;
+        let mut classes = vec![];
+        if let Some(color) = spec.fg() {
+            classes.push(match color {
+                Color::Black => "fg-black",
+                Color::Blue => "fg-blue",
+                Color::Green => "fg-green",
+                Color::Red => "fg-red",
+                Color::Cyan => "fg-cyan",
+                Color::Magenta => "fg-magenta",
+                Color::Yellow => "fg-yellow",
+                Color::White => "fg-white",
+                _ => "fg-other",
+            });
+        }
+        if spec.bold() {
+            classes.push("bold");
+        }
+        if !classes.is_empty() {
+            let mut inner = self.0.lock().unwrap();
+            write!(inner.html, "<span class=\"{}\">", classes.join(" ")).unwrap();
+            inner.in_span = true;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        let mut inner = self.0.lock().unwrap();
+        inner.push_text();
+        if inner.in_span {
+            inner.html.push_str("</span>");
+            inner.in_span = false;
+        }
+        Ok(())
+    }
+}
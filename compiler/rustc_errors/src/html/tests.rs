@@ -0,0 +1,111 @@
+use super::*;
+
+use crate::{codes::*, Applicability, DiagCtxt};
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::{BytePos, Span};
+
+use std::path::Path;
+use std::str;
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+/// Runs `f` against a `DiagCtxt` backed by an HTML emitter and returns the resulting report.
+fn with_html_report(code: &str, emit_empty_report: bool, f: impl FnOnce(&DiagCtxt)) -> String {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let he = HtmlEmitter::new(Box::new(Shared { data: output.clone() }), sm, fallback_bundle)
+            .emit_empty_report(emit_empty_report);
+
+        let dcx = DiagCtxt::new(Box::new(he));
+        f(&dcx);
+        // The report is only written once the emitter goes away.
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        str::from_utf8(&bytes).unwrap().to_owned()
+    })
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+#[test]
+fn escaped_and_without_ansi_codes() {
+    let report = with_html_report("fn main() { let x: Vec<u8> = 1; }", false, |dcx| {
+        dcx.struct_span_err(span(29, 30), "expected `Vec<u8>`").with_code(E0308).emit();
+    });
+
+    assert!(report.starts_with("<!DOCTYPE html>"));
+    assert!(report.contains("<title>rustc: 1 error, 0 warnings</title>"));
+    assert!(report.contains(
+        "<summary><span class=\"level\">error[E0308]</span>: expected `Vec&lt;u8&gt;` \
+        <span class=\"location\">test.rs:1:30: 1:31</span></summary>"
+    ));
+    // The source line is part of the snippet.
+    assert!(report.contains("let x: Vec&lt;u8&gt; = 1;"));
+    assert!(!report.contains('\x1b'));
+}
+
+#[test]
+fn notes_and_suggestions() {
+    let report = with_html_report("fn main() {\n    let x = 1\n}", false, |dcx| {
+        dcx.struct_span_warn(span(20, 21), "unused variable")
+            .with_span_note(span(16, 21), "declared here")
+            .with_note("no location")
+            .with_span_suggestion(
+                span(20, 21),
+                "prefix it with an underscore",
+                "_x",
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+
+    assert!(report.contains("<title>rustc: 0 errors, 1 warning</title>"));
+    assert!(report.contains("<details class=\"diagnostic warning\" open>"));
+    assert!(report.contains("help: prefix it with an underscore: `_x`"));
+    assert!(report.contains("<details class=\"child\" open><summary>note: declared here</summary>"));
+    assert!(report.contains("<ul class=\"notes\">\n<li>note: no location</li>\n</ul>"));
+}
+
+#[test]
+fn empty_report() {
+    assert_eq!(with_html_report("", false, |_| {}), "");
+
+    let report = with_html_report("", true, |_| {});
+    assert!(report.contains("<h1>rustc: 0 errors, 0 warnings</h1>"));
+    assert!(report.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn characters_split_across_writes() {
+    let mut buf = HtmlBuffer::default();
+    let bytes = "é <".as_bytes();
+    buf.write_all(&bytes[..1]).unwrap();
+    buf.write_all(&bytes[1..]).unwrap();
+    buf.set_color(ColorSpec::new().set_bold(true)).unwrap();
+    buf.write_all(b"x").unwrap();
+    buf.reset().unwrap();
+
+    let mut inner = buf.0.lock().unwrap();
+    inner.push_text();
+    assert_eq!(inner.html, "é &lt;<span class=\"bold\">x</span>");
+}
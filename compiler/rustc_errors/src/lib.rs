@@ -60,6 +60,7 @@
 pub use termcolor::{Color, ColorSpec, WriteColor};
 
 use emitter::{is_case_difference, DynEmitter, Emitter};
+use lint_baseline::LintBaseline;
 use registry::Registry;
 use rustc_data_structures::fx::{FxHashSet, FxIndexMap, FxIndexSet};
 use rustc_data_structures::stable_hasher::{Hash128, StableHasher};
@@ -82,15 +83,20 @@
 use Level::*;
 
 pub mod annotate_snippet_emitter_writer;
+pub mod apply_suggestions;
 pub mod codes;
 mod diagnostic;
 mod diagnostic_impls;
 pub mod emitter;
 pub mod error;
+pub mod grouping;
+pub mod html;
 pub mod json;
+pub mod lint_baseline;
 mod lock;
 pub mod markdown;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 #[cfg(test)]
@@ -475,6 +481,14 @@ struct DiagCtxtInner {
 
     future_breakage_diagnostics: Vec<DiagInner>,
 
+    /// Machine-applicable suggestions of all emitted diagnostics, collected when
+    /// [`DiagCtxtFlags::collect_machine_applicable_suggestions`] is set.
+    machine_applicable_suggestions: Vec<CodeSuggestion>,
+
+    /// The lint warnings to suppress, and the ones recorded for a new baseline.
+    /// (rustc: see `--lint-baseline` and `--write-lint-baseline`)
+    lint_baseline: Option<LintBaseline>,
+
     /// The [`Self::unstable_expect_diagnostics`] should be empty when this struct is
     /// dropped. However, it can have values if the compilation is stopped early
     /// or is only partially executed. To avoid ICEs, like in rust#94953 we only
@@ -551,6 +565,10 @@ pub struct DiagCtxtFlags {
     pub deduplicate_diagnostics: bool,
     /// Track where errors are created. Enabled with `-Ztrack-diagnostics`.
     pub track_diagnostics: bool,
+    /// Keep the machine-applicable suggestions of emitted diagnostics around,
+    /// so they can be applied to the source files afterwards.
+    /// (rustc: see `-Z apply-suggestions`)
+    pub collect_machine_applicable_suggestions: bool,
 }
 
 impl Drop for DiagCtxtInner {
@@ -605,6 +623,11 @@ pub fn with_ice_file(mut self, ice_file: PathBuf) -> Self {
         self
     }
 
+    pub fn with_lint_baseline(mut self, lint_baseline: LintBaseline) -> Self {
+        self.inner.get_mut().lint_baseline = Some(lint_baseline);
+        self
+    }
+
     pub fn new(emitter: Box<DynEmitter>) -> Self {
         Self {
             inner: Lock::new(DiagCtxtInner {
@@ -623,6 +646,8 @@ pub fn new(emitter: Box<DynEmitter>) -> Self {
                 emitted_diagnostics: Default::default(),
                 stashed_diagnostics: Default::default(),
                 future_breakage_diagnostics: Vec::new(),
+                machine_applicable_suggestions: Vec::new(),
+                lint_baseline: None,
                 check_unstable_expect_diagnostics: false,
                 unstable_expect_diagnostics: Vec::new(),
                 fulfilled_expectations: Default::default(),
@@ -683,6 +708,8 @@ pub fn reset_err_count(&self) {
             emitted_diagnostics,
             stashed_diagnostics,
             future_breakage_diagnostics,
+            machine_applicable_suggestions,
+            lint_baseline: _,
             check_unstable_expect_diagnostics,
             unstable_expect_diagnostics,
             fulfilled_expectations,
@@ -704,6 +731,7 @@ pub fn reset_err_count(&self) {
         *emitted_diagnostics = Default::default();
         *stashed_diagnostics = Default::default();
         *future_breakage_diagnostics = Default::default();
+        *machine_applicable_suggestions = Default::default();
         *check_unstable_expect_diagnostics = false;
         *unstable_expect_diagnostics = Default::default();
         *fulfilled_expectations = Default::default();
@@ -990,6 +1018,18 @@ pub fn emit_future_breakage_report(&self) {
         }
     }
 
+    /// Takes the machine-applicable suggestions collected so far. Only returns
+    /// anything if [`DiagCtxtFlags::collect_machine_applicable_suggestions`] is set.
+    pub fn take_machine_applicable_suggestions(&self) -> Vec<CodeSuggestion> {
+        std::mem::take(&mut self.inner.borrow_mut().machine_applicable_suggestions)
+    }
+
+    /// Returns the lint warnings emitted so far as the contents of a baseline file, if a
+    /// baseline is being recorded.
+    pub fn recorded_lint_baseline(&self) -> Option<String> {
+        self.inner.borrow().lint_baseline.as_ref()?.to_json()
+    }
+
     pub fn emit_unused_externs(
         &self,
         lint_level: rustc_lint_defs::Level,
@@ -1388,6 +1428,17 @@ fn emit_diagnostic(&mut self, mut diagnostic: DiagInner) -> Option<ErrorGuarante
             self.future_breakage_diagnostics.push(diagnostic.clone());
         }
 
+        if let Some(lint_baseline) = &mut self.lint_baseline
+            && lint_baseline.check(&diagnostic)
+        {
+            // Like for expected lints, this keeps the `must_produce_diag` check from firing.
+            self.suppressed_expected_diag = true;
+            // The diagnostic still has to be tracked, so that it is replayed in later
+            // incremental sessions, which may use a different baseline.
+            (*TRACK_DIAGNOSTIC)(diagnostic, &mut |_| {});
+            return None;
+        }
+
         // Note that because this comes before the `match` below,
         // `-Zeagerly-emit-delayed-bugs` continues to work even after we've
         // issued an error and stopped recording new delayed bugs.
@@ -1477,6 +1528,17 @@ fn emit_diagnostic(&mut self, mut diagnostic: DiagInner) -> Option<ErrorGuarante
                 }
                 self.has_printed = true;
 
+                if self.flags.collect_machine_applicable_suggestions {
+                    self.machine_applicable_suggestions.extend(
+                        diagnostic
+                            .suggestions
+                            .iter()
+                            .flatten()
+                            .filter(|sugg| sugg.applicability == Applicability::MachineApplicable)
+                            .cloned(),
+                    );
+                }
+
                 self.emitter.emit_diagnostic(diagnostic);
             }
 
@@ -0,0 +1,153 @@
+//! Lint baselines, which suppress the lint warnings that a crate already had when the baseline
+//! was recorded.
+//!
+//! This allows enabling a stricter lint on a large codebase without fixing all existing
+//! occurrences first: a baseline is recorded once with `--write-lint-baseline`, and later
+//! compilations passing `--lint-baseline` only report warnings that are not in it.
+//!
+//! A warning is identified by the name of its lint, the file of its primary span, and the source
+//! snippet of that span with all whitespace collapsed. Line numbers are deliberately left out, so
+//! that warnings don't reappear when unrelated code above them changes. The baseline counts how
+//! often each warning occurred, so a new occurrence of a snippet that was already warned about
+//! elsewhere in the same file is still reported.
+//!
+//! Only lints at the `warn` level are recorded and suppressed. Lints that are denied, e.g. with
+//! `#![deny(...)]` or `-D warnings`, still fail the compilation, and lints at the `force-warn`
+//! level are always reported.
+
+use crate::{DiagInner, Level};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::Lrc;
+use rustc_span::source_map::SourceMap;
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// The version of the baseline file format.
+const VERSION: u32 = 1;
+
+/// The maximum number of characters of a snippet that is stored. Lints on whole items would
+/// otherwise copy large parts of the source into the baseline.
+const MAX_SNIPPET_LEN: usize = 120;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BaselineFile {
+    version: u32,
+    warnings: Vec<BaselineWarning>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BaselineWarning {
+    lint: String,
+    file: String,
+    snippet: String,
+    count: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct WarningKey {
+    file: String,
+    lint: String,
+    snippet: String,
+}
+
+/// The warnings to suppress and, if a new baseline is being recorded, the warnings seen so far.
+pub struct LintBaseline {
+    sm: Lrc<SourceMap>,
+    /// The warnings of the baseline, with the number of occurrences that are still suppressed.
+    suppressed: FxHashMap<WarningKey, usize>,
+    /// All lint warnings emitted so far, including suppressed ones, if recording.
+    recorded: Option<FxHashMap<WarningKey, usize>>,
+}
+
+impl LintBaseline {
+    pub fn new(sm: Lrc<SourceMap>) -> LintBaseline {
+        LintBaseline { sm, suppressed: Default::default(), recorded: None }
+    }
+
+    /// Suppresses the warnings in `json`, which holds the contents of a baseline file.
+    pub fn suppress(mut self, json: &str) -> Result<LintBaseline, String> {
+        let file: BaselineFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if file.version != VERSION {
+            return Err(format!("unsupported baseline version {}", file.version));
+        }
+        for BaselineWarning { lint, file, snippet, count } in file.warnings {
+            *self.suppressed.entry(WarningKey { file, lint, snippet }).or_default() += count;
+        }
+        Ok(self)
+    }
+
+    /// Records all lint warnings, so that they can be written out as a new baseline.
+    pub fn record(mut self) -> LintBaseline {
+        self.recorded = Some(Default::default());
+        self
+    }
+
+    /// Returns the recorded warnings as the contents of a baseline file, if recording.
+    pub fn to_json(&self) -> Option<String> {
+        let recorded = self.recorded.as_ref()?;
+        let mut warnings: Vec<_> = recorded.iter().collect();
+        warnings.sort();
+        let warnings = warnings
+            .into_iter()
+            .map(|(WarningKey { file, lint, snippet }, &count)| BaselineWarning {
+                lint: lint.clone(),
+                file: file.clone(),
+                snippet: snippet.clone(),
+                count,
+            })
+            .collect();
+        let file = BaselineFile { version: VERSION, warnings };
+        Some(serde_json::to_string_pretty(&file).unwrap() + "\n")
+    }
+
+    /// Records `diag` if it is a lint warning, and returns whether it is in the baseline and
+    /// should not be emitted.
+    pub(crate) fn check(&mut self, diag: &DiagInner) -> bool {
+        if diag.level != Level::Warning {
+            return false;
+        }
+        let Some(key) = self.key(diag) else {
+            return false;
+        };
+        if let Some(recorded) = &mut self.recorded {
+            *recorded.entry(key.clone()).or_default() += 1;
+        }
+        match self.suppressed.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn key(&self, diag: &DiagInner) -> Option<WarningKey> {
+        let lint = diag.is_lint.as_ref()?.name.clone();
+        let (file, snippet) = match diag.span.primary_span() {
+            Some(span) if !span.is_dummy() => {
+                let file = self.sm.lookup_source_file(span.lo());
+                let snippet = self.sm.span_to_snippet(span).unwrap_or_default();
+                (self.sm.filename_for_diagnostics(&file.name).to_string(), normalize(&snippet))
+            }
+            _ => (String::new(), String::new()),
+        };
+        Some(WarningKey { file, lint, snippet })
+    }
+}
+
+/// Collapses all whitespace in `snippet` into single spaces, and shortens it to
+/// [`MAX_SNIPPET_LEN`] characters.
+fn normalize(snippet: &str) -> String {
+    let mut normalized = String::new();
+    for word in snippet.split_whitespace() {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+    normalized.chars().take(MAX_SNIPPET_LEN).collect()
+}
@@ -0,0 +1,159 @@
+use super::*;
+
+use crate::emitter::Emitter;
+use crate::translation::Translate;
+use crate::{Diag, DiagCtxt, FluentBundle, LazyFallbackBundle};
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::{BytePos, Span};
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Records the start of the primary span of every emitted diagnostic.
+struct SpanEmitter {
+    fallback_bundle: LazyFallbackBundle,
+    emitted: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Translate for SpanEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        None
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for SpanEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        self.emitted.lock().unwrap().push(diag.span.primary_span().unwrap().lo().0);
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        None
+    }
+}
+
+/// Runs `f` against a `DiagCtxt` using `baseline`, and returns the positions of the emitted
+/// diagnostics and the recorded baseline.
+fn with_baseline(
+    code: &str,
+    baseline: impl FnOnce(LintBaseline) -> LintBaseline,
+    f: impl FnOnce(&DiagCtxt),
+) -> (Vec<u32>, Option<String>) {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let emitted = Arc::new(Mutex::new(Vec::new()));
+        let emitter = SpanEmitter { fallback_bundle, emitted: emitted.clone() };
+        let dcx =
+            DiagCtxt::new(Box::new(emitter)).with_lint_baseline(baseline(LintBaseline::new(sm)));
+        f(&dcx);
+
+        let emitted = emitted.lock().unwrap().clone();
+        (emitted, dcx.recorded_lint_baseline())
+    })
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+fn lint(dcx: &DiagCtxt, name: &str, span: Span) {
+    let mut diag = dcx.struct_span_warn(span, "lint");
+    diag.is_lint(name.to_owned(), false);
+    diag.emit();
+}
+
+const CODE: &str = "unsafe fn f(p: *const u8) {\n    p.read();\n    p  .read();\n    p.read();\n}";
+
+#[test]
+fn record() {
+    let (emitted, json) = with_baseline(CODE, LintBaseline::record, |dcx| {
+        lint(dcx, "unsafe_op_in_unsafe_fn", span(32, 40));
+        lint(dcx, "unsafe_op_in_unsafe_fn", span(46, 56));
+        lint(dcx, "dead_code", span(10, 11));
+        // Not a lint.
+        dcx.struct_span_warn(span(62, 70), "warning").emit();
+    });
+
+    assert_eq!(emitted, [32, 46, 10, 62]);
+    assert_eq!(
+        json.unwrap(),
+        r#"{
+  "version": 1,
+  "warnings": [
+    {
+      "lint": "dead_code",
+      "file": "test.rs",
+      "snippet": "f",
+      "count": 1
+    },
+    {
+      "lint": "unsafe_op_in_unsafe_fn",
+      "file": "test.rs",
+      "snippet": "p .read()",
+      "count": 1
+    },
+    {
+      "lint": "unsafe_op_in_unsafe_fn",
+      "file": "test.rs",
+      "snippet": "p.read()",
+      "count": 1
+    }
+  ]
+}
+"#
+    );
+}
+
+#[test]
+fn suppress() {
+    let baseline = r#"{"version": 1, "warnings": [
+        {"lint": "unsafe_op_in_unsafe_fn", "file": "test.rs", "snippet": "p.read()", "count": 1},
+        {"lint": "unsafe_op_in_unsafe_fn", "file": "test.rs", "snippet": "p .read()", "count": 1},
+        {"lint": "dead_code", "file": "other.rs", "snippet": "f", "count": 1},
+        {"lint": "missing_docs", "file": "test.rs", "snippet": "f", "count": 1}
+    ]}"#;
+    let (emitted, json) = with_baseline(
+        CODE,
+        |b| b.suppress(baseline).unwrap(),
+        |dcx| {
+            lint(dcx, "unsafe_op_in_unsafe_fn", span(32, 40));
+            // Only one occurrence is in the baseline.
+            lint(dcx, "unsafe_op_in_unsafe_fn", span(62, 70));
+            // Whitespace is normalized.
+            lint(dcx, "unsafe_op_in_unsafe_fn", span(46, 56));
+            // Different file.
+            lint(dcx, "dead_code", span(10, 11));
+            // Lints turned into errors are never suppressed.
+            let mut err = Diag::<()>::new(dcx, Level::Error, "lint");
+            err.span(span(10, 11));
+            err.is_lint("missing_docs".to_owned(), false);
+            err.emit();
+        },
+    );
+
+    assert_eq!(emitted, [62, 10, 10]);
+    assert_eq!(json, None);
+}
+
+#[test]
+fn invalid_baseline() {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        let err = LintBaseline::new(sm.clone()).suppress(r#"{"version": 2, "warnings": []}"#);
+        assert_eq!(err.err().unwrap(), "unsupported baseline version 2");
+        assert!(LintBaseline::new(sm).suppress("[]").is_err());
+    })
+}
+
+#[test]
+fn normalized_snippets() {
+    assert_eq!(normalize("  a\n\t  b  "), "a b");
+    assert_eq!(normalize(&"x".repeat(200)).len(), MAX_SNIPPET_LEN);
+}
@@ -0,0 +1,434 @@
+//! A SARIF emitter for errors.
+//!
+//! Unlike the JSON emitter, which streams one record per diagnostic, a [SARIF 2.1.0] log is a
+//! single document. The emitter therefore collects results as diagnostics are emitted and
+//! writes the whole log out when it is dropped.
+//!
+//! Error codes and lint names become rule ids, suggestions become fixes and the spans of child
+//! notes are reported as related locations.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::translation::{to_fluent_args, Translate};
+use crate::{
+    diagnostic::IsLint, CodeSuggestion, DiagInner, FluentBundle, LazyFallbackBundle, Level,
+    MultiSpan, Subdiag,
+};
+use derive_setters::Setters;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::{IntoDynSyncSend, Lrc};
+use rustc_error_messages::FluentArgs;
+use rustc_lint_defs::Applicability;
+use rustc_span::source_map::SourceMap;
+use rustc_span::Span;
+use serde::Serialize;
+use std::error::Report;
+use std::io::{self, Write};
+
+#[cfg(test)]
+mod tests;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Setters)]
+pub struct SarifEmitter {
+    #[setters(skip)]
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    registry: Option<Registry>,
+    #[setters(skip)]
+    sm: Lrc<SourceMap>,
+    fluent_bundle: Option<Lrc<FluentBundle>>,
+    #[setters(skip)]
+    fallback_bundle: LazyFallbackBundle,
+    #[setters(skip)]
+    pretty: bool,
+    /// Write a log even if no results were collected. Emitters that are only used for early
+    /// errors leave this unset, so they don't produce a second, empty log.
+    emit_empty_log: bool,
+    #[setters(skip)]
+    rules: FxIndexMap<String, ReportingDescriptor>,
+    #[setters(skip)]
+    results: Vec<SarifResult>,
+}
+
+impl SarifEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Lrc<SourceMap>,
+        fallback_bundle: LazyFallbackBundle,
+        pretty: bool,
+    ) -> SarifEmitter {
+        SarifEmitter {
+            dst: IntoDynSyncSend(dst),
+            registry: None,
+            sm,
+            fluent_bundle: None,
+            fallback_bundle,
+            pretty,
+            emit_empty_log: false,
+            rules: FxIndexMap::default(),
+            results: Vec::new(),
+        }
+    }
+
+    fn emit_log(&mut self) -> io::Result<()> {
+        let log = SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "rustc",
+                        information_uri: "https://www.rust-lang.org/",
+                        rules: self.rules.values().collect(),
+                    },
+                },
+                // Columns computed by the source map count `char`s, not UTF-16 code units.
+                column_kind: "unicodeCodePoints",
+                results: &self.results,
+            }],
+        };
+        if self.pretty {
+            serde_json::to_writer_pretty(&mut *self.dst, &log)?
+        } else {
+            serde_json::to_writer(&mut *self.dst, &log)?
+        };
+        self.dst.write_all(b"\n")?;
+        self.dst.flush()
+    }
+
+    /// Returns the index of the rule for the given diagnostic, registering it if necessary.
+    fn rule_index(&mut self, diag: &DiagInner) -> Option<(String, usize)> {
+        let (id, full_description, help_uri) = if let Some(code) = diag.code {
+            let explanation = self
+                .registry
+                .as_ref()
+                .and_then(|registry| registry.try_find_description(code).ok())
+                .map(|text| Message { text: text.to_owned() });
+            let help_uri = format!("https://doc.rust-lang.org/error_codes/{code}.html");
+            (code.to_string(), explanation, Some(help_uri))
+        } else if let Some(IsLint { name, .. }) = &diag.is_lint {
+            (name.clone(), None, None)
+        } else {
+            return None;
+        };
+
+        let entry = self.rules.entry(id.clone());
+        let index = entry.index();
+        entry.or_insert_with(|| ReportingDescriptor { id: id.clone(), full_description, help_uri });
+        Some((id, index))
+    }
+
+    fn location(&self, span: Span, message: Option<String>) -> Option<Location> {
+        let physical_location = self.physical_location(span)?;
+        Some(Location { physical_location, message: message.map(|text| Message { text }) })
+    }
+
+    fn physical_location(&self, span: Span) -> Option<PhysicalLocation> {
+        let region = self.region(span)?;
+        let file = self.sm.lookup_source_file(span.lo());
+        let uri = self.sm.filename_for_diagnostics(&file.name).to_string().replace('\\', "/");
+        Some(PhysicalLocation { artifact_location: ArtifactLocation { uri }, region })
+    }
+
+    fn region(&self, span: Span) -> Option<Region> {
+        if span.is_dummy() {
+            return None;
+        }
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        let byte_offset = start.file.original_relative_byte_pos(span.lo()).0;
+        let byte_end = start.file.original_relative_byte_pos(span.hi()).0;
+        Some(Region {
+            start_line: start.line,
+            start_column: start.col.0 + 1,
+            end_line: end.line,
+            end_column: end.col.0 + 1,
+            byte_offset,
+            byte_length: byte_end - byte_offset,
+        })
+    }
+
+    /// Turns the labels of a multispan into locations. Primary spans are returned first, the
+    /// remaining labelled spans second.
+    fn locations(&self, msp: &MultiSpan, args: &FluentArgs<'_>) -> (Vec<Location>, Vec<Location>) {
+        let mut primary = vec![];
+        let mut secondary = vec![];
+        for span_label in msp.span_labels() {
+            let label = span_label
+                .label
+                .as_ref()
+                .map(|label| self.translate_message(label, args).map_err(Report::new).unwrap())
+                .map(|label| label.to_string());
+            if let Some(location) = self.location(span_label.span, label) {
+                if span_label.is_primary {
+                    primary.push(location);
+                } else {
+                    secondary.push(location);
+                }
+            }
+        }
+        (primary, secondary)
+    }
+
+    fn related_locations(&self, subdiag: &Subdiag, args: &FluentArgs<'_>) -> Vec<Location> {
+        let message = format!(
+            "{}: {}",
+            subdiag.level.to_str(),
+            self.translate_messages(&subdiag.messages, args)
+        );
+        subdiag
+            .span
+            .primary_spans()
+            .iter()
+            .filter_map(|&span| self.location(span, Some(message.clone())))
+            .collect()
+    }
+
+    fn fixes(&self, suggestion: &CodeSuggestion, args: &FluentArgs<'_>) -> Vec<Fix> {
+        let description =
+            self.translate_message(&suggestion.msg, args).map_err(Report::new).unwrap().to_string();
+        suggestion
+            .substitutions
+            .iter()
+            .filter_map(|substitution| {
+                let mut changes: FxIndexMap<String, Vec<Replacement>> = FxIndexMap::default();
+                for part in &substitution.parts {
+                    let PhysicalLocation { artifact_location, region } =
+                        self.physical_location(part.span)?;
+                    changes.entry(artifact_location.uri).or_default().push(Replacement {
+                        deleted_region: region,
+                        inserted_content: ArtifactContent { text: part.snippet.clone() },
+                    });
+                }
+                Some(Fix {
+                    description: Message { text: description.clone() },
+                    artifact_changes: changes
+                        .into_iter()
+                        .map(|(uri, replacements)| ArtifactChange {
+                            artifact_location: ArtifactLocation { uri },
+                            replacements,
+                        })
+                        .collect(),
+                    properties: FixProperties { applicability: suggestion.applicability },
+                })
+            })
+            .collect()
+    }
+}
+
+impl Translate for SarifEmitter {
+    fn fluent_bundle(&self) -> Option<&Lrc<FluentBundle>> {
+        self.fluent_bundle.as_ref()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: DiagInner) {
+        let level = match diag.level {
+            Level::Bug | Level::DelayedBug | Level::Fatal | Level::Error => "error",
+            Level::ForceWarning(_) | Level::Warning => "warning",
+            Level::Note | Level::OnceNote | Level::Help | Level::OnceHelp => "note",
+            // Summaries like "aborting due to N previous errors" are not results.
+            Level::FailureNote => return,
+            Level::Allow | Level::Expect(_) => unreachable!(),
+        };
+
+        let args = to_fluent_args(diag.args.iter());
+        let mut text = self.translate_messages(&diag.messages, &args).into_owned();
+        let (locations, mut related_locations) = self.locations(&diag.span, &args);
+
+        for child in &diag.children {
+            if child.span.primary_spans().is_empty() {
+                // Children without a location have nowhere else to go, so they are
+                // appended to the message, as the human emitter would show them.
+                text.push_str(&format!(
+                    "\n{}: {}",
+                    child.level.to_str(),
+                    self.translate_messages(&child.messages, &args)
+                ));
+            } else {
+                related_locations.extend(self.related_locations(child, &args));
+            }
+        }
+
+        let fixes = diag
+            .suggestions
+            .iter()
+            .flatten()
+            .flat_map(|suggestion| self.fixes(suggestion, &args))
+            .collect();
+
+        let rule = self.rule_index(&diag);
+        self.results.push(SarifResult {
+            rule_id: rule.as_ref().map(|(id, _)| id.clone()),
+            rule_index: rule.map(|(_, index)| index),
+            level,
+            message: Message { text },
+            locations,
+            related_locations,
+            fixes,
+        });
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+
+    fn should_show_explain(&self) -> bool {
+        // The explanations are attached to the rules instead.
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        if self.results.is_empty() && !self.emit_empty_log {
+            return;
+        }
+        if let Err(e) = self.emit_log() {
+            panic!("failed to print SARIF log: {e:?}");
+        }
+    }
+}
+
+// The following data types are provided just for serialisation. Field names follow the
+// SARIF 2.1.0 specification.
+
+#[derive(Serialize)]
+struct SarifLog<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+    tool: Tool<'a>,
+    column_kind: &'static str,
+    results: &'a [SarifResult],
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    driver: ToolComponent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent<'a> {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<&'a ReportingDescriptor>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportingDescriptor {
+    /// The error code (e.g. "E0308") or the lint name.
+    id: String,
+    /// The long-form explanation of an error code, if there is one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full_description: Option<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_index: Option<usize>,
+    /// "error", "warning" or "note".
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    /// Spans of child notes and secondary labels.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// 1-based.
+    start_line: usize,
+    /// 1-based, character offset.
+    start_column: usize,
+    end_line: usize,
+    /// 1-based, character offset one past the end of the region.
+    end_column: usize,
+    byte_offset: u32,
+    byte_length: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+    properties: FixProperties,
+}
+
+#[derive(Serialize)]
+struct FixProperties {
+    /// Whether tools may apply this fix without consulting the user.
+    applicability: Applicability,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: ArtifactContent,
+}
+
+#[derive(Serialize)]
+struct ArtifactContent {
+    text: String,
+}
@@ -0,0 +1,122 @@
+use super::*;
+
+use crate::{codes::*, DiagCtxt};
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::BytePos;
+
+use std::path::Path;
+use std::str;
+use std::sync::{Arc, Mutex};
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+/// Runs `f` against a `DiagCtxt` backed by a SARIF emitter and returns the resulting log.
+fn with_sarif_log(code: &str, emit_empty_log: bool, f: impl FnOnce(&DiagCtxt)) -> String {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let se = SarifEmitter::new(
+            Box::new(Shared { data: output.clone() }),
+            sm,
+            fallback_bundle,
+            false,
+        )
+        .emit_empty_log(emit_empty_log);
+
+        let dcx = DiagCtxt::new(Box::new(se));
+        f(&dcx);
+        // The log is only written once the emitter goes away.
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        str::from_utf8(&bytes).unwrap().to_owned()
+    })
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+#[test]
+fn result_with_rule_and_location() {
+    let log = with_sarif_log("fn main() { let x = 1 }", false, |dcx| {
+        dcx.struct_span_err(span(16, 17), "foo").with_code(E0308).emit();
+    });
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "E0308");
+
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "E0308");
+    assert_eq!(result["ruleIndex"], 0);
+    assert_eq!(result["level"], "error");
+    assert_eq!(result["message"]["text"], "foo");
+
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "test.rs");
+    assert_eq!(location["region"]["startLine"], 1);
+    assert_eq!(location["region"]["startColumn"], 17);
+    assert_eq!(location["region"]["endColumn"], 18);
+    assert_eq!(location["region"]["byteOffset"], 16);
+    assert_eq!(location["region"]["byteLength"], 1);
+}
+
+#[test]
+fn notes_and_suggestions() {
+    let log = with_sarif_log("fn main() {\n    let x = 1\n}", false, |dcx| {
+        dcx.struct_span_warn(span(20, 21), "unused variable")
+            .with_span_note(span(16, 21), "declared here")
+            .with_note("no location")
+            .with_span_suggestion(
+                span(20, 21),
+                "prefix it with an underscore",
+                "_x",
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    let result = &log["runs"][0]["results"][0];
+
+    assert_eq!(result["level"], "warning");
+    assert_eq!(result["message"]["text"], "unused variable\nnote: no location");
+
+    let related = &result["relatedLocations"][0];
+    assert_eq!(related["message"]["text"], "note: declared here");
+    assert_eq!(related["physicalLocation"]["region"]["startLine"], 2);
+    assert_eq!(related["physicalLocation"]["region"]["startColumn"], 5);
+
+    let fix = &result["fixes"][0];
+    assert_eq!(fix["description"]["text"], "prefix it with an underscore");
+    assert_eq!(fix["properties"]["applicability"], "MachineApplicable");
+    let replacement = &fix["artifactChanges"][0]["replacements"][0];
+    assert_eq!(replacement["insertedContent"]["text"], "_x");
+    assert_eq!(replacement["deletedRegion"]["byteOffset"], 20);
+}
+
+#[test]
+fn empty_log() {
+    assert_eq!(with_sarif_log("", false, |_| {}), "");
+
+    let log = with_sarif_log("", true, |_| {});
+    let log: serde_json::Value = serde_json::from_str(&log).unwrap();
+    assert_eq!(log["runs"][0]["results"], serde_json::json!([]));
+}
@@ -455,6 +455,10 @@ pub struct BuiltinAttribute {
         test_runner, CrateLevel, template!(List: "path"), ErrorFollowing, custom_test_frameworks,
         "custom test frameworks are an unstable feature",
     ),
+    gated!(
+        test_timeout, Normal, template!(NameValueStr: "seconds"), WarnFollowing,
+        @only_local: true, experimental!(test_timeout)
+    ),
     // RFC #1268
     gated!(
         marker, Normal, template!(Word), WarnFollowing, @only_local: true,
@@ -223,6 +223,8 @@ pub fn internal(&self, feature: Symbol) -> bool {
     (internal, rustc_attrs, "1.0.0", None),
     /// Allows using the `#[stable]` and `#[unstable]` attributes.
     (internal, staged_api, "1.0.0", None),
+    /// Allows using `#[test_timeout]` to kill a test that runs for too long.
+    (unstable, test_timeout, "CURRENT_RUSTC_VERSION", None),
     /// Added for testing unstable lints; perma-unstable.
     (internal, test_unstable_lint, "1.60.0", None),
     /// Use for stable + negative coherence and strict coherence depending on trait's
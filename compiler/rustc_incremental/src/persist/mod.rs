@@ -7,6 +7,7 @@
 mod file_format;
 mod fs;
 mod load;
+mod replay;
 mod save;
 mod work_product;
 
@@ -16,6 +17,7 @@
 pub use load::load_query_result_cache;
 pub use load::setup_dep_graph;
 pub use load::LoadResult;
+pub use replay::replay_side_effects;
 pub use save::save_dep_graph;
 pub use save::save_work_product_index;
 pub use work_product::copy_cgu_workproduct_to_incr_comp_cache_dir;
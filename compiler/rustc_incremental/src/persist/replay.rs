@@ -0,0 +1,47 @@
+//! Replaying the diagnostics of query results that were reused from the previous session.
+//!
+//! Diagnostics emitted by a query are stored in the query result cache as a side effect of
+//! its dep-node, and emitted again whenever that node is marked green. Nodes whose results
+//! were not needed in the current session are never marked at all, so their warnings are
+//! silently lost, even though nothing they depend on has changed.
+//!
+//! With `-Z replay-all-warnings`, all nodes of the previous session that have side effects
+//! and were left unmarked are marked green once compilation is done. Marking a node green
+//! emits its stored diagnostics and carries them over into the cache of the current session,
+//! so they are not lost in later sessions either. Nodes that can't be marked green depend on
+//! something that changed, and their diagnostics are dropped as they may be stale.
+
+use rustc_middle::dep_graph::DepContext;
+use rustc_middle::ty::TyCtxt;
+
+/// Emits the diagnostics of all query results of the previous session that are still valid,
+/// but were not replayed because they were not needed in the current session.
+pub fn replay_side_effects(tcx: TyCtxt<'_>) {
+    let sess = tcx.sess;
+    if sess.opts.incremental.is_none() || !sess.opts.unstable_opts.replay_all_warnings {
+        return;
+    }
+    // Marking nodes green can force queries, which isn't safe after an error, and a failed
+    // compilation doesn't need to report every warning anyway.
+    if sess.dcx().has_errors().is_some() {
+        return;
+    }
+    let Some(on_disk_cache) = &tcx.query_system.on_disk_cache else {
+        return;
+    };
+
+    sess.time("incr_comp_replay_side_effects", || {
+        for prev_index in on_disk_cache.prev_side_effect_nodes() {
+            let Some(dep_node) = tcx.dep_graph.uncolored_prev_node(prev_index) else {
+                continue;
+            };
+            // These are re-executed whenever they are needed, so they were either executed in
+            // this session or their results are not relevant anymore.
+            if tcx.is_eval_always(dep_node.kind) {
+                continue;
+            }
+            debug!("replaying side effects of {dep_node:?}");
+            tcx.try_mark_green(&dep_node);
+        }
+    });
+}
@@ -444,6 +444,13 @@ fn write_out_deps(tcx: TyCtxt<'_>, outputs: &OutputFilenames, out_filenames: &[P
             files.push(normalize_path(profile_sample.as_path().to_path_buf()));
         }
 
+        if let Some(ref lint_config) = sess.opts.lint_config {
+            files.push(normalize_path(lint_config.clone()));
+        }
+        if let Some(ref lint_baseline) = sess.opts.lint_baseline {
+            files.push(normalize_path(lint_baseline.clone()));
+        }
+
         // Debugger visualizer files
         for debugger_visualizer in tcx.debugger_visualizers(LOCAL_CRATE) {
             files.push(normalize_path(debugger_visualizer.path.clone().unwrap()));
@@ -821,7 +828,7 @@ fn analysis(tcx: TyCtxt<'_>, (): ()) -> Result<()> {
         let _ = tcx.all_diagnostic_items(());
     });
 
-    if sess.opts.unstable_opts.print_vtable_sizes {
+    if sess.opts.unstable_opts.print_vtable_sizes.is_some() {
         let traits = tcx.traits(LOCAL_CRATE);
 
         for &tr in traits {
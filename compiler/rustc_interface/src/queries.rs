@@ -318,6 +318,9 @@ pub fn enter<F, T>(&self, f: F) -> T
         // since that likely means there was a parse error.
         if let Some(Ok(gcx)) = &mut *queries.gcx.result.borrow_mut() {
             let gcx = gcx.get_mut();
+            // This may still mark queries as green, which can force other queries.
+            gcx.enter(rustc_incremental::replay_side_effects);
+
             // We assume that no queries are run past here. If there are new queries
             // after this point, they'll show up as "<unknown>" in self-profiling data.
             {
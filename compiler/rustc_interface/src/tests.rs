@@ -4,11 +4,12 @@
 use rustc_errors::{emitter::HumanReadableErrorType, registry, ColorConfig};
 use rustc_session::config::{
     build_configuration, build_session_options, rustc_optgroups, BranchProtection, CFGuard, Cfg,
-    CollapseMacroDebuginfo, DebugInfo, DumpMonoStatsFormat, ErrorOutputType, ExternEntry,
-    ExternLocation, Externs, FunctionReturn, InliningThreshold, Input, InstrumentCoverage,
-    InstrumentXRay, LinkSelfContained, LinkerPluginLto, LocationDetail, LtoCli, NextSolverConfig,
-    OomStrategy, Options, OutFileName, OutputType, OutputTypes, PAuthKey, PacRet, Passes, Polonius,
-    ProcMacroExecutionStrategy, Strip, SwitchWithOptPath, SymbolManglingVersion, WasiExecModel,
+    CodeStatsFormat, CollapseMacroDebuginfo, DebugInfo, DumpMonoStatsFormat, ErrorOutputType,
+    ExternEntry, ExternLocation, Externs, FunctionReturn, InliningThreshold, Input,
+    InstrumentCoverage, InstrumentXRay, LinkSelfContained, LinkerPluginLto, LocationDetail, LtoCli,
+    NextSolverConfig, OomStrategy, Options, OutFileName, OutputType, OutputTypes, PAuthKey, PacRet,
+    Passes, Polonius, ProcMacroExecutionStrategy, Strip, SwitchWithOptPath, SymbolManglingVersion,
+    WasiExecModel,
 };
 use rustc_session::lint::Level;
 use rustc_session::search_paths::SearchPath;
@@ -295,6 +296,55 @@ fn test_lint_cap_hash_different() {
     assert_non_crate_hash_different(&v2, &v3);
 }
 
+#[test]
+fn test_lint_config_file() {
+    let dir = std::env::temp_dir().join(format!("rustc-lint-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let toml = dir.join("lints.toml");
+    std::fs::write(
+        &toml,
+        "[lints.rust]\n\
+        unsafe-op-in-unsafe-fn = \"deny\" # comment\n\
+        unused = { level = 'warn', priority = -1 }\n\
+        [lints.clippy]\n\
+        pedantic = { level = \"forbid\", priority = 2 }\n",
+    )
+    .unwrap();
+    let json = dir.join("lints.json");
+    std::fs::write(&json, r#"{"lints": {"rust": {"dead_code": "force-warn"}}}"#).unwrap();
+
+    let lint_opts = |path: &Path| {
+        rustc_span::create_default_session_globals_then(|| {
+            let args = ["-Zunstable-options", "--lint-config", path.to_str().unwrap(), "-Aunused"];
+            let matches = optgroups().parse(args.map(String::from)).unwrap();
+            let mut early_dcx = EarlyDiagCtxt::new(ErrorOutputType::default());
+            let opts = build_session_options(&mut early_dcx, &matches);
+            assert_eq!(opts.lint_config.as_deref(), Some(path));
+            opts.lint_opts
+        })
+    };
+
+    // Entries are ordered by priority and then by name, before the command line flags.
+    assert_eq!(
+        lint_opts(&toml),
+        vec![
+            ("unused".to_string(), Level::Warn),
+            ("unsafe_op_in_unsafe_fn".to_string(), Level::Deny),
+            ("clippy::pedantic".to_string(), Level::Forbid),
+            ("unused".to_string(), Level::Allow),
+        ]
+    );
+    assert_eq!(
+        lint_opts(&json),
+        vec![
+            ("dead_code".to_string(), Level::ForceWarn(None)),
+            ("unused".to_string(), Level::Allow),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn test_search_paths_tracking_hash_different_order() {
     let mut v1 = Options::default();
@@ -658,6 +708,7 @@ macro_rules! untracked {
 
     // Make sure that changing an [UNTRACKED] option leaves the hash unchanged.
     // tidy-alphabetical-start
+    untracked!(apply_suggestions, NonZero::new(2));
     untracked!(assert_incr_state, Some(String::from("loaded")));
     untracked!(deduplicate_diagnostics, false);
     untracked!(dump_dep_graph, true);
@@ -671,6 +722,7 @@ macro_rules! untracked {
     untracked!(dylib_lto, true);
     untracked!(emit_stack_sizes, true);
     untracked!(future_incompat_test, true);
+    untracked!(group_diagnostics, true);
     untracked!(hir_stats, true);
     untracked!(identify_regions, true);
     untracked!(incremental_info, true);
@@ -692,11 +744,13 @@ macro_rules! untracked {
     untracked!(print_codegen_stats, true);
     untracked!(print_llvm_passes, true);
     untracked!(print_mono_items, Some(String::from("abc")));
-    untracked!(print_type_sizes, true);
+    untracked!(print_type_sizes, Some(CodeStatsFormat::Json));
+    untracked!(print_vtable_sizes, Some(CodeStatsFormat::Text));
     untracked!(proc_macro_backtrace, true);
     untracked!(proc_macro_execution_strategy, ProcMacroExecutionStrategy::CrossThread);
     untracked!(profile_closures, true);
     untracked!(query_dep_graph, true);
+    untracked!(replay_all_warnings, true);
     untracked!(self_profile, SwitchWithOptPath::Enabled(None));
     untracked!(self_profile_events, Some(vec![String::new()]));
     untracked!(shell_argfiles, true);
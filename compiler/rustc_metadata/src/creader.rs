@@ -17,11 +17,12 @@
 use rustc_hir::definitions::Definitions;
 use rustc_index::IndexVec;
 use rustc_middle::ty::TyCtxt;
-use rustc_session::config::{self, CrateType, ExternLocation};
+use rustc_session::config::{self, CrateType, ExternLocation, PrintKind};
 use rustc_session::cstore::{CrateDepKind, CrateSource, ExternCrate, ExternCrateSource};
 use rustc_session::lint;
 use rustc_session::output::validate_crate_name;
 use rustc_session::search_paths::PathKind;
+use rustc_session::Session;
 use rustc_span::edition::Edition;
 use rustc_span::symbol::{sym, Symbol};
 use rustc_span::{Span, DUMMY_SP};
@@ -29,8 +30,9 @@
 
 use proc_macro::bridge::client::ProcMacro;
 use std::error::Error;
+use std::fmt::Write;
 use std::ops::Fn;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::{cmp, iter};
 
@@ -66,6 +68,24 @@ pub struct CStore {
 
     /// Unused externs of the crate
     unused_externs: Vec<Symbol>,
+
+    /// Every attempt to resolve a crate name, in order, if `--print=extern-resolution`
+    /// was requested.
+    extern_resolutions: Vec<ExternResolution>,
+}
+
+/// An attempt of the crate loader to resolve a crate name.
+struct ExternResolution {
+    name: Symbol,
+    /// For transitive dependencies, the crate that pulled them in and the hash of the
+    /// dependency recorded in its metadata.
+    required_by: Option<(Symbol, Svh)>,
+    /// The crate the name resolved to, `None` if resolution failed.
+    cnum: Option<CrateNum>,
+    /// Whether an already loaded crate was reused instead of searching for the crate.
+    reused: bool,
+    /// Files that were looked at and rejected by the locator, with the reason why.
+    rejected: Vec<(PathBuf, String)>,
 }
 
 impl std::fmt::Debug for CStore {
@@ -308,8 +328,82 @@ pub fn new(
             has_alloc_error_handler: false,
             stable_crate_ids,
             unused_externs: Vec::new(),
+            extern_resolutions: Vec::new(),
         }
     }
+
+    /// Describes the crates that were loaded, for `--print=crate-graph`.
+    pub fn crate_graph_info(&self, sess: &Session) -> String {
+        let mut info = String::new();
+        for (cnum, data) in self.iter_crate_data() {
+            writeln!(info, "crate #{cnum} `{}`", data.name()).unwrap();
+            writeln!(info, "  hash: {}", data.hash()).unwrap();
+            writeln!(info, "  dep kind: {}", dep_kind_str(data.dep_kind())).unwrap();
+            write_crate_source(&mut info, sess, data.source());
+            let deps = data.dependencies().filter(|&dep| dep != cnum);
+            let deps: Vec<_> =
+                deps.map(|dep| format!("`{}` (#{dep})", self.get_crate_data(dep).name())).collect();
+            if !deps.is_empty() {
+                writeln!(info, "  dependencies: {}", deps.join(", ")).unwrap();
+            }
+        }
+        info
+    }
+
+    /// Describes how each crate name was resolved, for `--print=extern-resolution`.
+    pub fn extern_resolution_info(&self, sess: &Session) -> String {
+        let mut info = String::new();
+        for resolution in &self.extern_resolutions {
+            let name = resolution.name;
+            if let Some((root, hash)) = resolution.required_by {
+                writeln!(info, "`{name}` with hash {hash}, which `{root}` depends on").unwrap();
+            } else {
+                writeln!(info, "`{name}`").unwrap();
+            }
+            for (path, reason) in &resolution.rejected {
+                writeln!(info, "  rejected {}: {reason}", path.display()).unwrap();
+            }
+            match resolution.cnum {
+                Some(cnum) if resolution.reused => {
+                    writeln!(info, "  reused crate #{cnum}, which was loaded before").unwrap();
+                }
+                Some(cnum) => {
+                    let data = self.get_crate_data(cnum);
+                    writeln!(info, "  loaded as crate #{cnum} with hash {}", data.hash()).unwrap();
+                    write_crate_source(&mut info, sess, data.source());
+                }
+                None => info.push_str("  not resolved\n"),
+            }
+        }
+        info
+    }
+}
+
+fn dep_kind_str(dep_kind: CrateDepKind) -> &'static str {
+    match dep_kind {
+        CrateDepKind::MacrosOnly => "macros-only",
+        CrateDepKind::Implicit => "implicit",
+        CrateDepKind::Explicit => "explicit",
+    }
+}
+
+/// Writes out the files a crate was loaded from, and which option made the compiler look there.
+fn write_crate_source(info: &mut String, sess: &Session, source: &CrateSource) {
+    let sysroot = try_canonicalize(&sess.sysroot).unwrap_or_else(|_| sess.sysroot.clone());
+    let CrateSource { dylib, rlib, rmeta } = source;
+    for (flavor, file) in [("dylib", dylib), ("rlib", rlib), ("rmeta", rmeta)] {
+        let Some((path, kind)) = file else { continue };
+        let origin = match kind {
+            PathKind::ExternFlag => "--extern",
+            _ if path.starts_with(&sysroot) => "sysroot",
+            PathKind::Native => "-L native",
+            PathKind::Crate => "-L crate",
+            PathKind::Dependency => "-L dependency",
+            PathKind::Framework => "-L framework",
+            PathKind::All => "-L",
+        };
+        writeln!(info, "  {flavor}: {} (found through {origin})", path.display()).unwrap();
+    }
 }
 
 impl<'a, 'tcx> CrateLoader<'a, 'tcx> {
@@ -547,6 +641,27 @@ fn resolve_crate(
         }
     }
 
+    /// Starts an entry for `--print=extern-resolution`, if it was requested, and returns
+    /// its index.
+    fn log_extern_resolution(
+        &mut self,
+        name: Symbol,
+        required_by: Option<(Symbol, Svh)>,
+    ) -> Option<usize> {
+        if !self.sess.opts.prints.iter().any(|p| p.kind == PrintKind::ExternResolution) {
+            return None;
+        }
+        let resolutions = &mut self.cstore.extern_resolutions;
+        resolutions.push(ExternResolution {
+            name,
+            required_by,
+            cnum: None,
+            reused: false,
+            rejected: Vec::new(),
+        });
+        Some(resolutions.len() - 1)
+    }
+
     fn maybe_resolve_crate<'b>(
         &'b mut self,
         name: Symbol,
@@ -568,6 +683,10 @@ fn maybe_resolve_crate<'b>(
             ),
             None => (None, None, None, None, PathKind::Crate, None),
         };
+        // The entry is pushed before loading, so that it comes before the entries of the
+        // dependencies of the crate. It stays unresolved if we bail out with an error.
+        let required_by = root.zip(hash).map(|(root, hash)| (root.name, hash));
+        let log_index = self.log_extern_resolution(name, required_by);
         let result = if let Some(cnum) = self.existing_match(name, hash, path_kind) {
             (LoadResult::Previous(cnum), None)
         } else {
@@ -585,19 +704,25 @@ fn maybe_resolve_crate<'b>(
                 path_kind,
             );
 
-            match self.load(&mut locator)? {
-                Some(res) => (res, None),
-                None => {
+            let result = match self.load(&mut locator) {
+                Ok(Some(res)) => Ok(Some((res, None))),
+                Ok(None) => {
                     dep_kind = CrateDepKind::MacrosOnly;
-                    match self.load_proc_macro(&mut locator, path_kind, host_hash)? {
-                        Some(res) => res,
-                        None => return Err(locator.into_error(root.cloned())),
-                    }
+                    self.load_proc_macro(&mut locator, path_kind, host_hash)
                 }
+                Err(err) => Err(err),
+            };
+            if let Some(index) = log_index {
+                self.cstore.extern_resolutions[index].rejected = locator.rejected_candidates();
+            }
+            match result? {
+                Some(res) => res,
+                None => return Err(locator.into_error(root.cloned())),
             }
         };
 
-        match result {
+        let reused = matches!(result.0, LoadResult::Previous(_));
+        let cnum = match result {
             (LoadResult::Previous(cnum), None) => {
                 let data = self.cstore.get_crate_data_mut(cnum);
                 if data.is_proc_macro_crate() {
@@ -607,13 +732,20 @@ fn maybe_resolve_crate<'b>(
                 if let Some(private_dep) = private_dep {
                     data.update_and_private_dep(private_dep);
                 }
-                Ok(cnum)
+                cnum
             }
             (LoadResult::Loaded(library), host_library) => {
-                self.register_crate(host_library, root, library, dep_kind, name, private_dep)
+                self.register_crate(host_library, root, library, dep_kind, name, private_dep)?
             }
             _ => panic!(),
+        };
+
+        if let Some(index) = log_index {
+            let resolution = &mut self.cstore.extern_resolutions[index];
+            resolution.cnum = Some(cnum);
+            resolution.reused = reused;
         }
+        Ok(cnum)
     }
 
     fn load(&self, locator: &mut CrateLocator<'_>) -> Result<Option<LoadResult>, CrateError> {
@@ -263,7 +263,7 @@ pub(crate) struct CrateLocator<'a> {
 
 #[derive(Clone)]
 pub(crate) struct CratePaths {
-    name: Symbol,
+    pub(crate) name: Symbol,
     source: CrateSource,
 }
 
@@ -753,6 +753,24 @@ fn find_commandline_library(&mut self) -> Result<Option<Library>, CrateError> {
         Ok(self.extract_lib(rlibs, rmetas, dylibs)?.map(|(_, lib)| lib))
     }
 
+    /// Lists the candidates that were looked at and rejected so far, with the reason why.
+    pub(crate) fn rejected_candidates(&self) -> Vec<(PathBuf, String)> {
+        let rejections = &self.crate_rejections;
+        let mut rejected = Vec::new();
+        let mut push = |mismatches: &[CrateMismatch], reason: fn(&str) -> String| {
+            for CrateMismatch { path, got } in mismatches {
+                rejected.push((path.clone(), reason(got)));
+            }
+        };
+        push(&rejections.via_hash, |got| format!("crate hash {got} is not the one required"));
+        push(&rejections.via_triple, |got| format!("compiled for target {got}"));
+        push(&rejections.via_kind, |_| "a static library, not a Rust crate".to_string());
+        push(&rejections.via_version, |got| format!("compiled by {got}"));
+        push(&rejections.via_filename, |_| "not named like a Rust library".to_string());
+        push(&rejections.via_invalid, |got| format!("invalid metadata: {got}"));
+        rejected
+    }
+
     pub(crate) fn into_error(self, root: Option<CratePaths>) -> CrateError {
         CrateError::LocatorCombined(Box::new(CombinedLocatorError {
             crate_name: self.crate_name,
@@ -364,6 +364,14 @@ pub fn load_side_effects(
         side_effects.unwrap_or_default()
     }
 
+    /// Returns the nodes of the previous compilation session that have `QuerySideEffects`
+    /// stored for them, in the order of the previous dep-graph.
+    pub fn prev_side_effect_nodes(&self) -> Vec<SerializedDepNodeIndex> {
+        let mut nodes: Vec<_> = self.prev_side_effects_index.keys().copied().collect();
+        nodes.sort_unstable();
+        nodes
+    }
+
     /// Stores a `QuerySideEffects` emitted during the current compilation session.
     /// Anything stored like this will be available via `load_side_effects` in
     /// the next compilation session.
@@ -945,6 +945,16 @@ pub fn is_green(&self, dep_node: &DepNode) -> bool {
         self.node_color(dep_node).is_some_and(|c| c.is_green())
     }
 
+    /// Returns the node with the given index in the previous session's dep-graph, unless it
+    /// has already been marked as red or green during the current compilation session.
+    pub fn uncolored_prev_node(&self, prev_index: SerializedDepNodeIndex) -> Option<DepNode> {
+        let data = self.data.as_ref()?;
+        if data.colors.get(prev_index).is_some() {
+            return None;
+        }
+        Some(data.previous.index_to_node(prev_index))
+    }
+
     /// This method loads all on-disk cacheable query results into memory, so
     /// they can be written out to the new cache file again. Most query results
     /// will already be in memory but in the case where we marked something as
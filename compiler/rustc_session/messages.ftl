@@ -16,8 +16,12 @@ session_crate_name_invalid = crate names cannot start with a `-`, but `{$s}` has
 
 session_expr_parentheses_needed = parentheses are required to parse this as an expression
 
+session_failed_to_apply_suggestions = failed to apply suggestions to `{$path}`: {$err}
+
 session_failed_to_create_profiler = failed to create profiler: {$err}
 
+session_failed_to_write_lint_baseline = failed to write lint baseline `{$path}`: {$err}
+
 session_feature_diagnostic_for_issue =
     see issue #{$n} <https://github.com/rust-lang/rust/issues/{$n}> for more information
 
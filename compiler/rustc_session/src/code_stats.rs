@@ -3,7 +3,15 @@
 use rustc_span::def_id::DefId;
 use rustc_span::Symbol;
 use rustc_target::abi::{Align, Size};
+use serde::Serialize;
 use std::cmp;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The version of the JSON written by `-Z print-type-sizes=json` and
+/// `-Z print-vtable-sizes=json`. Bump this when changing the structs at the end of this file.
+const CODE_STATS_JSON_VERSION: u32 = 1;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -130,8 +138,7 @@ pub fn record_vtable_size(&self, trait_did: DefId, trait_name: &str, info: VTabl
         );
     }
 
-    pub fn print_type_sizes(&self) {
-        let type_sizes = self.type_sizes.borrow();
+    fn sorted_type_sizes(type_sizes: &FxHashSet<TypeSizeInfo>) -> Vec<&TypeSizeInfo> {
         // We will soon sort, so the initial order does not matter.
         #[allow(rustc::potential_query_instability)]
         let mut sorted: Vec<_> = type_sizes.iter().collect();
@@ -139,8 +146,12 @@ pub fn print_type_sizes(&self) {
         // Primary sort: large-to-small.
         // Secondary sort: description (dictionary order)
         sorted.sort_by_key(|info| (cmp::Reverse(info.overall_size), &info.type_description));
+        sorted
+    }
 
-        for info in sorted {
+    pub fn print_type_sizes(&self) {
+        let type_sizes = self.type_sizes.borrow();
+        for info in Self::sorted_type_sizes(&type_sizes) {
             let TypeSizeInfo { type_description, overall_size, align, kind, variants, .. } = info;
             println!(
                 "print-type-size type: `{type_description}`: {overall_size} bytes, alignment: {align} bytes"
@@ -228,7 +239,58 @@ pub fn print_type_sizes(&self) {
         }
     }
 
-    pub fn print_vtable_sizes(&self, crate_name: Symbol) {
+    /// Writes the recorded type sizes as JSON to `path`, in the same order
+    /// `print_type_sizes` prints them.
+    pub fn write_type_sizes_json(&self, crate_name: Symbol, path: &Path) -> io::Result<()> {
+        let type_sizes = self.type_sizes.borrow();
+        let types = Self::sorted_type_sizes(&type_sizes)
+            .into_iter()
+            .map(|info| JsonTypeSize {
+                type_description: &info.type_description,
+                kind: match info.kind {
+                    DataTypeKind::Struct => "struct",
+                    DataTypeKind::Union => "union",
+                    DataTypeKind::Enum => "enum",
+                    DataTypeKind::Closure => "closure",
+                    DataTypeKind::Coroutine => "coroutine",
+                },
+                size: info.overall_size,
+                align: info.align,
+                packed: info.packed,
+                discriminant_size: info.opt_discr_size,
+                variants: info
+                    .variants
+                    .iter()
+                    .map(|variant| JsonVariant {
+                        name: variant.name.map(|name| name.to_string()),
+                        size_kind: match variant.kind {
+                            SizeKind::Exact => "exact",
+                            SizeKind::Min => "min",
+                        },
+                        size: variant.size,
+                        align: variant.align,
+                        fields: variant
+                            .fields
+                            .iter()
+                            .map(|field| JsonField {
+                                kind: field.kind.to_string(),
+                                name: field.name.to_string(),
+                                offset: field.offset,
+                                size: field.size,
+                                align: field.align,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let crate_name = crate_name.as_str();
+        let report = JsonTypeSizes { version: CODE_STATS_JSON_VERSION, crate_name, types };
+        write_json(path, &report)
+    }
+
+    fn take_sorted_vtable_sizes(&self) -> Vec<VTableSizeInfo> {
         // We will soon sort, so the initial order does not matter.
         #[allow(rustc::potential_query_instability)]
         let mut infos =
@@ -242,6 +304,11 @@ pub fn print_vtable_sizes(&self, crate_name: Symbol) {
                 .reverse()
                 .then_with(|| a.trait_name.cmp(&b.trait_name))
         });
+        infos
+    }
+
+    pub fn print_vtable_sizes(&self, crate_name: Symbol) {
+        let infos = self.take_sorted_vtable_sizes();
 
         for VTableSizeInfo {
             trait_name,
@@ -256,4 +323,91 @@ pub fn print_vtable_sizes(&self, crate_name: Symbol) {
             );
         }
     }
+
+    /// Writes the recorded vtable sizes as JSON to `path`, in the same order
+    /// `print_vtable_sizes` prints them.
+    pub fn write_vtable_sizes_json(&self, crate_name: Symbol, path: &Path) -> io::Result<()> {
+        let infos = self.take_sorted_vtable_sizes();
+        let vtables = infos
+            .iter()
+            .map(|info| JsonVTableSize {
+                trait_name: &info.trait_name,
+                entries: info.entries,
+                entries_ignoring_upcasting: info.entries_ignoring_upcasting,
+                entries_for_upcasting: info.entries_for_upcasting,
+                upcasting_cost_percent: info.upcasting_cost_percent,
+            })
+            .collect();
+
+        let crate_name = crate_name.as_str();
+        let report = JsonVTableSizes { version: CODE_STATS_JSON_VERSION, crate_name, vtables };
+        write_json(path, &report)
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut file, value)?;
+    file.write_all(b"\n")?;
+    file.flush()
+}
+
+// The following data types are provided just for serialisation.
+
+#[derive(Serialize)]
+struct JsonTypeSizes<'a> {
+    version: u32,
+    crate_name: &'a str,
+    /// Sorted from largest to smallest.
+    types: Vec<JsonTypeSize<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonTypeSize<'a> {
+    type_description: &'a str,
+    /// "struct", "union", "enum", "closure" or "coroutine".
+    kind: &'static str,
+    size: u64,
+    align: u64,
+    packed: bool,
+    discriminant_size: Option<u64>,
+    variants: Vec<JsonVariant>,
+}
+
+#[derive(Serialize)]
+struct JsonVariant {
+    /// `None` for structs, closures and unnamed variants.
+    name: Option<String>,
+    /// "exact", or "min" if the size is a lower bound.
+    size_kind: &'static str,
+    size: u64,
+    align: u64,
+    fields: Vec<JsonField>,
+}
+
+#[derive(Serialize)]
+struct JsonField {
+    /// "field", "upvar" or "local".
+    kind: String,
+    name: String,
+    offset: u64,
+    size: u64,
+    align: u64,
+}
+
+#[derive(Serialize)]
+struct JsonVTableSizes<'a> {
+    version: u32,
+    crate_name: &'a str,
+    /// Sorted by upcasting cost, from largest to smallest.
+    vtables: Vec<JsonVTableSize<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonVTableSize<'a> {
+    trait_name: &'a str,
+    entries: usize,
+    entries_ignoring_upcasting: usize,
+    entries_for_upcasting: usize,
+    upcasting_cost_percent: f64,
 }
@@ -34,6 +34,7 @@
 use std::str::{self, FromStr};
 use std::sync::LazyLock;
 
+mod lint_config;
 pub mod sigpipe;
 
 /// The different settings that the `-C strip` flag can have.
@@ -526,6 +527,13 @@ pub enum ErrorOutputType {
         /// human output.
         json_rendered: HumanReadableErrorType,
     },
+    /// A single SARIF log, for consumption by static analysis and code-scanning tools.
+    Sarif {
+        /// Render the log in a human readable way (with indents and newlines).
+        pretty: bool,
+    },
+    /// A self-contained HTML report of all diagnostics, to be read in a browser.
+    Html,
 }
 
 impl Default for ErrorOutputType {
@@ -723,6 +731,8 @@ pub enum PrintKind {
     LinkArgs,
     SplitDebuginfo,
     DeploymentTarget,
+    CrateGraph,
+    ExternResolution,
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -1055,6 +1065,9 @@ fn default() -> Options {
             debuginfo_compression: DebugInfoCompression::None,
             lint_opts: Vec::new(),
             lint_cap: None,
+            lint_config: None,
+            lint_baseline: None,
+            write_lint_baseline: None,
             describe_lints: false,
             output_types: OutputTypes(BTreeMap::new()),
             search_paths: vec![],
@@ -1136,6 +1149,7 @@ pub fn dcx_flags(&self, can_emit_warnings: bool) -> DiagCtxtFlags {
             macro_backtrace: self.macro_backtrace,
             deduplicate_diagnostics: self.deduplicate_diagnostics,
             track_diagnostics: self.track_diagnostics,
+            collect_machine_applicable_suggestions: self.apply_suggestions.is_some(),
         }
     }
 }
@@ -1734,7 +1748,8 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
             "[crate-name|file-names|sysroot|target-libdir|cfg|calling-conventions|\
              target-list|target-cpus|target-features|relocation-models|code-models|\
              tls-models|target-spec-json|all-target-specs-json|native-static-libs|\
-             stack-protector-strategies|link-args|deployment-target]",
+             stack-protector-strategies|link-args|deployment-target|crate-graph|\
+             extern-resolution]",
         ),
         opt::flagmulti_s("g", "", "Equivalent to -C debuginfo=2"),
         opt::flagmulti_s("O", "", "Equivalent to -C opt-level=2"),
@@ -1793,7 +1808,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif|html",
         ),
         opt::multi_s("", "json", "Configure the JSON output of the compiler", "CONFIG"),
         opt::opt_s(
@@ -1818,6 +1833,19 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "FROM=TO",
         ),
         opt::multi("", "env-set", "Inject an environment variable", "VAR=VALUE"),
+        opt::opt("", "lint-config", "Read lint levels from a TOML or JSON file", "PATH"),
+        opt::opt(
+            "",
+            "lint-baseline",
+            "Only report lint warnings that are not in a baseline file",
+            "PATH",
+        ),
+        opt::opt(
+            "",
+            "write-lint-baseline",
+            "Write all lint warnings to a baseline file, for use with `--lint-baseline`",
+            "PATH",
+        ),
     ]);
     opts
 }
@@ -1826,6 +1854,15 @@ pub fn get_cmd_lint_options(
     early_dcx: &EarlyDiagCtxt,
     matches: &getopts::Matches,
 ) -> (Vec<(String, lint::Level)>, bool, Option<lint::Level>) {
+    // Levels from the configuration file come first, so that they can be overridden by the
+    // command line.
+    let mut lint_opts = match matches.opt_str("lint-config") {
+        Some(path) => lint_config::parse_lint_config(Path::new(&path)).unwrap_or_else(|e| {
+            early_dcx.early_fatal(format!("failed to read lint configuration `{path}`: {e}"))
+        }),
+        None => vec![],
+    };
+
     let mut lint_opts_with_position = vec![];
     let mut describe_lints = false;
 
@@ -1840,11 +1877,9 @@ pub fn get_cmd_lint_options(
     }
 
     lint_opts_with_position.sort_by_key(|x| x.0);
-    let lint_opts = lint_opts_with_position
-        .iter()
-        .cloned()
-        .map(|(_, lint_name, level)| (lint_name, level))
-        .collect();
+    lint_opts.extend(
+        lint_opts_with_position.into_iter().map(|(_, lint_name, level)| (lint_name, level)),
+    );
 
     let lint_cap = matches.opt_str("cap-lints").map(|cap| {
         lint::Level::from_str(&cap)
@@ -1966,6 +2001,9 @@ pub fn parse_error_format(
             }
             Some("json") => ErrorOutputType::Json { pretty: false, json_rendered },
             Some("pretty-json") => ErrorOutputType::Json { pretty: true, json_rendered },
+            Some("sarif") => ErrorOutputType::Sarif { pretty: false },
+            Some("pretty-sarif") => ErrorOutputType::Sarif { pretty: true },
+            Some("html") => ErrorOutputType::Html,
             Some("short") => ErrorOutputType::HumanReadable(HumanReadableErrorType::Short(color)),
 
             Some(arg) => {
@@ -1973,8 +2011,8 @@ pub fn parse_error_format(
                     HumanReadableErrorType::Default(color),
                 ));
                 early_dcx.early_fatal(format!(
-                    "argument for `--error-format` must be `human`, `json` or \
-                     `short` (instead was `{arg}`)"
+                    "argument for `--error-format` must be `human`, `json`, `short`, \
+                     `sarif` or `html` (instead was `{arg}`)"
                 ))
             }
         }
@@ -2033,6 +2071,13 @@ fn check_error_format_stability(
         if let ErrorOutputType::Json { pretty: true, .. } = error_format {
             early_dcx.early_fatal("`--error-format=pretty-json` is unstable");
         }
+        if let ErrorOutputType::Sarif { pretty } = error_format {
+            let name = if pretty { "pretty-sarif" } else { "sarif" };
+            early_dcx.early_fatal(format!("`--error-format={name}` is unstable"));
+        }
+        if let ErrorOutputType::Html = error_format {
+            early_dcx.early_fatal("`--error-format=html` is unstable");
+        }
         if let ErrorOutputType::HumanReadable(HumanReadableErrorType::AnnotateSnippet(_)) =
             error_format
         {
@@ -2142,8 +2187,10 @@ fn collect_print_requests(
         ("calling-conventions", PrintKind::CallingConventions),
         ("cfg", PrintKind::Cfg),
         ("code-models", PrintKind::CodeModels),
+        ("crate-graph", PrintKind::CrateGraph),
         ("crate-name", PrintKind::CrateName),
         ("deployment-target", PrintKind::DeploymentTarget),
+        ("extern-resolution", PrintKind::ExternResolution),
         ("file-names", PrintKind::FileNames),
         ("link-args", PrintKind::LinkArgs),
         ("native-static-libs", PrintKind::NativeStaticLibs),
@@ -2190,6 +2237,16 @@ fn collect_print_requests(
                     );
                 }
             }
+            Some(&(_, print_kind @ (PrintKind::CrateGraph | PrintKind::ExternResolution))) => {
+                if unstable_opts.unstable_options {
+                    print_kind
+                } else {
+                    early_dcx.early_fatal(format!(
+                        "the `-Z unstable-options` flag must also be passed to \
+                         enable the {req} print option",
+                    ));
+                }
+            }
             Some(&(_, print_kind)) => print_kind,
             None => {
                 let prints =
@@ -2635,6 +2692,9 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
 
     let mut unstable_opts = UnstableOptions::build(early_dcx, matches);
     let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(early_dcx, matches);
+    let lint_config = matches.opt_str("lint-config").map(PathBuf::from);
+    let lint_baseline = matches.opt_str("lint-baseline").map(PathBuf::from);
+    let write_lint_baseline = matches.opt_str("write-lint-baseline").map(PathBuf::from);
 
     check_error_format_stability(early_dcx, &unstable_opts, error_format);
 
@@ -2913,6 +2973,9 @@ pub fn build_session_options(early_dcx: &mut EarlyDiagCtxt, matches: &getopts::M
         debuginfo_compression,
         lint_opts,
         lint_cap,
+        lint_config,
+        lint_baseline,
+        write_lint_baseline,
         describe_lints,
         output_types,
         search_paths,
@@ -2969,13 +3032,15 @@ fn parse_pretty(early_dcx: &EarlyDiagCtxt, unstable_opts: &UnstableOptions) -> O
         "thir-flat" => ThirFlat,
         "mir" => Mir,
         "stable-mir" => StableMir,
+        "mir-json" => MirJson,
+        "smir-snapshot" => SmirSnapshot,
         "mir-cfg" => MirCFG,
         name => early_dcx.early_fatal(format!(
             "argument to `unpretty` must be one of `normal`, `identified`, \
                             `expanded`, `expanded,identified`, `expanded,hygiene`, \
                             `ast-tree`, `ast-tree,expanded`, `hir`, `hir,identified`, \
-                            `hir,typed`, `hir-tree`, `thir-tree`, `thir-flat`, `mir`, `stable-mir`, or \
-                            `mir-cfg`; got {name}"
+                            `hir,typed`, `hir-tree`, `thir-tree`, `thir-flat`, `mir`, `stable-mir`, \
+                            `mir-json`, `smir-snapshot`, or `mir-cfg`; got {name}"
         )),
     };
     debug!("got unpretty option: {first:?}");
@@ -3151,6 +3216,10 @@ pub enum PpMode {
     MirCFG,
     /// `-Zunpretty=stable-mir`
     StableMir,
+    /// `-Zunpretty=mir-json`
+    MirJson,
+    /// `-Zunpretty=smir-snapshot`
+    SmirSnapshot,
 }
 
 impl PpMode {
@@ -3168,7 +3237,9 @@ pub fn needs_ast_map(&self) -> bool {
             | ThirFlat
             | Mir
             | MirCFG
-            | StableMir => true,
+            | StableMir
+            | MirJson
+            | SmirSnapshot => true,
         }
     }
     pub fn needs_hir(&self) -> bool {
@@ -3176,13 +3247,24 @@ pub fn needs_hir(&self) -> bool {
         match *self {
             Source(_) | AstTree | AstTreeExpanded => false,
 
-            Hir(_) | HirTree | ThirTree | ThirFlat | Mir | MirCFG | StableMir => true,
+            Hir(_) | HirTree | ThirTree | ThirFlat | Mir | MirCFG | StableMir | MirJson
+            | SmirSnapshot => true,
         }
     }
 
     pub fn needs_analysis(&self) -> bool {
         use PpMode::*;
-        matches!(*self, Hir(PpHirMode::Typed) | Mir | StableMir | MirCFG | ThirTree | ThirFlat)
+        matches!(
+            *self,
+            Hir(PpHirMode::Typed)
+                | Mir
+                | StableMir
+                | MirJson
+                | SmirSnapshot
+                | MirCFG
+                | ThirTree
+                | ThirFlat
+        )
     }
 }
 
@@ -3497,6 +3579,15 @@ pub fn extension(self) -> &'static str {
     }
 }
 
+/// Which format to use for `-Z print-type-sizes` and `-Z print-vtable-sizes`
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum CodeStatsFormat {
+    /// Print one line per item to stdout
+    Text,
+    /// Write structured JSON to a file in the output directory
+    Json,
+}
+
 /// `-Zpolonius` values, enabling the borrow checker polonius analysis, and which version: legacy,
 /// or future prototype.
 #[derive(Clone, Copy, PartialEq, Hash, Debug, Default)]
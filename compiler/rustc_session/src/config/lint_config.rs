@@ -0,0 +1,271 @@
+//! Lint level configuration files, passed with `--lint-config`.
+//!
+//! The file has the layout of the `[lints]` table of a Cargo manifest, so that a lint policy can
+//! be shared between Cargo and other build systems:
+//!
+//! ```toml
+//! [lints.rust]
+//! unsafe_op_in_unsafe_fn = "deny"
+//! missing_docs = { level = "warn", priority = 1 }
+//!
+//! [lints.clippy]
+//! pedantic = { level = "warn", priority = -1 }
+//! ```
+//!
+//! Files whose name ends in `.json` hold the same data as JSON, e.g.
+//! `{ "lints": { "rust": { "unsafe_op_in_unsafe_fn": "deny" } } }`.
+//!
+//! Like in Cargo, lints are applied in order of increasing priority and then by name, so lint
+//! groups need a lower priority than the lints in them whose level they don't override. The
+//! resulting levels are applied before those of the `-A`/`-W`/`-D`/`-F` flags.
+//!
+//! To not pull a TOML parser into the compiler, only the subset of TOML needed for the layout
+//! above is supported: table headers, `key = value` pairs, basic and literal strings, integers,
+//! inline tables and comments.
+
+use crate::lint;
+use rustc_data_structures::fx::FxHashSet;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A lint level requested by the configuration file.
+struct LintEntry {
+    /// The lint name, with its tool prefix for tool lints.
+    name: String,
+    level: String,
+    priority: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonLintConfig {
+    lints: BTreeMap<String, BTreeMap<String, JsonLintSetting>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonLintSetting {
+    Level(String),
+    Detailed {
+        level: String,
+        #[serde(default)]
+        priority: i32,
+    },
+}
+
+/// Reads the lint configuration file at `path` and returns the lint levels in it, in the order
+/// they have to be applied.
+pub(super) fn parse_lint_config(path: &Path) -> Result<Vec<(String, lint::Level)>, String> {
+    let src = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut entries = if path.extension().is_some_and(|ext| ext == "json") {
+        parse_json(&src)?
+    } else {
+        parse_toml(&src)?
+    };
+
+    let mut seen = FxHashSet::default();
+    for entry in &entries {
+        if !seen.insert(&entry.name) {
+            return Err(format!("the level of `{}` is specified more than once", entry.name));
+        }
+    }
+
+    entries.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.name.cmp(&b.name)));
+    entries
+        .into_iter()
+        .map(|LintEntry { name, level, .. }| {
+            let level = match &level[..] {
+                "force-warn" => lint::ForceWarn(None),
+                level => lint::Level::from_str(level)
+                    .ok_or_else(|| format!("unknown lint level `{level}` for `{name}`"))?,
+            };
+            Ok((name, level))
+        })
+        .collect()
+}
+
+fn lint_name(tool: &str, lint: &str) -> String {
+    let lint = lint.replace('-', "_");
+    if tool == "rust" { lint } else { format!("{tool}::{lint}") }
+}
+
+fn parse_json(src: &str) -> Result<Vec<LintEntry>, String> {
+    let config: JsonLintConfig = serde_json::from_str(src).map_err(|e| e.to_string())?;
+    let mut entries = vec![];
+    for (tool, lints) in config.lints {
+        for (lint, setting) in lints {
+            let (level, priority) = match setting {
+                JsonLintSetting::Level(level) => (level, 0),
+                JsonLintSetting::Detailed { level, priority } => (level, priority),
+            };
+            entries.push(LintEntry { name: lint_name(&tool, &lint), level, priority });
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_toml(src: &str) -> Result<Vec<LintEntry>, String> {
+    let mut entries = vec![];
+    let mut tool = None;
+    for (i, line) in src.lines().enumerate() {
+        let error = |msg: String| format!("line {}: {msg}", i + 1);
+        let mut parser = TomlParser { rest: line };
+        parser.skip_whitespace();
+        if parser.at_end() {
+            continue;
+        }
+
+        if parser.eat('[') {
+            let header = parser.dotted_key().map_err(error)?;
+            parser.expect(']').and_then(|()| parser.expect_end()).map_err(error)?;
+            match &header[..] {
+                [lints, name] if lints == "lints" => tool = Some(name.clone()),
+                _ => return Err(error("expected a table header like `[lints.rust]`".into())),
+            }
+            continue;
+        }
+
+        let Some(tool) = &tool else {
+            return Err(error("expected a table header like `[lints.rust]`".into()));
+        };
+        let lint = parser.key().map_err(error)?;
+        parser.expect('=').map_err(error)?;
+        let (level, priority) = parser.lint_setting().map_err(error)?;
+        parser.expect_end().map_err(error)?;
+        entries.push(LintEntry { name: lint_name(tool, &lint), level, priority });
+    }
+    Ok(entries)
+}
+
+/// Parses the parts of a single line of TOML.
+struct TomlParser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> TomlParser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t']);
+    }
+
+    /// Whether the rest of the line is empty or a comment.
+    fn at_end(&self) -> bool {
+        self.rest.is_empty() || self.rest.starts_with('#')
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if let Some(rest) = self.rest.strip_prefix(c) {
+            self.rest = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) { Ok(()) } else { Err(format!("expected `{c}`")) }
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.at_end() { Ok(()) } else { Err(format!("unexpected `{}`", self.rest)) }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if self.rest.starts_with(['"', '\'']) {
+            return self.string();
+        }
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err("expected a key".into());
+        }
+        let (key, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(key.to_owned())
+    }
+
+    fn dotted_key(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = vec![self.key()?];
+        while self.eat('.') {
+            keys.push(self.key()?);
+        }
+        Ok(keys)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let unterminated = || "unterminated string".to_owned();
+        if let Some(rest) = self.rest.strip_prefix('\'') {
+            // Literal strings have no escapes.
+            let (s, rest) = rest.split_once('\'').ok_or_else(unterminated)?;
+            self.rest = rest;
+            return Ok(s.to_owned());
+        }
+
+        let mut chars = self.rest.strip_prefix('"').ok_or("expected a string")?.char_indices();
+        let mut s = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 2..];
+                    return Ok(s);
+                }
+                '\\' => match chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    _ => return Err("unsupported escape sequence in string".into()),
+                },
+                c => s.push(c),
+            }
+        }
+        Err(unterminated())
+    }
+
+    fn integer(&mut self) -> Result<i32, String> {
+        self.skip_whitespace();
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '-' || c == '+'))
+            .unwrap_or(self.rest.len());
+        let (n, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        n.parse().map_err(|_| "expected an integer".to_owned())
+    }
+
+    /// Parses either a level, or an inline table with a level and a priority.
+    fn lint_setting(&mut self) -> Result<(String, i32), String> {
+        if !self.eat('{') {
+            self.skip_whitespace();
+            return Ok((self.string()?, 0));
+        }
+
+        let mut level = None;
+        let mut priority = 0;
+        loop {
+            match &self.key()?[..] {
+                "level" => {
+                    self.expect('=')?;
+                    self.skip_whitespace();
+                    level = Some(self.string()?);
+                }
+                "priority" => {
+                    self.expect('=')?;
+                    priority = self.integer()?;
+                }
+                key => {
+                    return Err(format!("unexpected key `{key}`, expected `level` or `priority`"));
+                }
+            }
+            if self.eat('}') {
+                break;
+            }
+            self.expect(',')?;
+        }
+        Ok((level.ok_or("missing `level`")?, priority))
+    }
+}
@@ -177,6 +177,20 @@ pub(crate) struct FileWriteFail<'a> {
     pub err: String,
 }
 
+#[derive(Diagnostic)]
+#[diag(session_failed_to_apply_suggestions)]
+pub(crate) struct FailedToApplySuggestions<'a> {
+    pub path: &'a std::path::Path,
+    pub err: String,
+}
+
+#[derive(Diagnostic)]
+#[diag(session_failed_to_write_lint_baseline)]
+pub(crate) struct FailedToWriteLintBaseline<'a> {
+    pub path: &'a std::path::Path,
+    pub err: std::io::Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(session_crate_name_does_not_match)]
 pub struct CrateNameDoesNotMatch {
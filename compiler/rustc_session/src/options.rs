@@ -143,6 +143,13 @@ pub struct Options {
         debuginfo_compression: DebugInfoCompression [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED_NO_CRATE_HASH],
         lint_cap: Option<lint::Level> [TRACKED_NO_CRATE_HASH],
+        /// The file passed with `--lint-config`. The levels in it are tracked as part of
+        /// `lint_opts`, the path is only needed for dep-info.
+        lint_config: Option<PathBuf> [UNTRACKED],
+        /// The baseline of known lint warnings passed with `--lint-baseline`. Warnings are
+        /// filtered when they are emitted, so this doesn't need to be tracked.
+        lint_baseline: Option<PathBuf> [UNTRACKED],
+        write_lint_baseline: Option<PathBuf> [UNTRACKED],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         search_paths: Vec<SearchPath> [UNTRACKED],
@@ -394,6 +401,8 @@ mod desc {
     pub const parse_linker_flavor: &str = ::rustc_target::spec::LinkerFlavorCli::one_of();
     pub const parse_optimization_fuel: &str = "crate=integer";
     pub const parse_dump_mono_stats: &str = "`markdown` (default) or `json`";
+    pub const parse_code_stats_format: &str =
+        "either a boolean (`yes`, `no`, `on`, `off`, etc), `text`, or `json`";
     pub const parse_instrument_coverage: &str =
         "`all` (default), `branch`, `except-unused-generics`, `except-unused-functions`, or `off`";
     pub const parse_instrument_xray: &str = "either a boolean (`yes`, `no`, `on`, `off`, etc), or a comma separated list of settings: `always` or `never` (mutually exclusive), `ignore-loops`, `instruction-threshold=N`, `skip-entry`, `skip-exit`";
@@ -896,6 +905,24 @@ pub(crate) fn parse_time_passes_format(slot: &mut TimePassesFormat, v: Option<&s
         }
     }
 
+    pub(crate) fn parse_code_stats_format(
+        slot: &mut Option<CodeStatsFormat>,
+        v: Option<&str>,
+    ) -> bool {
+        match v {
+            Some("text") => *slot = Some(CodeStatsFormat::Text),
+            Some("json") => *slot = Some(CodeStatsFormat::Json),
+            _ => {
+                let mut bool_arg = false;
+                if !parse_bool(&mut bool_arg, v) {
+                    return false;
+                }
+                *slot = bool_arg.then_some(CodeStatsFormat::Text);
+            }
+        }
+        true
+    }
+
     pub(crate) fn parse_dump_mono_stats(slot: &mut DumpMonoStatsFormat, v: Option<&str>) -> bool {
         match v {
             None => true,
@@ -1548,6 +1575,10 @@ pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>)
         "only allow the listed language features to be enabled in code (comma separated)"),
     always_encode_mir: bool = (false, parse_bool, [TRACKED],
         "encode MIR of all functions into the crate metadata (default: no)"),
+    apply_suggestions: Option<NonZero<usize>> = (None, parse_treat_err_as_bug, [UNTRACKED],
+        "apply machine-applicable suggestions to the source files after compilation, and \
+        compile the crate again up to `val` times while suggestions keep being applied \
+        (default if specified without a value: 1 - apply suggestions once)"),
     asm_comments: bool = (false, parse_bool, [TRACKED],
         "generate comments into the assembly (may change behavior) (default: no)"),
     assert_incr_state: Option<String> = (None, parse_opt_string, [UNTRACKED],
@@ -1656,6 +1687,9 @@ pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>)
     graphviz_font: String = ("Courier, monospace".to_string(), parse_string, [UNTRACKED],
         "use the given `fontname` in graphviz output; can be overridden by setting \
         environment variable `RUSTC_GRAPHVIZ_FONT` (default: `Courier, monospace`)"),
+    group_diagnostics: bool = (false, parse_bool, [UNTRACKED],
+        "report diagnostics that only differ in the macro invocation they come from once, \
+        with a list of the invocations (default: no)"),
     has_thread_local: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "explicitly enable the `cfg(target_thread_local)` directive"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
@@ -1817,10 +1851,12 @@ pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>)
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print the result of the monomorphization collection pass"),
-    print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
-        "print layout information for each type encountered (default: no)"),
-    print_vtable_sizes: bool = (false, parse_bool, [UNTRACKED],
-        "print size comparison between old and new vtable layouts (default: no)"),
+    print_type_sizes: Option<CodeStatsFormat> = (None, parse_code_stats_format, [UNTRACKED],
+        "print layout information for each type encountered; `json` writes it to \
+        `<crate>.type_sizes.json` in the output directory instead (default: no)"),
+    print_vtable_sizes: Option<CodeStatsFormat> = (None, parse_code_stats_format, [UNTRACKED],
+        "print size comparison between old and new vtable layouts; `json` writes it to \
+        `<crate>.vtable_sizes.json` in the output directory instead (default: no)"),
     proc_macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
          "show backtraces for panics during proc-macro execution (default: no)"),
     proc_macro_execution_strategy: ProcMacroExecutionStrategy = (ProcMacroExecutionStrategy::SameThread,
@@ -1852,6 +1888,9 @@ pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>)
     remark_dir: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
         "directory into which to write optimization remarks (if not specified, they will be \
 written to standard error output)"),
+    replay_all_warnings: bool = (false, parse_bool, [UNTRACKED],
+        "in incremental mode, replay the diagnostics of all query results reused from the \
+        previous session, even those that weren't needed by this one (default: no)"),
     sanitizer: SanitizerSet = (SanitizerSet::empty(), parse_sanitizers, [TRACKED],
         "use a sanitizer"),
     sanitizer_cfi_canonical_jump_tables: Option<bool> = (Some(true), parse_opt_bool, [TRACKED],
@@ -1994,7 +2033,9 @@ pub(crate) fn parse_function_return(slot: &mut FunctionReturn, v: Option<&str>)
         `hir,typed` (HIR with types for each node),
         `hir-tree` (dump the raw HIR),
         `thir-tree`, `thir-flat`,
-        `mir` (the MIR), or `mir-cfg` (graphviz formatted MIR)"),
+        `mir` (the MIR), `mir-json` (the stable MIR as JSON),
+        `smir-snapshot` (a snapshot of the stable MIR of the crate for offline analysis),
+        or `mir-cfg` (graphviz formatted MIR)"),
     unsound_mir_opts: bool = (false, parse_bool, [TRACKED],
         "enable unsound and buggy MIR optimizations (default: no)"),
     /// This name is kind of confusing: Most unstable options enable something themselves, while
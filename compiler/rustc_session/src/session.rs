@@ -18,9 +18,14 @@
     AtomicU64, DynSend, DynSync, Lock, Lrc, MappedReadGuard, ReadGuard, RwLock,
 };
 use rustc_errors::annotate_snippet_emitter_writer::AnnotateSnippetEmitter;
+use rustc_errors::apply_suggestions::apply_suggestions;
 use rustc_errors::emitter::{stderr_destination, DynEmitter, HumanEmitter, HumanReadableErrorType};
+use rustc_errors::grouping::GroupingEmitter;
+use rustc_errors::html::HtmlEmitter;
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::lint_baseline::LintBaseline;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_errors::{
     codes::*, fallback_fluent_bundle, Diag, DiagCtxt, DiagMessage, ErrorGuaranteed, FatalAbort,
     FluentBundle, IntoDiagnostic, LazyFallbackBundle, TerminalUrl,
@@ -39,11 +44,12 @@
 use std::any::Any;
 use std::env;
 use std::fmt;
+use std::fs;
 use std::io;
 use std::ops::{Div, Mul};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::{atomic::AtomicBool, atomic::Ordering::SeqCst, Arc};
+use std::sync::{atomic::AtomicBool, atomic::AtomicUsize, atomic::Ordering::SeqCst, Arc};
 
 struct OptimizationFuel {
     /// If `-zfuel=crate=n` is specified, initially set to `n`, otherwise `0`.
@@ -215,6 +221,11 @@ pub struct Session {
     /// This is mainly useful for other tools that reads that debuginfo to figure out
     /// how to call the compiler with the same arguments.
     pub expanded_args: Vec<String>,
+
+    /// The number of suggestions that `-Z apply-suggestions` applied to the source files.
+    /// This is shared so the driver can still read it once the session is gone, to decide
+    /// whether the crate has to be compiled again.
+    pub applied_suggestions: Arc<AtomicUsize>,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -292,6 +303,12 @@ pub fn finish_diagnostics(&self, registry: &Registry) -> Option<ErrorGuaranteed>
         let mut guar = None;
         guar = guar.or(self.check_miri_unleashed_features());
         guar = guar.or(self.dcx().emit_stashed_diagnostics());
+        if self.opts.unstable_opts.apply_suggestions.is_some() {
+            self.apply_suggestions();
+        }
+        if let Some(path) = &self.opts.write_lint_baseline {
+            guar = guar.or(self.write_lint_baseline(path));
+        }
         self.dcx().print_error_count(registry);
         if self.opts.json_future_incompat {
             self.dcx().emit_future_breakage_report();
@@ -299,6 +316,26 @@ pub fn finish_diagnostics(&self, registry: &Registry) -> Option<ErrorGuaranteed>
         guar
     }
 
+    /// Applies the machine-applicable suggestions of all diagnostics emitted so far to the
+    /// source files, for `-Z apply-suggestions`.
+    fn apply_suggestions(&self) {
+        let suggestions = self.dcx().take_machine_applicable_suggestions();
+        let applied = apply_suggestions(self.source_map(), &suggestions);
+        for (path, err) in &applied.errors {
+            self.dcx().emit_warn(errors::FailedToApplySuggestions { path, err: err.to_string() });
+        }
+        self.applied_suggestions.fetch_add(applied.applied, SeqCst);
+    }
+
+    /// Writes the lint warnings emitted so far to `path`, for `--write-lint-baseline`.
+    fn write_lint_baseline(&self, path: &Path) -> Option<ErrorGuaranteed> {
+        let baseline = self.dcx().recorded_lint_baseline()?;
+        match fs::write(path, baseline) {
+            Ok(()) => None,
+            Err(err) => Some(self.dcx().emit_err(errors::FailedToWriteLintBaseline { path, err })),
+        }
+    }
+
     /// Returns true if the crate is a testing one.
     pub fn is_test_crate(&self) -> bool {
         self.opts.test
@@ -321,7 +358,7 @@ pub fn create_feature_err<'a>(
     /// Record the fact that we called `trimmed_def_paths`, and do some
     /// checking about whether its cost was justified.
     pub fn record_trimmed_def_paths(&self) {
-        if self.opts.unstable_opts.print_type_sizes
+        if self.opts.unstable_opts.print_type_sizes.is_some()
             || self.opts.unstable_opts.query_dep_graph
             || self.opts.unstable_opts.dump_mir.is_some()
             || self.opts.unstable_opts.unpretty.is_some()
@@ -969,7 +1006,7 @@ fn default_emitter(
         }
         t => t,
     };
-    match sopts.error_format {
+    let emitter: Box<DynEmitter> = match sopts.error_format {
         config::ErrorOutputType::HumanReadable(kind) => {
             let (short, color_config) = kind.unzip();
 
@@ -1017,6 +1054,34 @@ fn default_emitter(
             .track_diagnostics(track_diagnostics)
             .terminal_url(terminal_url),
         ),
+        config::ErrorOutputType::Sarif { pretty } => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                source_map,
+                fallback_bundle,
+                pretty,
+            )
+            .registry(Some(registry))
+            .fluent_bundle(bundle)
+            .emit_empty_log(true),
+        ),
+        config::ErrorOutputType::Html => Box::new(
+            HtmlEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                source_map,
+                fallback_bundle,
+            )
+            .fluent_bundle(bundle)
+            .ui_testing(sopts.unstable_opts.ui_testing)
+            .diagnostic_width(sopts.diagnostic_width)
+            .emit_empty_report(true),
+        ),
+    };
+
+    if sopts.unstable_opts.group_diagnostics {
+        Box::new(GroupingEmitter::new(emitter))
+    } else {
+        emitter
     }
 }
 
@@ -1087,6 +1152,24 @@ pub fn build_session(
     if let Some(ice_file) = ice_file {
         dcx = dcx.with_ice_file(ice_file);
     }
+    if sopts.lint_baseline.is_some() || sopts.write_lint_baseline.is_some() {
+        let mut lint_baseline = LintBaseline::new(source_map.clone());
+        if let Some(path) = &sopts.lint_baseline {
+            lint_baseline = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| lint_baseline.suppress(&json))
+                .unwrap_or_else(|e| {
+                    early_dcx.early_fatal(format!(
+                        "failed to read lint baseline `{}`: {e}",
+                        path.display()
+                    ))
+                });
+        }
+        if sopts.write_lint_baseline.is_some() {
+            lint_baseline = lint_baseline.record();
+        }
+        dcx = dcx.with_lint_baseline(lint_baseline);
+    }
 
     // Now that the proper handler has been constructed, drop early_dcx to
     // prevent accidental use.
@@ -1174,6 +1257,7 @@ pub fn build_session(
         cfg_version,
         using_internal_features,
         expanded_args,
+        applied_suggestions: Default::default(),
     };
 
     validate_commandline_args_with_session_available(&sess);
@@ -1487,6 +1571,17 @@ fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
             pretty,
             json_rendered,
         )),
+        config::ErrorOutputType::Sarif { pretty } => Box::new(SarifEmitter::new(
+            Box::new(io::BufWriter::new(io::stderr())),
+            Lrc::new(SourceMap::new(FilePathMapping::empty())),
+            fallback_bundle,
+            pretty,
+        )),
+        config::ErrorOutputType::Html => Box::new(HtmlEmitter::new(
+            Box::new(io::BufWriter::new(io::stderr())),
+            Lrc::new(SourceMap::new(FilePathMapping::empty())),
+            fallback_bundle,
+        )),
     };
     emitter
 }
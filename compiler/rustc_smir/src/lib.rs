@@ -11,6 +11,8 @@
     test(attr(allow(unused_variables), deny(warnings)))
 )]
 #![doc(rust_logo)]
+#![feature(lazy_cell)]
+#![feature(never_type)]
 #![feature(rustdoc_internals)]
 #![allow(internal_features)]
 #![allow(rustc::usage_of_ty_tykind)]
@@ -0,0 +1,232 @@
+//! Collection of the facts computed by the borrow checker, see [stable_mir::mir::borrowck].
+//!
+//! The body that is borrow checked is stolen by the MIR passes that run afterwards, so its facts
+//! can't be computed once the analysis is done. A tool calls [collect_borrowck_facts] before it
+//! runs the compiler, e.g. with [run!](crate::run). The facts of every body are then kept when
+//! it is borrow checked, and retrieved with [stable_mir::CrateItem::borrowck_facts]. Like without
+//! the facts, bodies that are tainted by errors are not borrow checked, so they have no facts.
+
+use super::SessionKey;
+use crate::rustc_smir::{Stable, Tables};
+use rustc_borrowck::borrow_set::TwoPhaseActivation;
+use rustc_borrowck::consumers::{self, BodyWithBorrowckFacts, ConsumerOptions, RichLocation};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir::{self, Location};
+use rustc_middle::query::queries::mir_borrowck::ProvidedValue;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::Providers;
+use rustc_session::Session;
+use rustc_span::def_id::LocalDefId;
+use stable_mir::mir::borrowck::{self as stable, BorrowckFacts, Loan, Point, PointKind};
+use stable_mir::mir::dataflow::ProgramPoint;
+use stable_mir::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// Whether the next compilation session collects the borrow checker facts.
+static COLLECT_BORROWCK_FACTS: AtomicBool = AtomicBool::new(false);
+
+/// The borrow checker facts collected by a compilation session.
+///
+/// The lifetime of the facts is erased since a query provider can't store anything that refers
+/// to the type context. They are only read with the type context of their session.
+struct SessionFacts {
+    facts: FxHashMap<LocalDefId, BodyWithBorrowckFacts<'static>>,
+}
+
+// SAFETY: The facts aren't `Send` since they contain `Rc`s, which aren't shared with anything
+// else. [override_queries] refuses to collect them with parallel frontend threads, so they are
+// only created and read by the thread that runs the compiler.
+unsafe impl Send for SessionFacts {}
+
+/// The borrow checker facts of the compilation sessions that are running.
+static SESSION_FACTS: LazyLock<Mutex<FxHashMap<SessionKey, SessionFacts>>> =
+    LazyLock::new(Default::default);
+
+/// Makes the compiler keep the borrow checker facts of the local functions, so they can be
+/// retrieved with [stable_mir::CrateItem::borrowck_facts].
+///
+/// The facts are collected by the next compilation session that is configured with
+/// [override_queries](super::override_queries), which [run!](crate::run) does, and this has to be
+/// called again for later sessions. Collecting the facts is not supported with parallel frontend
+/// threads, i.e. `-Z threads` greater than 1.
+pub fn collect_borrowck_facts() {
+    COLLECT_BORROWCK_FACTS.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether the next compilation session collects the borrow checker facts.
+pub(crate) fn collects_borrowck_facts() -> bool {
+    COLLECT_BORROWCK_FACTS.load(Ordering::Relaxed)
+}
+
+pub(crate) fn override_queries(session: &Session, providers: &mut Providers) {
+    if !COLLECT_BORROWCK_FACTS.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    if session.threads() > 1 {
+        session
+            .dcx()
+            .fatal("borrow checker facts can't be collected with parallel frontend threads");
+    }
+    let session_facts = SessionFacts { facts: FxHashMap::default() };
+    SESSION_FACTS.lock().unwrap().insert(SessionKey::new(session), session_facts);
+    providers.mir_borrowck = mir_borrowck;
+}
+
+pub(crate) fn end_session(session: SessionKey) {
+    SESSION_FACTS.lock().unwrap().remove(&session);
+}
+
+/// Borrow checks the body once, like the default provider, and keeps its facts.
+fn mir_borrowck(tcx: TyCtxt<'_>, def_id: LocalDefId) -> ProvidedValue<'_> {
+    let (result, facts) =
+        consumers::mir_borrowck_with_facts(tcx, def_id, ConsumerOptions::PoloniusInputFacts);
+    if let Some(facts) = facts {
+        // SAFETY: The facts are only read by `borrowck_facts`, with the type context of the
+        // session.
+        let facts: BodyWithBorrowckFacts<'static> = unsafe { std::mem::transmute(facts) };
+        let mut sessions = SESSION_FACTS.lock().unwrap();
+        sessions.get_mut(&SessionKey::new(tcx.sess)).unwrap().facts.insert(def_id, facts);
+    }
+    result
+}
+
+/// Converts the borrow checker facts of a local function into their stable form.
+pub(crate) fn borrowck_facts<'tcx>(
+    tables: &mut Tables<'tcx>,
+    def_id: LocalDefId,
+) -> Result<BorrowckFacts, Error> {
+    let session = SessionKey::new(tables.tcx.sess);
+    // Don't hold the lock while converting the facts, which may run queries.
+    let facts = SESSION_FACTS
+        .lock()
+        .unwrap()
+        .get_mut(&session)
+        .and_then(|session_facts| session_facts.facts.remove(&def_id));
+    let Some(facts) = facts else {
+        return Err(Error::new(format!(
+            "No borrow checker facts were collected for `{}`",
+            tables.tcx.def_path_str(def_id)
+        )));
+    };
+    // SAFETY: The facts were created by the type context of `tables`, see `SessionFacts`.
+    let tcx_facts: &BodyWithBorrowckFacts<'tcx> = unsafe { std::mem::transmute(&facts) };
+    let result = if tcx_facts.body.coroutine.is_some() {
+        Err(Error::new(format!(
+            "Borrow checker facts of coroutines are not supported: `{}`",
+            tables.tcx.def_path_str(def_id)
+        )))
+    } else {
+        Ok(stable_facts(tcx_facts, tables))
+    };
+    if let Some(session_facts) = SESSION_FACTS.lock().unwrap().get_mut(&session) {
+        session_facts.facts.insert(def_id, facts);
+    }
+    result
+}
+
+/// Converts the body that was borrow checked. Its false edges, which are only used by the borrow
+/// checker, are converted into gotos to their real targets.
+fn stable_body(body: &mir::Body<'_>, tables: &mut Tables<'_>) -> stable_mir::mir::Body {
+    let mut body = body.clone();
+    for data in body.basic_blocks_mut() {
+        let terminator = data.terminator_mut();
+        if let mir::TerminatorKind::FalseEdge { real_target, .. }
+        | mir::TerminatorKind::FalseUnwind { real_target, .. } = terminator.kind
+        {
+            terminator.kind = mir::TerminatorKind::Goto { target: real_target };
+        }
+    }
+    body.stable(tables)
+}
+
+fn stable_facts(facts: &BodyWithBorrowckFacts<'_>, tables: &mut Tables<'_>) -> BorrowckFacts {
+    let location_table = facts.location_table.as_ref().unwrap();
+    let input = facts.input_facts.as_ref().unwrap();
+    let point = |index| {
+        let (location, kind) = match location_table.to_location(index) {
+            RichLocation::Start(location) => (location, PointKind::Start),
+            RichLocation::Mid(location) => (location, PointKind::Mid),
+        };
+        Point { location: program_point(location), kind }
+    };
+    let loans = facts
+        .borrow_set
+        .location_map
+        .values()
+        .map(|borrow| Loan {
+            kind: borrow.kind.stable(tables),
+            region: borrow.region.as_usize(),
+            borrowed_place: borrow.borrowed_place.stable(tables),
+            assigned_place: borrow.assigned_place.stable(tables),
+            reserve_location: program_point(borrow.reserve_location),
+            activation: match borrow.activation_location {
+                TwoPhaseActivation::NotTwoPhase => stable::TwoPhaseActivation::NotTwoPhase,
+                TwoPhaseActivation::NotActivated => stable::TwoPhaseActivation::NotActivated,
+                TwoPhaseActivation::ActivatedAt(location) => {
+                    stable::TwoPhaseActivation::ActivatedAt(program_point(location))
+                }
+            },
+        })
+        .collect();
+    BorrowckFacts {
+        body: stable_body(&facts.body, tables),
+        loans,
+        universal_region: input.universal_region.iter().map(|region| region.as_usize()).collect(),
+        loan_issued_at: input
+            .loan_issued_at
+            .iter()
+            .map(|&(region, loan, index)| (region.as_usize(), loan.as_usize(), point(index)))
+            .collect(),
+        loan_killed_at: input
+            .loan_killed_at
+            .iter()
+            .map(|&(loan, index)| (loan.as_usize(), point(index)))
+            .collect(),
+        loan_invalidated_at: input
+            .loan_invalidated_at
+            .iter()
+            .map(|&(index, loan)| (point(index), loan.as_usize()))
+            .collect(),
+        subset_base: input
+            .subset_base
+            .iter()
+            .map(|&(sup, sub, index)| (sup.as_usize(), sub.as_usize(), point(index)))
+            .collect(),
+        known_placeholder_subset: input
+            .known_placeholder_subset
+            .iter()
+            .map(|&(sup, sub)| (sup.as_usize(), sub.as_usize()))
+            .collect(),
+        cfg_edge: input.cfg_edge.iter().map(|&(from, to)| (point(from), point(to))).collect(),
+        var_used_at: input
+            .var_used_at
+            .iter()
+            .map(|&(local, index)| (local.as_usize(), point(index)))
+            .collect(),
+        var_defined_at: input
+            .var_defined_at
+            .iter()
+            .map(|&(local, index)| (local.as_usize(), point(index)))
+            .collect(),
+        var_dropped_at: input
+            .var_dropped_at
+            .iter()
+            .map(|&(local, index)| (local.as_usize(), point(index)))
+            .collect(),
+        use_of_var_derefs_origin: input
+            .use_of_var_derefs_origin
+            .iter()
+            .map(|&(local, region)| (local.as_usize(), region.as_usize()))
+            .collect(),
+        drop_of_var_derefs_origin: input
+            .drop_of_var_derefs_origin
+            .iter()
+            .map(|&(local, region)| (local.as_usize(), region.as_usize()))
+            .collect(),
+    }
+}
+
+fn program_point(location: Location) -> ProgramPoint {
+    ProgramPoint { block: location.block.as_usize(), statement_index: location.statement_index }
+}
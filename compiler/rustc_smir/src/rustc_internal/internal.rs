@@ -1,21 +1,29 @@
 //! Module containing the translation from stable mir constructs to the rustc counterpart.
 //!
 //! This module will only include a few constructs to allow users to invoke internal rustc APIs
-//! due to incomplete stable coverage.
+//! due to incomplete stable coverage, and the parts of MIR bodies needed to hand a transformed
+//! body back to the compiler.
 
 // Prefer importing stable_mir over internal rustc constructs to make this file more readable.
 use crate::rustc_smir::Tables;
+use rustc_middle::mir as rustc_mir;
 use rustc_middle::ty::{self as rustc_ty, Ty as InternalTy, TyCtxt};
 use rustc_span::Symbol;
+use rustc_target::abi::FieldIdx;
 use stable_mir::abi::Layout;
 use stable_mir::mir::alloc::AllocId;
 use stable_mir::mir::mono::{Instance, MonoItem, StaticDef};
-use stable_mir::mir::{Mutability, Safety};
+use stable_mir::mir::{
+    AggregateKind, AssertMessage, BinOp, BorrowKind, CastKind, Constant, CopyNonOverlapping,
+    CoroutineDesugaring, CoroutineKind, CoroutineSource, FakeReadCause, MutBorrowKind, Mutability,
+    NonDivergingIntrinsic, NullOp, Operand, Place, PointerCoercion, ProjectionElem, RetagKind,
+    Rvalue, Safety, SwitchTargets, UnOp,
+};
 use stable_mir::ty::{
     Abi, AdtDef, Binder, BoundRegionKind, BoundTyKind, BoundVariableKind, ClosureKind, Const,
     DynKind, ExistentialPredicate, ExistentialProjection, ExistentialTraitRef, FloatTy, FnSig,
-    GenericArgKind, GenericArgs, IndexedVal, IntTy, Movability, Region, RigidTy, Span, TermKind,
-    TraitRef, Ty, UintTy, VariantDef, VariantIdx,
+    GenericArgKind, GenericArgs, IndexedVal, IntTy, Movability, ParamEnv, Region, RigidTy, Span,
+    TermKind, TraitRef, Ty, UintTy, VariantDef, VariantIdx,
 };
 use stable_mir::{CrateItem, CrateNum, DefId};
 
@@ -405,6 +413,17 @@ fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<
     }
 }
 
+impl RustcInternal for ParamEnv {
+    type T<'tcx> = rustc_ty::ParamEnv<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            ParamEnv::RevealAll => rustc_ty::ParamEnv::reveal_all(),
+            ParamEnv::Item(def_id) => tcx.param_env(def_id.internal(tables, tcx)),
+        }
+    }
+}
+
 impl RustcInternal for AllocId {
     type T<'tcx> = rustc_middle::mir::interpret::AllocId;
     fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
@@ -492,6 +511,415 @@ fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<
     }
 }
 
+impl RustcInternal for Place {
+    type T<'tcx> = rustc_mir::Place<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        rustc_mir::Place {
+            local: rustc_mir::Local::from_usize(self.local),
+            projection: tcx.mk_place_elems_from_iter(
+                self.projection.iter().map(|elem| elem.internal(tables, tcx)),
+            ),
+        }
+    }
+}
+
+impl RustcInternal for ProjectionElem {
+    type T<'tcx> = rustc_mir::PlaceElem<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            ProjectionElem::Deref => rustc_mir::ProjectionElem::Deref,
+            ProjectionElem::Field(idx, ty) => rustc_mir::ProjectionElem::Field(
+                FieldIdx::from_usize(*idx),
+                ty.internal(tables, tcx),
+            ),
+            ProjectionElem::Index(local) => {
+                rustc_mir::ProjectionElem::Index(rustc_mir::Local::from_usize(*local))
+            }
+            ProjectionElem::ConstantIndex { offset, min_length, from_end } => {
+                rustc_mir::ProjectionElem::ConstantIndex {
+                    offset: *offset,
+                    min_length: *min_length,
+                    from_end: *from_end,
+                }
+            }
+            ProjectionElem::Subslice { from, to, from_end } => {
+                rustc_mir::ProjectionElem::Subslice { from: *from, to: *to, from_end: *from_end }
+            }
+            // The name of the variant is only used for printing MIR, see the conversion to stable
+            // MIR.
+            ProjectionElem::Downcast(idx) => {
+                rustc_mir::ProjectionElem::Downcast(None, idx.internal(tables, tcx))
+            }
+            ProjectionElem::OpaqueCast(ty) => {
+                rustc_mir::ProjectionElem::OpaqueCast(ty.internal(tables, tcx))
+            }
+            ProjectionElem::Subtype(ty) => {
+                rustc_mir::ProjectionElem::Subtype(ty.internal(tables, tcx))
+            }
+        }
+    }
+}
+
+impl RustcInternal for Operand {
+    type T<'tcx> = rustc_mir::Operand<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            Operand::Copy(place) => rustc_mir::Operand::Copy(place.internal(tables, tcx)),
+            Operand::Move(place) => rustc_mir::Operand::Move(place.internal(tables, tcx)),
+            Operand::Constant(constant) => {
+                rustc_mir::Operand::Constant(Box::new(constant.internal(tables, tcx)))
+            }
+        }
+    }
+}
+
+impl RustcInternal for Constant {
+    type T<'tcx> = rustc_mir::ConstOperand<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        rustc_mir::ConstOperand {
+            span: self.span.internal(tables, tcx),
+            user_ty: self.user_ty.map(rustc_ty::UserTypeAnnotationIndex::from_usize),
+            const_: self.literal.internal(tables, tcx),
+        }
+    }
+}
+
+impl RustcInternal for Rvalue {
+    type T<'tcx> = rustc_mir::Rvalue<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            Rvalue::AddressOf(mutability, place) => rustc_mir::Rvalue::AddressOf(
+                mutability.internal(tables, tcx),
+                place.internal(tables, tcx),
+            ),
+            Rvalue::Aggregate(kind, operands) => rustc_mir::Rvalue::Aggregate(
+                Box::new(kind.internal(tables, tcx)),
+                operands.iter().map(|op| op.internal(tables, tcx)).collect(),
+            ),
+            Rvalue::BinaryOp(bin_op, lhs, rhs) => rustc_mir::Rvalue::BinaryOp(
+                bin_op.internal(tables, tcx),
+                Box::new((lhs.internal(tables, tcx), rhs.internal(tables, tcx))),
+            ),
+            Rvalue::Cast(kind, op, ty) => rustc_mir::Rvalue::Cast(
+                kind.internal(tables, tcx),
+                op.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::CheckedBinaryOp(bin_op, lhs, rhs) => rustc_mir::Rvalue::CheckedBinaryOp(
+                bin_op.internal(tables, tcx),
+                Box::new((lhs.internal(tables, tcx), rhs.internal(tables, tcx))),
+            ),
+            Rvalue::CopyForDeref(place) => {
+                rustc_mir::Rvalue::CopyForDeref(place.internal(tables, tcx))
+            }
+            Rvalue::Discriminant(place) => {
+                rustc_mir::Rvalue::Discriminant(place.internal(tables, tcx))
+            }
+            Rvalue::Len(place) => rustc_mir::Rvalue::Len(place.internal(tables, tcx)),
+            Rvalue::Ref(region, kind, place) => rustc_mir::Rvalue::Ref(
+                region.internal(tables, tcx),
+                kind.internal(tables, tcx),
+                place.internal(tables, tcx),
+            ),
+            Rvalue::Repeat(op, count) => {
+                rustc_mir::Rvalue::Repeat(op.internal(tables, tcx), ty_const(count, tables, tcx))
+            }
+            Rvalue::ShallowInitBox(op, ty) => rustc_mir::Rvalue::ShallowInitBox(
+                op.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::ThreadLocalRef(item) => {
+                rustc_mir::Rvalue::ThreadLocalRef(item.internal(tables, tcx))
+            }
+            Rvalue::NullaryOp(null_op, ty) => rustc_mir::Rvalue::NullaryOp(
+                null_op.internal(tables, tcx),
+                ty.internal(tables, tcx),
+            ),
+            Rvalue::UnaryOp(un_op, op) => {
+                rustc_mir::Rvalue::UnaryOp(un_op.internal(tables, tcx), op.internal(tables, tcx))
+            }
+            Rvalue::Use(op) => rustc_mir::Rvalue::Use(op.internal(tables, tcx)),
+        }
+    }
+}
+
+impl RustcInternal for AggregateKind {
+    type T<'tcx> = rustc_mir::AggregateKind<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            AggregateKind::Array(ty) => rustc_mir::AggregateKind::Array(ty.internal(tables, tcx)),
+            AggregateKind::Tuple => rustc_mir::AggregateKind::Tuple,
+            AggregateKind::Adt(def, variant, args, user_ty, field) => {
+                rustc_mir::AggregateKind::Adt(
+                    def.0.internal(tables, tcx),
+                    variant.internal(tables, tcx),
+                    args.internal(tables, tcx),
+                    user_ty.map(rustc_ty::UserTypeAnnotationIndex::from_usize),
+                    field.map(FieldIdx::from_usize),
+                )
+            }
+            AggregateKind::Closure(def, args) => rustc_mir::AggregateKind::Closure(
+                def.0.internal(tables, tcx),
+                args.internal(tables, tcx),
+            ),
+            AggregateKind::Coroutine(def, args, _movability) => {
+                rustc_mir::AggregateKind::Coroutine(
+                    def.0.internal(tables, tcx),
+                    args.internal(tables, tcx),
+                )
+            }
+        }
+    }
+}
+
+impl RustcInternal for BorrowKind {
+    type T<'tcx> = rustc_mir::BorrowKind;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            BorrowKind::Shared => rustc_mir::BorrowKind::Shared,
+            BorrowKind::Fake => rustc_mir::BorrowKind::Fake,
+            BorrowKind::Mut { kind } => rustc_mir::BorrowKind::Mut {
+                kind: match kind {
+                    MutBorrowKind::Default => rustc_mir::MutBorrowKind::Default,
+                    MutBorrowKind::TwoPhaseBorrow => rustc_mir::MutBorrowKind::TwoPhaseBorrow,
+                    MutBorrowKind::ClosureCapture => rustc_mir::MutBorrowKind::ClosureCapture,
+                },
+            },
+        }
+    }
+}
+
+impl RustcInternal for CastKind {
+    type T<'tcx> = rustc_mir::CastKind;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            CastKind::PointerExposeAddress => rustc_mir::CastKind::PointerExposeAddress,
+            CastKind::PointerFromExposedAddress => rustc_mir::CastKind::PointerFromExposedAddress,
+            CastKind::PointerCoercion(coercion) => {
+                rustc_mir::CastKind::PointerCoercion(coercion.internal(tables, tcx))
+            }
+            CastKind::DynStar => rustc_mir::CastKind::DynStar,
+            CastKind::IntToInt => rustc_mir::CastKind::IntToInt,
+            CastKind::FloatToInt => rustc_mir::CastKind::FloatToInt,
+            CastKind::FloatToFloat => rustc_mir::CastKind::FloatToFloat,
+            CastKind::IntToFloat => rustc_mir::CastKind::IntToFloat,
+            CastKind::PtrToPtr => rustc_mir::CastKind::PtrToPtr,
+            CastKind::FnPtrToPtr => rustc_mir::CastKind::FnPtrToPtr,
+            CastKind::Transmute => rustc_mir::CastKind::Transmute,
+        }
+    }
+}
+
+impl RustcInternal for PointerCoercion {
+    type T<'tcx> = rustc_ty::adjustment::PointerCoercion;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        use rustc_ty::adjustment::PointerCoercion as InternalCoercion;
+        match self {
+            PointerCoercion::ReifyFnPointer => InternalCoercion::ReifyFnPointer,
+            PointerCoercion::UnsafeFnPointer => InternalCoercion::UnsafeFnPointer,
+            PointerCoercion::ClosureFnPointer(safety) => {
+                InternalCoercion::ClosureFnPointer(safety.internal(tables, tcx))
+            }
+            PointerCoercion::MutToConstPointer => InternalCoercion::MutToConstPointer,
+            PointerCoercion::ArrayToPointer => InternalCoercion::ArrayToPointer,
+            PointerCoercion::Unsize => InternalCoercion::Unsize,
+        }
+    }
+}
+
+impl RustcInternal for NullOp {
+    type T<'tcx> = rustc_mir::NullOp<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            NullOp::SizeOf => rustc_mir::NullOp::SizeOf,
+            NullOp::AlignOf => rustc_mir::NullOp::AlignOf,
+            NullOp::OffsetOf(indices) => rustc_mir::NullOp::OffsetOf(tcx.mk_offset_of_from_iter(
+                indices.iter().map(|(variant, field)| {
+                    (variant.internal(tables, tcx), FieldIdx::from_usize(*field))
+                }),
+            )),
+            NullOp::DebugAssertions => rustc_mir::NullOp::DebugAssertions,
+        }
+    }
+}
+
+impl RustcInternal for BinOp {
+    type T<'tcx> = rustc_mir::BinOp;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            BinOp::Add => rustc_mir::BinOp::Add,
+            BinOp::AddUnchecked => rustc_mir::BinOp::AddUnchecked,
+            BinOp::Sub => rustc_mir::BinOp::Sub,
+            BinOp::SubUnchecked => rustc_mir::BinOp::SubUnchecked,
+            BinOp::Mul => rustc_mir::BinOp::Mul,
+            BinOp::MulUnchecked => rustc_mir::BinOp::MulUnchecked,
+            BinOp::Div => rustc_mir::BinOp::Div,
+            BinOp::Rem => rustc_mir::BinOp::Rem,
+            BinOp::BitXor => rustc_mir::BinOp::BitXor,
+            BinOp::BitAnd => rustc_mir::BinOp::BitAnd,
+            BinOp::BitOr => rustc_mir::BinOp::BitOr,
+            BinOp::Shl => rustc_mir::BinOp::Shl,
+            BinOp::ShlUnchecked => rustc_mir::BinOp::ShlUnchecked,
+            BinOp::Shr => rustc_mir::BinOp::Shr,
+            BinOp::ShrUnchecked => rustc_mir::BinOp::ShrUnchecked,
+            BinOp::Eq => rustc_mir::BinOp::Eq,
+            BinOp::Lt => rustc_mir::BinOp::Lt,
+            BinOp::Le => rustc_mir::BinOp::Le,
+            BinOp::Ne => rustc_mir::BinOp::Ne,
+            BinOp::Ge => rustc_mir::BinOp::Ge,
+            BinOp::Gt => rustc_mir::BinOp::Gt,
+            BinOp::Offset => rustc_mir::BinOp::Offset,
+        }
+    }
+}
+
+impl RustcInternal for UnOp {
+    type T<'tcx> = rustc_mir::UnOp;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            UnOp::Not => rustc_mir::UnOp::Not,
+            UnOp::Neg => rustc_mir::UnOp::Neg,
+        }
+    }
+}
+
+impl RustcInternal for RetagKind {
+    type T<'tcx> = rustc_mir::RetagKind;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            RetagKind::FnEntry => rustc_mir::RetagKind::FnEntry,
+            RetagKind::TwoPhase => rustc_mir::RetagKind::TwoPhase,
+            RetagKind::Raw => rustc_mir::RetagKind::Raw,
+            RetagKind::Default => rustc_mir::RetagKind::Default,
+        }
+    }
+}
+
+impl RustcInternal for FakeReadCause {
+    type T<'tcx> = rustc_mir::FakeReadCause;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        // The closure that is matched or bound is only kept as an opaque value in stable MIR.
+        match self {
+            FakeReadCause::ForMatchGuard => rustc_mir::FakeReadCause::ForMatchGuard,
+            FakeReadCause::ForMatchedPlace(_) => rustc_mir::FakeReadCause::ForMatchedPlace(None),
+            FakeReadCause::ForGuardBinding => rustc_mir::FakeReadCause::ForGuardBinding,
+            FakeReadCause::ForLet(_) => rustc_mir::FakeReadCause::ForLet(None),
+            FakeReadCause::ForIndex => rustc_mir::FakeReadCause::ForIndex,
+        }
+    }
+}
+
+impl RustcInternal for NonDivergingIntrinsic {
+    type T<'tcx> = rustc_mir::NonDivergingIntrinsic<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            NonDivergingIntrinsic::Assume(op) => {
+                rustc_mir::NonDivergingIntrinsic::Assume(op.internal(tables, tcx))
+            }
+            NonDivergingIntrinsic::CopyNonOverlapping(CopyNonOverlapping { src, dst, count }) => {
+                rustc_mir::NonDivergingIntrinsic::CopyNonOverlapping(
+                    rustc_mir::CopyNonOverlapping {
+                        src: src.internal(tables, tcx),
+                        dst: dst.internal(tables, tcx),
+                        count: count.internal(tables, tcx),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl RustcInternal for AssertMessage {
+    type T<'tcx> = rustc_mir::AssertMessage<'tcx>;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            AssertMessage::BoundsCheck { len, index } => rustc_mir::AssertKind::BoundsCheck {
+                len: len.internal(tables, tcx),
+                index: index.internal(tables, tcx),
+            },
+            AssertMessage::Overflow(bin_op, lhs, rhs) => rustc_mir::AssertKind::Overflow(
+                bin_op.internal(tables, tcx),
+                lhs.internal(tables, tcx),
+                rhs.internal(tables, tcx),
+            ),
+            AssertMessage::OverflowNeg(op) => {
+                rustc_mir::AssertKind::OverflowNeg(op.internal(tables, tcx))
+            }
+            AssertMessage::DivisionByZero(op) => {
+                rustc_mir::AssertKind::DivisionByZero(op.internal(tables, tcx))
+            }
+            AssertMessage::RemainderByZero(op) => {
+                rustc_mir::AssertKind::RemainderByZero(op.internal(tables, tcx))
+            }
+            AssertMessage::ResumedAfterReturn(kind) => {
+                rustc_mir::AssertKind::ResumedAfterReturn(kind.internal(tables, tcx))
+            }
+            AssertMessage::ResumedAfterPanic(kind) => {
+                rustc_mir::AssertKind::ResumedAfterPanic(kind.internal(tables, tcx))
+            }
+            AssertMessage::MisalignedPointerDereference { required, found } => {
+                rustc_mir::AssertKind::MisalignedPointerDereference {
+                    required: required.internal(tables, tcx),
+                    found: found.internal(tables, tcx),
+                }
+            }
+        }
+    }
+}
+
+impl RustcInternal for CoroutineKind {
+    type T<'tcx> = rustc_hir::CoroutineKind;
+
+    fn internal<'tcx>(&self, tables: &mut Tables<'_>, tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        match self {
+            CoroutineKind::Desugared(desugaring, source) => {
+                let desugaring = match desugaring {
+                    CoroutineDesugaring::Async => rustc_hir::CoroutineDesugaring::Async,
+                    CoroutineDesugaring::Gen => rustc_hir::CoroutineDesugaring::Gen,
+                    CoroutineDesugaring::AsyncGen => rustc_hir::CoroutineDesugaring::AsyncGen,
+                };
+                let source = match source {
+                    CoroutineSource::Block => rustc_hir::CoroutineSource::Block,
+                    CoroutineSource::Closure => rustc_hir::CoroutineSource::Closure,
+                    CoroutineSource::Fn => rustc_hir::CoroutineSource::Fn,
+                };
+                rustc_hir::CoroutineKind::Desugared(desugaring, source)
+            }
+            CoroutineKind::Coroutine(movability) => {
+                rustc_hir::CoroutineKind::Coroutine(movability.internal(tables, tcx))
+            }
+        }
+    }
+}
+
+impl RustcInternal for SwitchTargets {
+    type T<'tcx> = rustc_mir::SwitchTargets;
+
+    fn internal<'tcx>(&self, _tables: &mut Tables<'_>, _tcx: TyCtxt<'tcx>) -> Self::T<'tcx> {
+        rustc_mir::SwitchTargets::new(
+            self.branches()
+                .map(|(value, target)| (value, rustc_mir::BasicBlock::from_usize(target))),
+            rustc_mir::BasicBlock::from_usize(self.otherwise()),
+        )
+    }
+}
+
 impl<T> RustcInternal for &T
 where
     T: RustcInternal,
@@ -9,6 +9,8 @@
 use rustc_middle::mir::interpret::AllocId;
 use rustc_middle::ty;
 use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::Providers;
+use rustc_session::Session;
 use rustc_span::def_id::{CrateNum, DefId};
 use rustc_span::Span;
 use scoped_tls::scoped_thread_local;
@@ -21,8 +23,10 @@
 use std::hash::Hash;
 use std::ops::Index;
 
+pub mod borrowck;
 mod internal;
 pub mod pretty;
+pub mod transform;
 
 /// Convert an internal Rust compiler item into its stable counterpart, if one exists.
 ///
@@ -81,6 +85,10 @@ pub fn crate_item(&mut self, did: DefId) -> stable_mir::CrateItem {
         stable_mir::CrateItem(self.create_def_id(did))
     }
 
+    pub fn module_def(&mut self, did: DefId) -> stable_mir::ModuleDef {
+        stable_mir::ModuleDef(self.create_def_id(did))
+    }
+
     pub fn adt_def(&mut self, did: DefId) -> stable_mir::ty::AdtDef {
         stable_mir::ty::AdtDef(self.create_def_id(did))
     }
@@ -203,11 +211,8 @@ pub(crate) fn with_tables<R>(f: impl for<'tcx> FnOnce(&mut Tables<'tcx>) -> R) -
     })
 }
 
-pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
-where
-    F: FnOnce() -> T,
-{
-    let tables = TablesWrapper(RefCell::new(Tables {
+fn new_tables(tcx: TyCtxt<'_>) -> TablesWrapper<'_> {
+    TablesWrapper(RefCell::new(Tables {
         tcx,
         def_ids: IndexMap::default(),
         alloc_ids: IndexMap::default(),
@@ -216,13 +221,71 @@ pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
         instances: IndexMap::default(),
         constants: IndexMap::default(),
         layouts: IndexMap::default(),
-    }));
+    }))
+}
+
+pub fn run<F, T>(tcx: TyCtxt<'_>, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> T,
+{
+    let tables = new_tables(tcx);
     stable_mir::compiler_interface::run(&tables, || init(&tables, f))
 }
 
+/// Like [run], but `f` may also be invoked while another context is running, e.g. from a query
+/// provider that is executed by one of the methods of that context. `f` gets a new context, and
+/// must not use any item of the other one.
+pub(crate) fn run_nested<'tcx, F, T>(tcx: TyCtxt<'tcx>, f: F) -> T
+where
+    F: FnOnce(&TablesWrapper<'tcx>) -> T,
+{
+    let tables = new_tables(tcx);
+    stable_mir::compiler_interface::run_nested(&tables, || {
+        let ptr = &tables as *const _ as *const ();
+        TLV.set(&Cell::new(ptr), || f(&tables))
+    })
+}
+
+/// Overrides the queries that apply the transformations registered with
+/// [transform::register_body_transform], and that collect the borrow checker facts if
+/// [borrowck::collect_borrowck_facts] was called. This has to be set as the `override_queries` of
+/// the compiler configuration by drivers that don't use [run!], and [end_session] has to be
+/// called once the compiler ran.
+pub fn override_queries(session: &Session, providers: &mut Providers) {
+    transform::override_queries(session, providers);
+    borrowck::override_queries(session, providers);
+}
+
+/// Returns whether the next compilation session has to be configured with [override_queries],
+/// i.e., whether MIR body transformations were registered or borrow checker facts are collected.
+pub fn needs_query_overrides() -> bool {
+    transform::has_body_transforms() || borrowck::collects_borrowck_facts()
+}
+
+/// Identifies a compilation session whose queries were overridden by [override_queries].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SessionKey(usize);
+
+impl SessionKey {
+    pub fn new(session: &Session) -> SessionKey {
+        SessionKey(session as *const Session as usize)
+    }
+}
+
+/// Drops the state that [override_queries] kept for a compilation session, like the MIR body
+/// transformations that it applied and the borrow checker facts that it collected.
+pub fn end_session(session: SessionKey) {
+    transform::end_session(session);
+    borrowck::end_session(session);
+}
+
 /// Instantiate and run the compiler with the provided arguments and callback.
 ///
 /// The callback will be invoked after the compiler ran all its analyses, but before code generation.
+/// The MIR transformations registered with [transform::register_body_transform] are applied to
+/// the bodies of the local functions, and are unregistered afterwards.
+/// The borrow checker facts are kept if [borrowck::collect_borrowck_facts] was called, which has
+/// to be called again for later runs.
 /// Note that this macro accepts two different formats for the callback:
 /// 1. An ident that resolves to a function that accepts no argument and returns `ControlFlow<B, C>`
 /// ```ignore(needs-extern-crate)
@@ -323,6 +386,8 @@ pub struct StableMir<B = (), C = (), F = fn($(optional!($with_tcx TyCtxt))?) ->
             args: Vec<String>,
             callback: Option<F>,
             result: Option<ControlFlow<B, C>>,
+            /// The compilation session, once it was created.
+            session: Option<rustc_internal::SessionKey>,
         }
 
         impl<B, C, F> StableMir<B, C, F>
@@ -333,7 +398,7 @@ impl<B, C, F> StableMir<B, C, F>
         {
             /// Creates a new `StableMir` instance, with given test_function and arguments.
             pub fn new(args: Vec<String>, callback: F) -> Self {
-                StableMir { args, callback: Some(callback), result: None }
+                StableMir { args, callback: Some(callback), result: None, session: None }
             }
 
             /// Runs the compiler against given target and tests it with `test_function`
@@ -341,6 +406,9 @@ pub fn run(&mut self) -> Result<C, CompilerError<B>> {
                 let compiler_result = rustc_driver::catch_fatal_errors(|| {
                     RunCompiler::new(&self.args.clone(), self).run()
                 });
+                if let Some(session) = self.session.take() {
+                    rustc_internal::end_session(session);
+                }
                 match (compiler_result, self.result.take()) {
                     (Ok(Ok(())), Some(ControlFlow::Continue(value))) => Ok(value),
                     (Ok(Ok(())), Some(ControlFlow::Break(value))) => {
@@ -365,6 +433,26 @@ impl<B, C, F> Callbacks for StableMir<B, C, F>
             C: Send,
             F: FnOnce($(optional!($with_tcx TyCtxt))?) -> ControlFlow<B, C> + Send,
         {
+            /// Called before creating the compiler instance. Installs the query overrides that
+            /// apply the registered MIR body transformations and collect borrow checker facts,
+            /// if they are needed.
+            fn config(&mut self, config: &mut interface::Config) {
+                if rustc_internal::needs_query_overrides() {
+                    config.override_queries = Some(rustc_internal::override_queries);
+                }
+            }
+
+            /// Called after parsing the crate root, before the queries are overridden. Records
+            /// the session, so that the state kept for it can be dropped once the compiler ran.
+            fn after_crate_root_parsing<'tcx>(
+                &mut self,
+                compiler: &interface::Compiler,
+                _queries: &'tcx Queries<'tcx>,
+            ) -> Compilation {
+                self.session = Some(rustc_internal::SessionKey::new(&compiler.sess));
+                Compilation::Continue
+            }
+
             /// Called after analysis. Return value instructs the compiler whether to
             /// continue the compilation afterwards (defaults to `Compilation::Continue`)
             fn after_analysis<'tcx>(
@@ -1,7 +1,23 @@
 use std::io;
 
-use super::run;
-use rustc_middle::ty::TyCtxt;
+use super::{run, with_tables};
+use crate::rustc_smir::Stable;
+use rustc_data_structures::fx::FxIndexSet;
+use rustc_middle::ty::print::with_no_trimmed_paths;
+use rustc_middle::ty::{InstanceDef, TyCtxt};
+use serde::Serialize;
+use stable_mir::mir::alloc::{AllocId, GlobalAlloc};
+use stable_mir::mir::pretty::pretty_ty;
+use stable_mir::mir::visit::Location;
+use stable_mir::mir::{Body, MirVisitor};
+use stable_mir::snapshot::Snapshot;
+use stable_mir::ty::{
+    Allocation, Const, ConstantKind, ExistentialPredicate, GenericArgs, LineInfo, RigidTy, Span,
+    Ty, TyKind,
+};
+use stable_mir::visitor::{Visitable, Visitor};
+use stable_mir::{CrateDef, CrateItem, DefId, Filename, ItemKind};
+use std::ops::ControlFlow;
 
 pub fn write_smir_pretty<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io::Result<()> {
     writeln!(
@@ -18,3 +34,301 @@ pub fn write_smir_pretty<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io
     });
     Ok(())
 }
+
+/// The version of the format written by [`write_smir_json`]. It has to be increased whenever
+/// the serialization of one of the stable MIR types changes.
+const MIR_JSON_VERSION: u32 = 2;
+
+/// The MIR bodies of all local items, as written by `-Zunpretty=mir-json`.
+///
+/// The bodies are serialized as stable MIR [`Body`]s. Types, spans, definitions and allocations
+/// are only referred to by their index in the bodies, the indices are resolved by the `types`,
+/// `spans`, `defs` and `allocs` tables. Constants are written in full, so the `id` of a constant
+/// only tells whether two constants are the same.
+#[derive(Serialize)]
+struct MirJson {
+    version: u32,
+    crate_name: String,
+    items: Vec<ItemJson>,
+    types: Vec<TyJson>,
+    spans: Vec<SpanJson>,
+    defs: Vec<DefJson>,
+    allocs: Vec<AllocJson>,
+}
+
+#[derive(Serialize)]
+struct ItemJson {
+    id: CrateItem,
+    name: String,
+    kind: ItemKind,
+    span: Span,
+    body: Body,
+    /// The source scopes of the body, indexed by the `scope` of its `SourceInfo`s.
+    source_scopes: Vec<SourceScopeJson>,
+}
+
+#[derive(Serialize)]
+struct SourceScopeJson {
+    span: Span,
+    parent_scope: Option<u32>,
+    /// The function this scope was inlined from, and the span of the inlined call.
+    inlined: Option<(String, Span)>,
+}
+
+#[derive(Serialize)]
+struct TyJson {
+    id: Ty,
+    /// The type as it is written in the textual stable MIR output.
+    name: String,
+    kind: TyKind,
+}
+
+#[derive(Serialize)]
+struct SpanJson {
+    id: Span,
+    file: Filename,
+    #[serde(flatten)]
+    lines: LineInfo,
+}
+
+#[derive(Serialize)]
+struct DefJson {
+    id: DefId,
+    /// The fully qualified path of the definition, e.g. `std::vec::Vec`.
+    path: String,
+    /// The kind of the definition, e.g. `function` or `struct`.
+    kind: &'static str,
+    /// The name of the crate the definition is declared in.
+    krate: String,
+}
+
+#[derive(Serialize)]
+struct AllocJson {
+    id: AllocId,
+    alloc: GlobalAllocJson,
+}
+
+/// A [`GlobalAlloc`], with the definitions it refers to resolved by the `defs` table.
+#[derive(Serialize)]
+enum GlobalAllocJson {
+    /// A function pointer to the instance `name` of the function `def`.
+    Function {
+        name: String,
+        def: DefId,
+    },
+    /// A vtable of `ty` for the trait `trait_def`, which is `None` for auto traits.
+    VTable {
+        ty: Ty,
+        trait_def: Option<DefId>,
+    },
+    Static {
+        def: DefId,
+    },
+    Memory(Allocation),
+}
+
+/// Writes the MIR bodies of all local items as JSON, see [`MirJson`].
+pub fn write_smir_json<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io::Result<()> {
+    let json = run(tcx, || {
+        let mut collector = Collector::default();
+        let mut items = vec![];
+        for item in stable_mir::all_local_items() {
+            let body = item.body();
+            let span = item.span();
+            let source_scopes = source_scopes(item);
+            collector.visit_body(&body);
+            collector.defs.insert(item.def_id());
+            collector.spans.insert(span);
+            for scope in &source_scopes {
+                collector.spans.insert(scope.span);
+                if let Some((_, call_site)) = scope.inlined {
+                    collector.spans.insert(call_site);
+                }
+            }
+            items.push(ItemJson {
+                id: item,
+                name: item.name(),
+                kind: item.kind(),
+                span,
+                body,
+                source_scopes,
+            });
+        }
+
+        let types = collector
+            .types
+            .into_iter()
+            .map(|ty| TyJson { id: ty, name: pretty_ty(ty.kind()), kind: ty.kind() })
+            .collect();
+        let spans = collector
+            .spans
+            .into_iter()
+            .map(|span| SpanJson { id: span, file: span.get_filename(), lines: span.get_lines() })
+            .collect();
+        let defs = collector
+            .defs
+            .into_iter()
+            .map(|def| {
+                with_tables(|tables| {
+                    let def_id = tables[def];
+                    DefJson {
+                        id: def,
+                        path: with_no_trimmed_paths!(tables.tcx.def_path_str(def_id)),
+                        kind: tables.tcx.def_descr(def_id),
+                        krate: tables.tcx.crate_name(def_id.krate).to_string(),
+                    }
+                })
+            })
+            .collect();
+        let crate_name = stable_mir::local_crate().name;
+        MirJson {
+            version: MIR_JSON_VERSION,
+            crate_name,
+            items,
+            types,
+            spans,
+            defs,
+            allocs: collector.allocs,
+        }
+    })
+    .map_err(|e| io::Error::other(e.to_string()))?;
+
+    serde_json::to_writer_pretty(&mut *w, &json)?;
+    writeln!(w)
+}
+
+/// Writes a snapshot of the stable MIR of the local crate, see [`Snapshot`].
+pub fn write_smir_snapshot<'tcx, W: io::Write>(tcx: TyCtxt<'tcx>, w: &mut W) -> io::Result<()> {
+    let snapshot = run(tcx, Snapshot::take).map_err(|e| io::Error::other(e.to_string()))?;
+    snapshot.write(&mut *w).map_err(|e| io::Error::other(e.to_string()))?;
+    writeln!(w)
+}
+
+/// Returns the source scopes of the body of `item`. These are not part of the stable MIR, so
+/// they are taken from the body it was built from.
+fn source_scopes(item: CrateItem) -> Vec<SourceScopeJson> {
+    with_tables(|tables| {
+        let def_id = tables[item.0];
+        let body = tables.tcx.instance_mir(InstanceDef::Item(def_id));
+        body.source_scopes
+            .iter()
+            .map(|scope| SourceScopeJson {
+                span: scope.span.stable(tables),
+                parent_scope: scope.parent_scope.map(|parent| parent.as_u32()),
+                inlined: scope.inlined.map(|(instance, call_site)| {
+                    (tables.tcx.def_path_str(instance.def_id()), call_site.stable(tables))
+                }),
+            })
+            .collect()
+    })
+}
+
+/// Collects the types, spans, definitions and allocations used in MIR bodies, including the ones
+/// nested in types and allocations.
+#[derive(Default)]
+struct Collector {
+    types: FxIndexSet<Ty>,
+    spans: FxIndexSet<Span>,
+    defs: FxIndexSet<DefId>,
+    alloc_ids: FxIndexSet<AllocId>,
+    allocs: Vec<AllocJson>,
+}
+
+impl Collector {
+    fn visit_allocation(&mut self, allocation: &Allocation) {
+        for (_, prov) in &allocation.provenance.ptrs {
+            let id = prov.0;
+            if !self.alloc_ids.insert(id) {
+                continue;
+            }
+            let alloc = match GlobalAlloc::from(id) {
+                GlobalAlloc::Function(instance) => {
+                    let def = instance.def.def_id();
+                    self.defs.insert(def);
+                    GlobalAllocJson::Function { name: instance.name(), def }
+                }
+                GlobalAlloc::VTable(ty, trait_ref) => {
+                    let _ = ty.visit(self);
+                    let trait_def = trait_ref.map(|trait_ref| trait_ref.value.def_id.def_id());
+                    self.defs.extend(trait_def);
+                    GlobalAllocJson::VTable { ty, trait_def }
+                }
+                GlobalAlloc::Static(def) => {
+                    self.defs.insert(def.def_id());
+                    GlobalAllocJson::Static { def: def.def_id() }
+                }
+                GlobalAlloc::Memory(allocation) => {
+                    self.visit_allocation(&allocation);
+                    GlobalAllocJson::Memory(allocation)
+                }
+            };
+            self.allocs.push(AllocJson { id, alloc });
+        }
+    }
+
+    fn visit_defs(&mut self, kind: &TyKind) {
+        let def = match kind {
+            TyKind::RigidTy(RigidTy::Adt(def, _)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::Foreign(def)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::FnDef(def, _)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::Closure(def, _)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::Coroutine(def, _, _)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::CoroutineWitness(def, _)) => def.def_id(),
+            TyKind::RigidTy(RigidTy::Dynamic(predicates, _, _)) => {
+                for predicate in predicates {
+                    let def = match &predicate.value {
+                        ExistentialPredicate::Trait(trait_ref) => trait_ref.def_id,
+                        ExistentialPredicate::Projection(projection) => projection.def_id,
+                        ExistentialPredicate::AutoTrait(def) => *def,
+                    };
+                    self.defs.insert(def.def_id());
+                }
+                return;
+            }
+            TyKind::Alias(_, alias) => alias.def_id.def_id(),
+            TyKind::RigidTy(_) | TyKind::Param(_) | TyKind::Bound(..) => return,
+        };
+        self.defs.insert(def);
+    }
+}
+
+impl MirVisitor for Collector {
+    fn visit_ty(&mut self, ty: &Ty, _location: Location) {
+        let _ = ty.visit(self);
+    }
+
+    fn visit_const(&mut self, constant: &Const, _location: Location) {
+        let _ = constant.visit(self);
+    }
+
+    fn visit_args(&mut self, args: &GenericArgs, _location: Location) {
+        let _ = args.visit(self);
+    }
+
+    fn visit_span(&mut self, span: &Span) {
+        self.spans.insert(*span);
+    }
+}
+
+impl Visitor for Collector {
+    type Break = ();
+
+    fn visit_ty(&mut self, ty: &Ty) -> ControlFlow<()> {
+        if !self.types.insert(*ty) {
+            return ControlFlow::Continue(());
+        }
+        self.visit_defs(&ty.kind());
+        ty.super_visit(self)
+    }
+
+    fn visit_const(&mut self, constant: &Const) -> ControlFlow<()> {
+        match constant.kind() {
+            ConstantKind::Allocated(allocation) => self.visit_allocation(allocation),
+            ConstantKind::Unevaluated(uv) => {
+                self.defs.insert(uv.def.def_id());
+            }
+            ConstantKind::Param(_) | ConstantKind::ZeroSized => {}
+        }
+        constant.super_visit(self)
+    }
+}
@@ -0,0 +1,570 @@
+//! Transformations of MIR bodies that are written against StableMIR.
+//!
+//! A tool registers a [BodyTransform] with [register_body_transform] before it runs the compiler,
+//! e.g. with [run!](crate::run). The transformation is then applied to the optimized MIR of every
+//! local function, so the transformed body is the one that gets code generated. This allows
+//! instrumenting code without modifying the compiler.
+
+use super::{run_nested, RustcInternal, SessionKey};
+use crate::rustc_smir::{Stable, Tables};
+use rustc_const_eval::transform::validate::validate_types;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::Providers;
+use rustc_session::Session;
+use rustc_span::def_id::LocalDefId;
+use rustc_span::Symbol;
+use stable_mir::mir::visit::{Location, PlaceContext};
+use stable_mir::mir::{
+    BasicBlock, Body, Local, LocalDecl, MirVisitor, Statement, StatementKind, Terminator,
+    TerminatorKind, UnwindAction, VarDebugInfo, VarDebugInfoContents,
+};
+use stable_mir::{opaque, CrateItem, Error};
+use std::sync::{LazyLock, Mutex};
+
+/// A transformation of the MIR body of a local function, see [register_body_transform].
+pub type BodyTransform = fn(CrateItem, &mut Body);
+
+/// The transformations that are applied by the next compilation session.
+static BODY_TRANSFORMS: Mutex<Vec<BodyTransform>> = Mutex::new(Vec::new());
+
+type OptimizedMir = for<'tcx> fn(TyCtxt<'tcx>, LocalDefId) -> &'tcx mir::Body<'tcx>;
+
+/// The transformations applied by a compilation session, and the `optimized_mir` provider that
+/// they replaced.
+#[derive(Clone)]
+struct SessionTransforms {
+    transforms: Vec<BodyTransform>,
+    default_optimized_mir: OptimizedMir,
+}
+
+/// The transformations of the compilation sessions that are running.
+static SESSION_TRANSFORMS: LazyLock<Mutex<FxHashMap<SessionKey, SessionTransforms>>> =
+    LazyLock::new(Default::default);
+
+/// Registers a transformation of the optimized MIR of the local functions, which changes the code
+/// that is generated for them.
+///
+/// Transformations are applied in the order they were registered. Each body is transformed in a
+/// new StableMIR context, so a transformation must not use items of another context, e.g. ones
+/// that were retrieved by the callback of [run!](crate::run).
+///
+/// The transformations are applied by the next compilation session that is configured with
+/// [override_queries](super::override_queries), which [run!](crate::run) does, and have to be
+/// registered again for later sessions. Incremental compilation doesn't track them, so it should
+/// not be used when they change.
+///
+/// A transformed body that is invalid, e.g. one that is ill-typed, refers to a block or a local
+/// that doesn't exist, or changes the signature of the function, is reported as an error and the
+/// original body is used instead.
+pub fn register_body_transform(transform: BodyTransform) {
+    BODY_TRANSFORMS.lock().unwrap().push(transform);
+}
+
+/// Returns whether transformations were registered for the next compilation session.
+pub(crate) fn has_body_transforms() -> bool {
+    !BODY_TRANSFORMS.lock().unwrap().is_empty()
+}
+
+pub(crate) fn override_queries(session: &Session, providers: &mut Providers) {
+    let transforms = std::mem::take(&mut *BODY_TRANSFORMS.lock().unwrap());
+    if transforms.is_empty() {
+        return;
+    }
+    let default_optimized_mir = providers.optimized_mir;
+    SESSION_TRANSFORMS
+        .lock()
+        .unwrap()
+        .insert(SessionKey::new(session), SessionTransforms { transforms, default_optimized_mir });
+    providers.optimized_mir = optimized_mir;
+}
+
+pub(crate) fn end_session(session: SessionKey) {
+    SESSION_TRANSFORMS.lock().unwrap().remove(&session);
+}
+
+fn optimized_mir(tcx: TyCtxt<'_>, def_id: LocalDefId) -> &mir::Body<'_> {
+    // Don't hold the lock while transforming, a transformation may need other bodies.
+    let SessionTransforms { transforms, default_optimized_mir } =
+        SESSION_TRANSFORMS.lock().unwrap()[&SessionKey::new(tcx.sess)].clone();
+    let body = default_optimized_mir(tcx, def_id);
+
+    let transformed = run_nested(tcx, |tables| {
+        let (item, original) = {
+            let mut tables = tables.0.borrow_mut();
+            (tables.crate_item(def_id.to_def_id()), body.stable(&mut *tables))
+        };
+        let mut transformed = original.clone();
+        for transform in transforms {
+            transform(item, &mut transformed);
+        }
+        (transformed != original)
+            .then(|| internal_body(&mut tables.0.borrow_mut(), tcx, body, &transformed))
+    });
+    match transformed {
+        Some(Ok(transformed)) => tcx.arena.alloc(transformed),
+        Some(Err(err)) => {
+            tcx.dcx().span_err(
+                tcx.def_span(def_id),
+                format!("failed to apply the MIR transformations: {err}"),
+            );
+            body
+        }
+        None => body,
+    }
+}
+
+/// Converts the transformed body back into MIR.
+///
+/// The information that StableMIR doesn't provide, like the source scopes and the user type
+/// annotations, is taken from the `original` body. Statements and terminators that have the span
+/// of an original one are put into its scope, new ones into the outermost scope. Statements and
+/// terminators that can't be converted, e.g. coverage statements that were moved to a different
+/// block, are reported as an error, and so is a body that the MIR validator rejects.
+fn internal_body<'tcx>(
+    tables: &mut Tables<'_>,
+    tcx: TyCtxt<'tcx>,
+    original: &mir::Body<'tcx>,
+    body: &Body,
+) -> Result<mir::Body<'tcx>, Error> {
+    check_indices(original, body)?;
+    let original_info = OriginalInfo::new(original, tables);
+    let is_cleanup = cleanup_blocks(body);
+
+    let mut new_body = original.clone();
+    *new_body.basic_blocks_mut() = body
+        .blocks
+        .iter()
+        .zip(is_cleanup)
+        .map(|(block, is_cleanup)| {
+            let BasicBlock { statements, terminator } = block;
+            let statements = statements
+                .iter()
+                .map(|statement| original_info.statement(statement, tables, tcx))
+                .collect::<Result<_, _>>()?;
+            let terminator = original_info.terminator(terminator, is_cleanup, tables, tcx)?;
+            let mut data = mir::BasicBlockData::new(Some(terminator));
+            data.statements = statements;
+            data.is_cleanup = is_cleanup;
+            Ok(data)
+        })
+        .collect::<Result<_, _>>()?;
+
+    new_body.local_decls = body
+        .local_decls()
+        .map(|(local, decl)| {
+            let LocalDecl { ty, span, mutability } = decl;
+            let mut new_decl = original
+                .local_decls
+                .get(mir::Local::from_usize(local))
+                .cloned()
+                .unwrap_or_else(|| mir::LocalDecl::new(tcx.types.unit, rustc_span::DUMMY_SP));
+            new_decl.ty = ty.internal(tables, tcx);
+            new_decl.source_info.span = span.internal(tables, tcx);
+            new_decl.mutability = mutability.internal(tables, tcx);
+            new_decl
+        })
+        .collect();
+    new_body.arg_count = body.arg_locals().len();
+    new_body.spread_arg = body.spread_arg().map(mir::Local::from_usize);
+    new_body.var_debug_info =
+        body.var_debug_info.iter().map(|info| var_debug_info(info, tables, tcx)).collect();
+    new_body.span = body.span.internal(tables, tcx);
+
+    // New constants have to be evaluated before the body is code generated.
+    let mut required_consts = RequiredConsts(original.required_consts.clone());
+    mir::visit::Visitor::visit_body(&mut required_consts, &new_body);
+    new_body.required_consts = required_consts.0;
+
+    validate(tcx, original, &new_body)?;
+    Ok(new_body)
+}
+
+/// Checks that the arguments of the transformed body match the original ones, and that it only
+/// refers to blocks and locals that exist, which the conversion and the MIR validator assume.
+fn check_indices(original: &mir::Body<'_>, body: &Body) -> Result<(), Error> {
+    if body.arg_locals().len() != original.arg_count {
+        return Err(Error::new(format!(
+            "Expected {} arguments in the transformed body, found {}",
+            original.arg_count,
+            body.arg_locals().len()
+        )));
+    }
+    let blocks = body.blocks.len();
+    if let Some(target) = body
+        .blocks
+        .iter()
+        .flat_map(|block| block.terminator.successors())
+        .find(|target| *target >= blocks)
+    {
+        return Err(Error::new(format!(
+            "Block `bb{target}` of the transformed body doesn't exist, it has {blocks} blocks"
+        )));
+    }
+    let mut locals = LocalChecker { locals: body.locals().len(), invalid: None };
+    locals.visit_body(body);
+    if let Some(local) = locals.invalid {
+        return Err(Error::new(format!(
+            "Local `_{local}` of the transformed body doesn't exist, it has {} locals",
+            body.locals().len()
+        )));
+    }
+    Ok(())
+}
+
+/// Finds a local that is used by a body but not declared.
+struct LocalChecker {
+    locals: usize,
+    invalid: Option<Local>,
+}
+
+impl MirVisitor for LocalChecker {
+    fn visit_local(&mut self, local: &Local, _: PlaceContext, _: Location) {
+        if *local >= self.locals {
+            self.invalid.get_or_insert(*local);
+        }
+    }
+}
+
+/// Checks the converted body with the MIR validator, and that the types of its return value and
+/// arguments match the signature of the function.
+fn validate<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    original: &mir::Body<'tcx>,
+    body: &mir::Body<'tcx>,
+) -> Result<(), Error> {
+    for local in (0..=body.arg_count).map(mir::Local::from_usize) {
+        let (expected, found) = (original.local_decls[local].ty, body.local_decls[local].ty);
+        if expected != found {
+            return Err(Error::new(format!(
+                "Expected type `{expected}` for `{local:?}` of the transformed body, found \
+                 `{found}`"
+            )));
+        }
+    }
+    let param_env = tcx.param_env_reveal_all_normalized(body.source.def_id());
+    let failures = validate_types(tcx, body.phase, param_env, body);
+    if let Some((location, msg)) = failures.into_iter().next() {
+        return Err(Error::new(format!("The transformed body is invalid at {location:?}: {msg}")));
+    }
+    Ok(())
+}
+
+/// The parts of the original body that are not part of StableMIR, by the span they belong to.
+struct OriginalInfo<'tcx> {
+    scopes: FxHashMap<stable_mir::ty::Span, mir::SourceScope>,
+    coverage: FxHashMap<stable_mir::ty::Span, Vec<(stable_mir::Opaque, mir::StatementKind<'tcx>)>>,
+    calls: FxHashMap<stable_mir::ty::Span, (mir::CallSource, rustc_span::Span)>,
+    inline_asm: FxHashMap<stable_mir::ty::Span, mir::TerminatorKind<'tcx>>,
+}
+
+impl<'tcx> OriginalInfo<'tcx> {
+    fn new(original: &mir::Body<'tcx>, tables: &mut Tables<'_>) -> Self {
+        let mut info = OriginalInfo {
+            scopes: Default::default(),
+            coverage: Default::default(),
+            calls: Default::default(),
+            inline_asm: Default::default(),
+        };
+        for block in original.basic_blocks.iter() {
+            for statement in &block.statements {
+                let span = statement.source_info.span.stable(tables);
+                info.scopes.entry(span).or_insert(statement.source_info.scope);
+                if let mir::StatementKind::Coverage(coverage) = &statement.kind {
+                    let kind = (opaque(coverage), statement.kind.clone());
+                    info.coverage.entry(span).or_default().push(kind);
+                }
+            }
+            let terminator = block.terminator();
+            let span = terminator.source_info.span.stable(tables);
+            info.scopes.entry(span).or_insert(terminator.source_info.scope);
+            match &terminator.kind {
+                mir::TerminatorKind::Call { call_source, fn_span, .. } => {
+                    info.calls.insert(span, (*call_source, *fn_span));
+                }
+                mir::TerminatorKind::InlineAsm { .. } => {
+                    info.inline_asm.insert(span, terminator.kind.clone());
+                }
+                _ => {}
+            }
+        }
+        info
+    }
+
+    fn source_info(
+        &self,
+        span: stable_mir::ty::Span,
+        tables: &mut Tables<'_>,
+        tcx: TyCtxt<'tcx>,
+    ) -> mir::SourceInfo {
+        mir::SourceInfo {
+            span: span.internal(tables, tcx),
+            scope: self.scopes.get(&span).copied().unwrap_or(mir::OUTERMOST_SOURCE_SCOPE),
+        }
+    }
+
+    fn statement(
+        &self,
+        statement: &Statement,
+        tables: &mut Tables<'_>,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<mir::Statement<'tcx>, Error> {
+        let Statement { kind, span } = statement;
+        let kind = match kind {
+            StatementKind::Assign(place, rvalue) => mir::StatementKind::Assign(Box::new((
+                place.internal(tables, tcx),
+                rvalue.internal(tables, tcx),
+            ))),
+            StatementKind::FakeRead(cause, place) => mir::StatementKind::FakeRead(Box::new((
+                cause.internal(tables, tcx),
+                place.internal(tables, tcx),
+            ))),
+            StatementKind::SetDiscriminant { place, variant_index } => {
+                mir::StatementKind::SetDiscriminant {
+                    place: Box::new(place.internal(tables, tcx)),
+                    variant_index: variant_index.internal(tables, tcx),
+                }
+            }
+            StatementKind::Deinit(place) => {
+                mir::StatementKind::Deinit(Box::new(place.internal(tables, tcx)))
+            }
+            StatementKind::StorageLive(local) => {
+                mir::StatementKind::StorageLive(mir::Local::from_usize(*local))
+            }
+            StatementKind::StorageDead(local) => {
+                mir::StatementKind::StorageDead(mir::Local::from_usize(*local))
+            }
+            StatementKind::Retag(kind, place) => mir::StatementKind::Retag(
+                kind.internal(tables, tcx),
+                Box::new(place.internal(tables, tcx)),
+            ),
+            StatementKind::PlaceMention(place) => {
+                mir::StatementKind::PlaceMention(Box::new(place.internal(tables, tcx)))
+            }
+            StatementKind::AscribeUserType { .. } => {
+                // These are removed before the MIR is optimized, and can't be added back since
+                // the user type annotations of the original body are kept.
+                return Err(Error::new(
+                    "User type ascriptions can't be added to an optimized body".to_string(),
+                ));
+            }
+            StatementKind::Coverage(coverage) => self
+                .coverage
+                .get(span)
+                .and_then(|kinds| kinds.iter().find(|(kind, _)| kind == coverage))
+                .map(|(_, kind)| kind.clone())
+                .ok_or_else(|| {
+                    Error::new(
+                        "Coverage statements can only be moved within their block".to_string(),
+                    )
+                })?,
+            StatementKind::Intrinsic(intrinsic) => {
+                mir::StatementKind::Intrinsic(Box::new(intrinsic.internal(tables, tcx)))
+            }
+            StatementKind::ConstEvalCounter => mir::StatementKind::ConstEvalCounter,
+            StatementKind::Nop => mir::StatementKind::Nop,
+        };
+        Ok(mir::Statement { source_info: self.source_info(*span, tables, tcx), kind })
+    }
+
+    fn terminator(
+        &self,
+        terminator: &Terminator,
+        is_cleanup: bool,
+        tables: &mut Tables<'_>,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<mir::Terminator<'tcx>, Error> {
+        let Terminator { kind, span } = terminator;
+        let block = |target: &usize| mir::BasicBlock::from_usize(*target);
+        // The reason is only used to choose the panic message when the code is terminated.
+        let reason = if is_cleanup {
+            mir::UnwindTerminateReason::InCleanup
+        } else {
+            mir::UnwindTerminateReason::Abi
+        };
+        let unwind = |unwind: &UnwindAction| match unwind {
+            UnwindAction::Continue => mir::UnwindAction::Continue,
+            UnwindAction::Unreachable => mir::UnwindAction::Unreachable,
+            UnwindAction::Terminate => mir::UnwindAction::Terminate(reason),
+            UnwindAction::Cleanup(target) => mir::UnwindAction::Cleanup(block(target)),
+        };
+        let kind = match kind {
+            TerminatorKind::Goto { target } => mir::TerminatorKind::Goto { target: block(target) },
+            TerminatorKind::SwitchInt { discr, targets } => mir::TerminatorKind::SwitchInt {
+                discr: discr.internal(tables, tcx),
+                targets: targets.internal(tables, tcx),
+            },
+            TerminatorKind::Resume => mir::TerminatorKind::UnwindResume,
+            TerminatorKind::Abort => mir::TerminatorKind::UnwindTerminate(reason),
+            TerminatorKind::Return => mir::TerminatorKind::Return,
+            TerminatorKind::Unreachable => mir::TerminatorKind::Unreachable,
+            TerminatorKind::Drop { place, target, unwind: drop_unwind } => {
+                mir::TerminatorKind::Drop {
+                    place: place.internal(tables, tcx),
+                    target: block(target),
+                    unwind: unwind(drop_unwind),
+                    replace: false,
+                }
+            }
+            TerminatorKind::Call { func, args, destination, target, unwind: call_unwind } => {
+                let (call_source, fn_span) = self
+                    .calls
+                    .get(span)
+                    .copied()
+                    .unwrap_or_else(|| (mir::CallSource::Misc, span.internal(tables, tcx)));
+                mir::TerminatorKind::Call {
+                    func: func.internal(tables, tcx),
+                    args: args
+                        .iter()
+                        .map(|arg| {
+                            rustc_span::source_map::respan(fn_span, arg.internal(tables, tcx))
+                        })
+                        .collect(),
+                    destination: destination.internal(tables, tcx),
+                    target: target.as_ref().map(block),
+                    unwind: unwind(call_unwind),
+                    call_source,
+                    fn_span,
+                }
+            }
+            TerminatorKind::Assert { cond, expected, msg, target, unwind: assert_unwind } => {
+                mir::TerminatorKind::Assert {
+                    cond: cond.internal(tables, tcx),
+                    expected: *expected,
+                    msg: Box::new(msg.internal(tables, tcx)),
+                    target: block(target),
+                    unwind: unwind(assert_unwind),
+                }
+            }
+            TerminatorKind::InlineAsm { operands, destination, unwind: asm_unwind, .. } => {
+                // Only the operands and the targets are part of StableMIR.
+                let Some(mir::TerminatorKind::InlineAsm {
+                    template,
+                    operands: original_operands,
+                    options,
+                    line_spans,
+                    ..
+                }) = self.inline_asm.get(span)
+                else {
+                    return Err(Error::new(
+                        "Inline assembly can only be moved within its block".to_string(),
+                    ));
+                };
+                if operands.len() != original_operands.len() {
+                    return Err(Error::new(
+                        "Operands can't be added to or removed from inline assembly".to_string(),
+                    ));
+                }
+                let operands = original_operands
+                    .iter()
+                    .zip(operands)
+                    .map(|(operand, new)| {
+                        let mut operand = operand.clone();
+                        let in_value = || {
+                            new.in_value.as_ref().ok_or_else(|| {
+                                Error::new("Input operands of inline assembly need a value".into())
+                            })
+                        };
+                        match &mut operand {
+                            mir::InlineAsmOperand::In { value, .. } => {
+                                *value = in_value()?.internal(tables, tcx);
+                            }
+                            mir::InlineAsmOperand::Out { place, .. } => {
+                                *place = new.out_place.internal(tables, tcx);
+                            }
+                            mir::InlineAsmOperand::InOut { in_value: value, out_place, .. } => {
+                                *value = in_value()?.internal(tables, tcx);
+                                *out_place = new.out_place.internal(tables, tcx);
+                            }
+                            mir::InlineAsmOperand::Const { .. }
+                            | mir::InlineAsmOperand::SymFn { .. }
+                            | mir::InlineAsmOperand::SymStatic { .. } => {}
+                        }
+                        Ok(operand)
+                    })
+                    .collect::<Result<_, _>>()?;
+                mir::TerminatorKind::InlineAsm {
+                    template: *template,
+                    operands,
+                    options: *options,
+                    line_spans: *line_spans,
+                    destination: destination.as_ref().map(block),
+                    unwind: unwind(asm_unwind),
+                }
+            }
+        };
+        Ok(mir::Terminator { source_info: self.source_info(*span, tables, tcx), kind })
+    }
+}
+
+fn var_debug_info<'tcx>(
+    info: &VarDebugInfo,
+    tables: &mut Tables<'_>,
+    tcx: TyCtxt<'tcx>,
+) -> mir::VarDebugInfo<'tcx> {
+    let VarDebugInfo { name, source_info, composite, value, argument_index } = info;
+    mir::VarDebugInfo {
+        name: Symbol::intern(name),
+        source_info: mir::SourceInfo {
+            span: source_info.span.internal(tables, tcx),
+            scope: mir::SourceScope::from_usize(source_info.scope),
+        },
+        composite: composite.as_ref().map(|composite| {
+            Box::new(mir::VarDebugInfoFragment {
+                ty: composite.ty.internal(tables, tcx),
+                projection: composite.projection.internal(tables, tcx),
+            })
+        }),
+        value: match value {
+            VarDebugInfoContents::Place(place) => {
+                mir::VarDebugInfoContents::Place(place.internal(tables, tcx))
+            }
+            VarDebugInfoContents::Const(constant) => {
+                mir::VarDebugInfoContents::Const(mir::ConstOperand {
+                    span: constant.span.internal(tables, tcx),
+                    user_ty: constant
+                        .user_ty
+                        .map(rustc_middle::ty::UserTypeAnnotationIndex::from_usize),
+                    const_: constant.const_.internal(tables, tcx),
+                })
+            }
+        },
+        argument_index: *argument_index,
+    }
+}
+
+/// Returns whether each block is a cleanup block, i.e., only reachable by unwinding.
+fn cleanup_blocks(body: &Body) -> Vec<bool> {
+    let mut is_cleanup = vec![false; body.blocks.len()];
+    let mut worklist: Vec<usize> = body
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.terminator.kind {
+            TerminatorKind::Drop { unwind: UnwindAction::Cleanup(target), .. }
+            | TerminatorKind::Call { unwind: UnwindAction::Cleanup(target), .. }
+            | TerminatorKind::Assert { unwind: UnwindAction::Cleanup(target), .. }
+            | TerminatorKind::InlineAsm { unwind: UnwindAction::Cleanup(target), .. } => {
+                Some(*target)
+            }
+            _ => None,
+        })
+        .collect();
+    while let Some(block) = worklist.pop() {
+        if !std::mem::replace(&mut is_cleanup[block], true) {
+            worklist.extend(body.blocks[block].terminator.successors());
+        }
+    }
+    is_cleanup
+}
+
+/// Collects the constants of a body that have to be evaluated before it is code generated.
+struct RequiredConsts<'tcx>(Vec<mir::ConstOperand<'tcx>>);
+
+impl<'tcx> mir::visit::Visitor<'tcx> for RequiredConsts<'tcx> {
+    fn visit_constant(&mut self, constant: &mir::ConstOperand<'tcx>, _: mir::Location) {
+        if matches!(constant.const_, mir::Const::Unevaluated(..)) && !self.0.contains(constant) {
+            self.0.push(*constant);
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use rustc_middle::mir::{
-    interpret::{alloc_range, AllocRange, Pointer},
+    interpret::{alloc_range, read_target_uint, AllocRange, Pointer, Scalar},
     ConstValue,
 };
 use stable_mir::Error;
 
+use crate::rustc_internal::RustcInternal;
 use crate::rustc_smir::{Stable, Tables};
 use stable_mir::mir::Mutability;
-use stable_mir::ty::{Allocation, ProvenanceMap};
+use stable_mir::ty::{Allocation, Prov, ProvenanceMap};
 
 /// Creates new empty `Allocation` from given `Align`.
 fn new_empty_allocation(align: rustc_target::abi::Align) -> Allocation {
@@ -140,3 +141,50 @@ pub(super) fn allocation_filter<'tcx>(
         mutability: alloc.mutability.stable(tables),
     }
 }
+
+/// Creates an immutable internal allocation from an `Allocation`, which is the inverse of
+/// [allocation_filter].
+pub(super) fn internal_allocation<'tcx>(
+    allocation: &Allocation,
+    tables: &mut Tables<'tcx>,
+) -> Result<rustc_middle::mir::interpret::Allocation, Error> {
+    let tcx = tables.tcx;
+    let align = rustc_target::abi::Align::from_bytes(allocation.align)
+        .map_err(|_| Error::new(format!("Invalid alignment: `{}`", allocation.align)))?;
+    let bytes: Vec<u8> = allocation.bytes.iter().map(|byte| byte.unwrap_or(0)).collect();
+    let mut internal = rustc_middle::mir::interpret::Allocation::from_bytes(
+        &bytes[..],
+        align,
+        rustc_middle::mir::Mutability::Mut,
+    );
+    for (offset, byte) in allocation.bytes.iter().enumerate() {
+        if byte.is_none() {
+            let range = alloc_range(
+                rustc_target::abi::Size::from_bytes(offset),
+                rustc_target::abi::Size::from_bytes(1),
+            );
+            internal.write_uninit(&tcx, range).map_err(|e| e.stable(tables))?;
+        }
+    }
+    let pointer_size = tcx.data_layout.pointer_size;
+    for &(offset, Prov(alloc_id)) in &allocation.provenance.ptrs {
+        let Some(address) = bytes.get(offset..offset + pointer_size.bytes_usize()) else {
+            return Err(Error::new(format!("Pointer at offset `{offset}` is out of bounds")));
+        };
+        let address = read_target_uint(tcx.data_layout.endian, address)
+            .map_err(|e| Error::new(e.to_string()))?;
+        let ptr = Pointer::new(
+            alloc_id.internal(tables, tcx).into(),
+            rustc_target::abi::Size::from_bytes(address),
+        );
+        internal
+            .write_scalar(
+                &tcx,
+                alloc_range(rustc_target::abi::Size::from_bytes(offset), pointer_size),
+                Scalar::from_pointer(ptr, &tcx),
+            )
+            .map_err(|e| e.stable(tables))?;
+    }
+    internal.mutability = rustc_middle::mir::Mutability::Not;
+    Ok(internal)
+}
@@ -6,6 +6,9 @@
 #![allow(rustc::usage_of_qualified_ty)]
 
 use rustc_abi::HasDataLayout;
+use rustc_ast::util::comments::beautify_doc_string;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_middle::traits::{CodegenObligationError, ObligationCause};
 use rustc_middle::ty;
 use rustc_middle::ty::layout::{
     FnAbiOf, FnAbiOfHelpers, HasParamEnv, HasTyCtxt, LayoutOf, LayoutOfHelpers,
@@ -15,23 +18,32 @@
     GenericPredicates, Instance, List, ParamEnv, ScalarInt, TyCtxt, TypeVisitableExt, ValTree,
 };
 use rustc_span::def_id::LOCAL_CRATE;
+use rustc_trait_selection::traits::query::evaluate_obligation::InferCtxtExt;
+use rustc_trait_selection::traits::Obligation;
 use stable_mir::abi::{FnAbi, Layout, LayoutShape};
 use stable_mir::compiler_interface::Context;
+use stable_mir::crate_def::{Attribute, Visibility};
 use stable_mir::mir::alloc::GlobalAlloc;
-use stable_mir::mir::mono::{InstanceDef, StaticDef};
+use stable_mir::mir::borrowck::BorrowckFacts;
+use stable_mir::mir::interpret::Machine;
+use stable_mir::mir::mono::{CodegenUnit, InstanceDef, StaticDef};
 use stable_mir::mir::Body;
 use stable_mir::target::{MachineInfo, MachineSize};
 use stable_mir::ty::{
     AdtDef, AdtKind, Allocation, ClosureDef, ClosureKind, Const, FieldDef, FnDef, ForeignDef,
-    ForeignItemKind, GenericArgs, LineInfo, PolyFnSig, RigidTy, Span, Ty, TyKind, VariantDef,
+    ForeignItemKind, GenericArgs, ImplSource, LineInfo, PolyFnSig, RigidTy, Span, TraitRef, Ty,
+    TyKind, VariantDef,
+};
+use stable_mir::{
+    Crate, CrateDef, CrateItem, CrateNum, DefId, Error, Filename, ItemKind, ModuleDef, Symbol,
 };
-use stable_mir::{Crate, CrateDef, CrateItem, CrateNum, DefId, Error, Filename, ItemKind, Symbol};
 use std::cell::RefCell;
 use std::iter;
 
-use crate::rustc_internal::RustcInternal;
+use crate::rustc_internal::{borrowck, RustcInternal};
 use crate::rustc_smir::builder::BodyBuilder;
-use crate::rustc_smir::{alloc, new_item_kind, smir_crate, Stable, Tables};
+use crate::rustc_smir::hir::HirBodyBuilder;
+use crate::rustc_smir::{alloc, interpret, new_item_kind, smir_crate, Stable, Tables};
 
 impl<'tcx> Context for TablesWrapper<'tcx> {
     fn target_info(&self) -> MachineInfo {
@@ -61,6 +73,40 @@ fn mir_body(&self, item: stable_mir::DefId) -> stable_mir::mir::Body {
         tables.tcx.instance_mir(rustc_middle::ty::InstanceDef::Item(def_id)).stable(&mut tables)
     }
 
+    fn borrowck_facts(&self, item: DefId) -> Result<BorrowckFacts, Error> {
+        let mut tables = self.0.borrow_mut();
+        let def_id = tables[item];
+        let Some(def_id) = def_id.as_local() else {
+            return Err(Error::new(format!(
+                "Borrow checker facts are only available for local items: `{}`",
+                tables.tcx.def_path_str(def_id)
+            )));
+        };
+        borrowck::borrowck_facts(&mut tables, def_id)
+    }
+
+    fn hir_body(&self, item: DefId) -> Result<stable_mir::hir::Body, Error> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let def_id = tables[item];
+        let Some((local_def_id, body_id)) = def_id.as_local().and_then(|local_def_id| {
+            Some((local_def_id, tcx.hir().maybe_body_owned_by(local_def_id)?))
+        }) else {
+            return Err(Error::new(format!(
+                "Item doesn't have a local body: `{}`",
+                tcx.def_path_str(def_id)
+            )));
+        };
+        let builder = HirBodyBuilder::new(&mut tables, local_def_id);
+        if builder.has_errors() {
+            return Err(Error::new(format!(
+                "Item could not be type checked: `{}`",
+                tcx.def_path_str(def_id)
+            )));
+        }
+        Ok(builder.build(tcx.hir().body(body_id)))
+    }
+
     fn has_body(&self, def: DefId) -> bool {
         let tables = self.0.borrow();
         let def_id = tables[def];
@@ -193,6 +239,75 @@ fn explicit_predicates_of(
         }
     }
 
+    fn is_trait_implemented(
+        &self,
+        trait_ref: &TraitRef,
+        param_env: stable_mir::ty::ParamEnv,
+    ) -> bool {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let param_env = param_env.internal(&mut *tables, tcx);
+        let trait_ref = trait_ref.internal(&mut *tables, tcx);
+        // A trait reference whose projections can't be normalized isn't implemented either.
+        let Ok(trait_ref) = tcx.try_normalize_erasing_regions(param_env, trait_ref) else {
+            return false;
+        };
+        let infcx = tcx.infer_ctxt().build();
+        let obligation = Obligation::new(tcx, ObligationCause::dummy(), param_env, trait_ref);
+        infcx.predicate_must_hold_modulo_regions(&obligation)
+    }
+
+    fn select_trait_impl(
+        &self,
+        trait_ref: &TraitRef,
+        param_env: stable_mir::ty::ParamEnv,
+    ) -> Result<ImplSource, Error> {
+        // Selecting the implementation is only supported for monomorphic code, like in codegen.
+        if param_env != stable_mir::ty::ParamEnv::RevealAll {
+            return Err(Error::new(format!(
+                "Implementations can only be selected in `ParamEnv::RevealAll`, not in \
+                `{param_env:?}`"
+            )));
+        }
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let param_env = param_env.internal(&mut *tables, tcx);
+        let trait_ref = trait_ref.internal(&mut *tables, tcx);
+        let trait_ref = tcx
+            .try_normalize_erasing_regions(param_env, trait_ref)
+            .map_err(|err| Error::new(format!("Failed to normalize `{trait_ref}`: {err:?}")))?;
+        if trait_ref.has_param()
+            || trait_ref.has_escaping_bound_vars()
+            || trait_ref.has_placeholders()
+        {
+            return Err(Error::new(format!(
+                "Implementations can only be selected for monomorphic trait references, not for \
+                `{trait_ref}`"
+            )));
+        }
+        match tcx.codegen_select_candidate((param_env, trait_ref)) {
+            Ok(impl_source) => Ok(impl_source.stable(&mut *tables)),
+            Err(CodegenObligationError::Ambiguity) => {
+                Err(Error::new(format!("The implementation of `{trait_ref}` is ambiguous")))
+            }
+            Err(CodegenObligationError::Unimplemented)
+            | Err(CodegenObligationError::FulfillmentError) => {
+                Err(Error::new(format!("`{trait_ref}` is not implemented")))
+            }
+        }
+    }
+
+    fn normalize_ty(&self, ty: Ty, param_env: stable_mir::ty::ParamEnv) -> Result<Ty, Error> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let param_env = param_env.internal(&mut *tables, tcx);
+        let ty = ty.internal(&mut *tables, tcx);
+        match tcx.try_normalize_erasing_regions(param_env, ty) {
+            Ok(ty) => Ok(ty.stable(&mut *tables)),
+            Err(err) => Err(Error::new(format!("Failed to normalize `{ty}`: {err:?}"))),
+        }
+    }
+
     fn local_crate(&self) -> stable_mir::Crate {
         let tables = self.0.borrow();
         smir_crate(tables.tcx, LOCAL_CRATE)
@@ -247,6 +362,109 @@ fn get_lines(&self, span: &Span) -> LineInfo {
         LineInfo { start_line: lines.1, start_col: lines.2, end_line: lines.3, end_col: lines.4 }
     }
 
+    fn get_attrs_by_path(
+        &self,
+        def_id: stable_mir::DefId,
+        attr: &[stable_mir::Symbol],
+    ) -> Vec<Attribute> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let did = tables[def_id];
+        let attr_name: Vec<_> = attr.iter().map(|seg| rustc_span::Symbol::intern(seg)).collect();
+        tcx.get_attrs_by_path(did, &attr_name)
+            .map(|attribute| attribute.stable(&mut *tables))
+            .collect()
+    }
+
+    fn get_all_attrs(&self, def_id: stable_mir::DefId) -> Vec<Attribute> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let did = tables[def_id];
+        tcx.get_attrs_unchecked(did)
+            .iter()
+            .map(|attribute| attribute.stable(&mut *tables))
+            .collect()
+    }
+
+    fn doc_comment(&self, def_id: stable_mir::DefId) -> Option<String> {
+        let tables = self.0.borrow();
+        let docs: Vec<_> = tables
+            .tcx
+            .get_attrs_unchecked(tables[def_id])
+            .iter()
+            .filter_map(|attribute| {
+                let (doc, kind) = attribute.doc_str_and_comment_kind()?;
+                Some(beautify_doc_string(doc, kind))
+            })
+            .collect();
+        if docs.is_empty() {
+            return None;
+        }
+        let docs = docs.iter().map(|doc| doc.as_str()).collect::<Vec<_>>().join("\n");
+        // Remove the indentation that all lines share, usually the space after `///`.
+        let indent = docs
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<_> = docs.lines().map(|line| line.get(indent..).unwrap_or("")).collect();
+        Some(lines.join("\n"))
+    }
+
+    fn visibility(&self, def_id: stable_mir::DefId) -> Option<Visibility> {
+        use rustc_hir::def::DefKind;
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let did = tables[def_id];
+        // These are the kinds whose visibility is recorded, like in the crate metadata. The query
+        // fails for the other ones.
+        match tcx.def_kind(did) {
+            DefKind::Mod
+            | DefKind::Struct
+            | DefKind::Union
+            | DefKind::Enum
+            | DefKind::Variant
+            | DefKind::Trait
+            | DefKind::TyAlias
+            | DefKind::ForeignTy
+            | DefKind::TraitAlias
+            | DefKind::AssocTy
+            | DefKind::Fn
+            | DefKind::Const
+            | DefKind::Static(..)
+            | DefKind::Ctor(..)
+            | DefKind::AssocFn
+            | DefKind::AssocConst
+            | DefKind::Macro(..)
+            | DefKind::Field => Some(tcx.visibility(did).stable(&mut *tables)),
+            DefKind::Use
+            | DefKind::ForeignMod
+            | DefKind::TyParam
+            | DefKind::ConstParam
+            | DefKind::LifetimeParam
+            | DefKind::AnonConst
+            | DefKind::InlineConst
+            | DefKind::OpaqueTy
+            | DefKind::GlobalAsm
+            | DefKind::Impl { .. }
+            | DefKind::Closure
+            | DefKind::ExternCrate => None,
+        }
+    }
+
+    fn parent_module(&self, def_id: stable_mir::DefId) -> Option<ModuleDef> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let mut did = tables[def_id];
+        loop {
+            did = tcx.opt_parent(did)?;
+            if tcx.def_kind(did) == rustc_hir::def::DefKind::Mod {
+                return Some(tables.module_def(did));
+            }
+        }
+    }
+
     fn item_kind(&self, item: CrateItem) -> ItemKind {
         let tables = self.0.borrow();
         new_item_kind(tables.tcx.def_kind(tables[item.0]))
@@ -537,6 +755,35 @@ fn eval_instance(&self, def: InstanceDef, const_ty: Ty) -> Result<Allocation, Er
             .map_err(|e| e.stable(&mut *tables))?
     }
 
+    fn interpret_instance(
+        &self,
+        def: InstanceDef,
+        args: &[Allocation],
+        machine: &mut dyn Machine,
+    ) -> Result<Allocation, Error> {
+        // The tables must not be borrowed during the execution, since the machine may use them.
+        let instance = self.0.borrow().instances[def];
+        interpret::interpret_instance(self, instance, args, machine)
+    }
+
+    fn codegen_units(&self) -> Vec<CodegenUnit> {
+        let mut tables = self.0.borrow_mut();
+        let tcx = tables.tcx;
+        let (_, cgus) = tcx.collect_and_partition_mono_items(());
+        cgus.iter()
+            .map(|cgu| CodegenUnit {
+                name: cgu.name().to_string(),
+                items: cgu
+                    .items_in_deterministic_order(tcx)
+                    .iter()
+                    .map(|(item, data)| (item.stable(&mut *tables), data.stable(&mut *tables)))
+                    .collect(),
+                size_estimate: cgu.size_estimate(),
+                is_primary: cgu.is_primary(),
+            })
+            .collect()
+    }
+
     fn eval_static_initializer(&self, def: StaticDef) -> Result<Allocation, Error> {
         let mut tables = self.0.borrow_mut();
         let tcx = tables.tcx;
@@ -2,7 +2,7 @@
 
 use rustc_middle::mir;
 use rustc_middle::mir::interpret::alloc_range;
-use rustc_middle::mir::mono::MonoItem;
+use rustc_middle::mir::mono::{Linkage, MonoItem, MonoItemData, Visibility};
 use stable_mir::mir::alloc::GlobalAlloc;
 use stable_mir::mir::{ConstOperand, Statement, UserTypeProjection, VarDebugInfoFragment};
 use stable_mir::ty::{Allocation, Const, ConstantKind};
@@ -750,3 +750,50 @@ fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
         }
     }
 }
+
+impl<'tcx> Stable<'tcx> for MonoItemData {
+    type T = stable_mir::mir::mono::MonoItemData;
+
+    fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
+        stable_mir::mir::mono::MonoItemData {
+            inlined: self.inlined,
+            linkage: self.linkage.stable(tables),
+            visibility: self.visibility.stable(tables),
+            size_estimate: self.size_estimate,
+        }
+    }
+}
+
+impl<'tcx> Stable<'tcx> for Linkage {
+    type T = stable_mir::mir::mono::Linkage;
+
+    fn stable(&self, _: &mut Tables<'_>) -> Self::T {
+        use stable_mir::mir::mono::Linkage as StableLinkage;
+        match self {
+            Linkage::External => StableLinkage::External,
+            Linkage::AvailableExternally => StableLinkage::AvailableExternally,
+            Linkage::LinkOnceAny => StableLinkage::LinkOnceAny,
+            Linkage::LinkOnceODR => StableLinkage::LinkOnceODR,
+            Linkage::WeakAny => StableLinkage::WeakAny,
+            Linkage::WeakODR => StableLinkage::WeakODR,
+            Linkage::Appending => StableLinkage::Appending,
+            Linkage::Internal => StableLinkage::Internal,
+            Linkage::Private => StableLinkage::Private,
+            Linkage::ExternalWeak => StableLinkage::ExternalWeak,
+            Linkage::Common => StableLinkage::Common,
+        }
+    }
+}
+
+impl<'tcx> Stable<'tcx> for Visibility {
+    type T = stable_mir::mir::mono::Visibility;
+
+    fn stable(&self, _: &mut Tables<'_>) -> Self::T {
+        use stable_mir::mir::mono::Visibility as StableVisibility;
+        match self {
+            Visibility::Default => StableVisibility::Default,
+            Visibility::Hidden => StableVisibility::Hidden,
+            Visibility::Protected => StableVisibility::Protected,
+        }
+    }
+}
@@ -83,3 +83,14 @@ fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
         tables.create_span(*self)
     }
 }
+
+impl<'tcx> Stable<'tcx> for rustc_ast::Attribute {
+    type T = stable_mir::crate_def::Attribute;
+
+    fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
+        stable_mir::crate_def::Attribute {
+            value: rustc_ast_pretty::pprust::attribute_to_string(self),
+            span: self.span.stable(tables),
+        }
+    }
+}
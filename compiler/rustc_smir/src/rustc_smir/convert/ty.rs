@@ -1,7 +1,7 @@
 //! Conversion of internal Rust compiler `ty` items to stable ones.
 
 use rustc_middle::ty::Ty;
-use rustc_middle::{mir, ty};
+use rustc_middle::{mir, traits, ty};
 use stable_mir::ty::{
     AdtKind, Const, ConstantKind, FloatTy, GenericArgs, GenericParamDef, IntTy, Region, RigidTy,
     TyKind, UintTy,
@@ -500,6 +500,40 @@ fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
     }
 }
 
+impl<'tcx> Stable<'tcx> for traits::ImplSource<'tcx, ()> {
+    type T = stable_mir::ty::ImplSource;
+
+    fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
+        use stable_mir::ty::ImplSource;
+        match self {
+            traits::ImplSource::UserDefined(data) => ImplSource::UserDefined {
+                impl_def: tables.impl_def(data.impl_def_id),
+                args: data.args.stable(tables),
+            },
+            traits::ImplSource::Param(_) => ImplSource::Param,
+            traits::ImplSource::Builtin(source, _) => ImplSource::Builtin(source.stable(tables)),
+        }
+    }
+}
+
+impl<'tcx> Stable<'tcx> for traits::BuiltinImplSource {
+    type T = stable_mir::ty::BuiltinImplSource;
+
+    fn stable(&self, _: &mut Tables<'_>) -> Self::T {
+        use stable_mir::ty::BuiltinImplSource;
+        match *self {
+            traits::BuiltinImplSource::Misc => BuiltinImplSource::Misc,
+            traits::BuiltinImplSource::Object { vtable_base } => {
+                BuiltinImplSource::Object { vtable_base }
+            }
+            traits::BuiltinImplSource::TraitUpcasting { vtable_vptr_slot } => {
+                BuiltinImplSource::TraitUpcasting { vtable_vptr_slot }
+            }
+            traits::BuiltinImplSource::TupleUnsizing => BuiltinImplSource::TupleUnsizing,
+        }
+    }
+}
+
 impl<'tcx> Stable<'tcx> for ty::Generics {
     type T = stable_mir::ty::Generics;
 
@@ -719,6 +753,18 @@ fn stable(&self, _: &mut Tables<'_>) -> Self::T {
     }
 }
 
+impl<'tcx> Stable<'tcx> for ty::Visibility<rustc_span::def_id::DefId> {
+    type T = stable_mir::crate_def::Visibility;
+
+    fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
+        use stable_mir::crate_def::Visibility;
+        match *self {
+            ty::Visibility::Public => Visibility::Public,
+            ty::Visibility::Restricted(module) => Visibility::Restricted(tables.module_def(module)),
+        }
+    }
+}
+
 impl<'tcx> Stable<'tcx> for ty::Region<'tcx> {
     type T = stable_mir::ty::Region;
 
@@ -752,6 +798,7 @@ fn stable(&self, tables: &mut Tables<'_>) -> Self::T {
                     },
                 })
             }
+            ty::ReVar(vid) => RegionKind::ReVar(vid.as_usize()),
             ty::ReErased => RegionKind::ReErased,
             _ => unreachable!("{self:?}"),
         }
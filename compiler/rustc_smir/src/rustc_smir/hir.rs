@@ -0,0 +1,407 @@
+//! Logic required to produce the stable view of the HIR of a body, see [stable_mir::hir].
+//!
+//! The types of the expressions and patterns are taken from the results of type checking the
+//! body, which also resolve paths, methods and fields.
+
+use crate::rustc_smir::{Stable, Tables};
+use rustc_hir as hir;
+use rustc_hir::def::Res;
+use rustc_hir::pat_util::EnumerateAndAdjustIterator;
+use rustc_middle::ty::{self, Ty, TypeckResults};
+use rustc_span::def_id::{DefId, LocalDefId};
+use stable_mir::hir::{
+    Arm, BinOp, BindingId, BindingMode, Block, Body, Capture, CaptureKind, Closure, Coroutine,
+    Expr, ExprKind, FieldExpr, FieldPat, LitKind, LoopSource, MatchSource, Param, Pat, PatKind,
+    Stmt, StmtKind, UnOp,
+};
+use stable_mir::mir::{BorrowKind, MutBorrowKind};
+use stable_mir::opaque;
+
+/// Builds the stable view of the HIR of a body.
+pub struct HirBodyBuilder<'a, 'tcx> {
+    tables: &'a mut Tables<'tcx>,
+    typeck: &'tcx TypeckResults<'tcx>,
+}
+
+impl<'a, 'tcx> HirBodyBuilder<'a, 'tcx> {
+    pub fn new(tables: &'a mut Tables<'tcx>, def_id: LocalDefId) -> Self {
+        let typeck = tables.tcx.typeck(def_id);
+        HirBodyBuilder { tables, typeck }
+    }
+
+    /// Whether the body has errors, in which case some of its types are unknown.
+    pub fn has_errors(&self) -> bool {
+        self.typeck.tainted_by_errors.is_some()
+    }
+
+    pub fn build(mut self, body: &'tcx hir::Body<'tcx>) -> Body {
+        Body { params: self.params(body.params), value: self.expr(body.value) }
+    }
+
+    fn params(&mut self, params: &'tcx [hir::Param<'tcx>]) -> Vec<Param> {
+        params
+            .iter()
+            .map(|param| Param { pat: self.pat(param.pat), span: param.span.stable(self.tables) })
+            .collect()
+    }
+
+    fn exprs(&mut self, exprs: &'tcx [hir::Expr<'tcx>]) -> Vec<Expr> {
+        exprs.iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    fn boxed(&mut self, expr: &'tcx hir::Expr<'tcx>) -> Box<Expr> {
+        Box::new(self.expr(expr))
+    }
+
+    fn expr(&mut self, expr: &'tcx hir::Expr<'tcx>) -> Expr {
+        let ty = self.typeck.expr_ty(expr);
+        let kind = match expr.kind {
+            hir::ExprKind::Array(exprs) => ExprKind::Array(self.exprs(exprs)),
+            hir::ExprKind::Repeat(elem, _) => ExprKind::Repeat(self.boxed(elem)),
+            hir::ExprKind::Tup(exprs) => ExprKind::Tuple(self.exprs(exprs)),
+            hir::ExprKind::Call(callee, args) => {
+                ExprKind::Call(self.boxed(callee), self.exprs(args))
+            }
+            hir::ExprKind::MethodCall(segment, receiver, args, _) => {
+                match self.typeck.type_dependent_def_id(expr.hir_id) {
+                    Some(def_id) => ExprKind::MethodCall {
+                        method: self.tables.fn_def(def_id),
+                        generic_args: self.typeck.node_args(expr.hir_id).stable(self.tables),
+                        name: segment.ident.to_string(),
+                        receiver: self.boxed(receiver),
+                        args: self.exprs(args),
+                    },
+                    None => ExprKind::Err,
+                }
+            }
+            hir::ExprKind::Binary(op, lhs, rhs) => {
+                ExprKind::Binary(bin_op(op.node), self.boxed(lhs), self.boxed(rhs))
+            }
+            hir::ExprKind::Unary(op, operand) => {
+                let op = match op {
+                    hir::UnOp::Deref => UnOp::Deref,
+                    hir::UnOp::Not => UnOp::Not,
+                    hir::UnOp::Neg => UnOp::Neg,
+                };
+                ExprKind::Unary(op, self.boxed(operand))
+            }
+            hir::ExprKind::Lit(lit) => lit_kind(&lit.node).map_or(ExprKind::Err, ExprKind::Lit),
+            hir::ExprKind::Cast(operand, _) => ExprKind::Cast(self.boxed(operand)),
+            // Neither changes the value of the expression, and type ascriptions are only used to
+            // guide type inference.
+            hir::ExprKind::Type(inner, _) | hir::ExprKind::DropTemps(inner) => {
+                return self.expr(inner);
+            }
+            hir::ExprKind::Let(let_expr) => ExprKind::Let {
+                pat: Box::new(self.pat(let_expr.pat)),
+                init: self.boxed(let_expr.init),
+            },
+            hir::ExprKind::If(cond, then, els) => ExprKind::If {
+                cond: self.boxed(cond),
+                then: self.boxed(then),
+                els: els.map(|els| self.boxed(els)),
+            },
+            hir::ExprKind::Loop(block, label, source, _) => ExprKind::Loop {
+                body: self.block(block),
+                label: label_name(label),
+                source: match source {
+                    hir::LoopSource::Loop => LoopSource::Loop,
+                    hir::LoopSource::While => LoopSource::While,
+                    hir::LoopSource::ForLoop => LoopSource::ForLoop,
+                },
+            },
+            hir::ExprKind::Match(scrutinee, arms, source) => ExprKind::Match {
+                scrutinee: self.boxed(scrutinee),
+                arms: arms.iter().map(|arm| self.arm(arm)).collect(),
+                source: match source {
+                    hir::MatchSource::Normal => MatchSource::Normal,
+                    hir::MatchSource::ForLoopDesugar => MatchSource::ForLoopDesugar,
+                    hir::MatchSource::TryDesugar(_) => MatchSource::TryDesugar,
+                    hir::MatchSource::AwaitDesugar => MatchSource::AwaitDesugar,
+                    hir::MatchSource::FormatArgs => MatchSource::FormatArgs,
+                },
+            },
+            hir::ExprKind::Closure(closure) => self.closure(closure, ty),
+            hir::ExprKind::Block(block, label) => {
+                ExprKind::Block(self.block(block), label_name(label))
+            }
+            hir::ExprKind::Assign(lhs, rhs, _) => {
+                ExprKind::Assign(self.boxed(lhs), self.boxed(rhs))
+            }
+            hir::ExprKind::AssignOp(op, lhs, rhs) => {
+                ExprKind::AssignOp(bin_op(op.node), self.boxed(lhs), self.boxed(rhs))
+            }
+            hir::ExprKind::Field(base, ident) => ExprKind::Field {
+                base: self.boxed(base),
+                name: ident.to_string(),
+                field: self.typeck.field_index(expr.hir_id).as_usize(),
+            },
+            hir::ExprKind::Index(base, index, _) => {
+                ExprKind::Index(self.boxed(base), self.boxed(index))
+            }
+            hir::ExprKind::Path(ref qpath) => match self.typeck.qpath_res(qpath, expr.hir_id) {
+                Res::Local(hir_id) => ExprKind::Local(binding_id(hir_id)),
+                res => match res_def_id(res, ty) {
+                    Some(def_id) => ExprKind::Path {
+                        def: self.tables.create_def_id(def_id),
+                        generic_args: self.typeck.node_args(expr.hir_id).stable(self.tables),
+                    },
+                    None => ExprKind::Other(opaque(&res)),
+                },
+            },
+            hir::ExprKind::AddrOf(kind, mutability, inner) => ExprKind::AddrOf {
+                raw: kind == hir::BorrowKind::Raw,
+                mutability: mutability.stable(self.tables),
+                expr: self.boxed(inner),
+            },
+            hir::ExprKind::Break(destination, value) => ExprKind::Break {
+                label: label_name(destination.label),
+                value: value.map(|value| self.boxed(value)),
+            },
+            hir::ExprKind::Continue(destination) => {
+                ExprKind::Continue { label: label_name(destination.label) }
+            }
+            hir::ExprKind::Ret(value) => ExprKind::Return(value.map(|value| self.boxed(value))),
+            hir::ExprKind::Struct(qpath, fields, base) => ExprKind::Struct {
+                variant: self.variant(qpath, expr.hir_id, ty).0.stable(self.tables),
+                fields: fields
+                    .iter()
+                    .map(|field| FieldExpr {
+                        field: self.typeck.field_index(field.hir_id).as_usize(),
+                        expr: self.expr(field.expr),
+                        span: field.span.stable(self.tables),
+                    })
+                    .collect(),
+                base: base.map(|base| self.boxed(base)),
+            },
+            hir::ExprKind::Err(_) => ExprKind::Err,
+            hir::ExprKind::ConstBlock(_)
+            | hir::ExprKind::Become(_)
+            | hir::ExprKind::InlineAsm(_)
+            | hir::ExprKind::OffsetOf(..)
+            | hir::ExprKind::Yield(..) => ExprKind::Other(opaque(&expr.kind)),
+        };
+        Expr { kind, ty: ty.stable(self.tables), span: expr.span.stable(self.tables) }
+    }
+
+    fn arm(&mut self, arm: &'tcx hir::Arm<'tcx>) -> Arm {
+        Arm {
+            pat: self.pat(arm.pat),
+            guard: arm.guard.map(|guard| self.expr(guard)),
+            body: self.expr(arm.body),
+            span: arm.span.stable(self.tables),
+        }
+    }
+
+    fn closure(&mut self, closure: &'tcx hir::Closure<'tcx>, ty: Ty<'tcx>) -> ExprKind {
+        let body = self.tables.tcx.hir().body(closure.body);
+        let by_move = matches!(closure.capture_clause, hir::CaptureBy::Value { .. });
+        let captures = self
+            .typeck
+            .closure_min_captures_flattened(closure.def_id)
+            .map(|capture| Capture {
+                var: binding_id(capture.get_root_variable()),
+                kind: match capture.info.capture_kind {
+                    ty::UpvarCapture::ByValue => CaptureKind::ByValue,
+                    ty::UpvarCapture::ByRef(kind) => CaptureKind::ByRef(match kind {
+                        ty::BorrowKind::ImmBorrow => BorrowKind::Shared,
+                        ty::BorrowKind::UniqueImmBorrow => {
+                            BorrowKind::Mut { kind: MutBorrowKind::ClosureCapture }
+                        }
+                        ty::BorrowKind::MutBorrow => {
+                            BorrowKind::Mut { kind: MutBorrowKind::Default }
+                        }
+                    }),
+                },
+            })
+            .collect();
+        match *ty.kind() {
+            ty::Closure(def_id, args) => ExprKind::Closure(Closure {
+                def: self.tables.closure_def(def_id),
+                kind: args.as_closure().kind().stable(self.tables),
+                params: self.params(body.params),
+                body: self.boxed(body.value),
+                by_move,
+                captures,
+            }),
+            ty::Coroutine(def_id, _) => ExprKind::Coroutine(Coroutine {
+                def: self.tables.coroutine_def(def_id),
+                body: self.boxed(body.value),
+                by_move,
+                captures,
+            }),
+            // E.g., async closures.
+            _ => ExprKind::Other(opaque(closure)),
+        }
+    }
+
+    fn block(&mut self, block: &'tcx hir::Block<'tcx>) -> Block {
+        Block {
+            stmts: block.stmts.iter().filter_map(|stmt| self.stmt(stmt)).collect(),
+            expr: block.expr.map(|expr| self.boxed(expr)),
+            is_unsafe: block.rules
+                == hir::BlockCheckMode::UnsafeBlock(hir::UnsafeSource::UserProvided),
+            span: block.span.stable(self.tables),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'tcx hir::Stmt<'tcx>) -> Option<Stmt> {
+        let kind = match stmt.kind {
+            hir::StmtKind::Local(local) => StmtKind::Let {
+                pat: self.pat(local.pat),
+                init: local.init.map(|init| self.expr(init)),
+                els: local.els.map(|els| self.block(els)),
+            },
+            hir::StmtKind::Item(_) => return None,
+            hir::StmtKind::Expr(expr) => StmtKind::Expr(self.expr(expr)),
+            hir::StmtKind::Semi(expr) => StmtKind::Semi(self.expr(expr)),
+        };
+        Some(Stmt { kind, span: stmt.span.stable(self.tables) })
+    }
+
+    fn pats(&mut self, pats: &'tcx [hir::Pat<'tcx>]) -> Vec<Pat> {
+        pats.iter().map(|pat| self.pat(pat)).collect()
+    }
+
+    fn pat(&mut self, pat: &'tcx hir::Pat<'tcx>) -> Pat {
+        let ty = self.typeck.pat_ty(pat);
+        let kind = match pat.kind {
+            hir::PatKind::Wild => PatKind::Wild,
+            hir::PatKind::Binding(_, hir_id, ident, subpattern) => PatKind::Binding {
+                id: binding_id(hir_id),
+                name: ident.to_string(),
+                mode: match self.typeck.pat_binding_modes()[pat.hir_id] {
+                    ty::BindingMode::BindByValue(mutability) => {
+                        BindingMode::ByValue(mutability.stable(self.tables))
+                    }
+                    ty::BindingMode::BindByReference(mutability) => {
+                        BindingMode::ByRef(mutability.stable(self.tables))
+                    }
+                },
+                subpattern: subpattern.map(|pat| Box::new(self.pat(pat))),
+            },
+            hir::PatKind::Struct(ref qpath, fields, rest) => PatKind::Struct {
+                variant: self.variant(qpath, pat.hir_id, ty).0.stable(self.tables),
+                fields: fields
+                    .iter()
+                    .map(|field| FieldPat {
+                        field: self.typeck.field_index(field.hir_id).as_usize(),
+                        pat: self.pat(field.pat),
+                    })
+                    .collect(),
+                rest,
+            },
+            hir::PatKind::TupleStruct(ref qpath, pats, dotdot) => {
+                let (variant, variant_def) = self.variant(qpath, pat.hir_id, ty);
+                PatKind::TupleStruct {
+                    variant: variant.stable(self.tables),
+                    fields: pats
+                        .iter()
+                        .enumerate_and_adjust(variant_def.fields.len(), dotdot)
+                        .map(|(field, pat)| FieldPat { field, pat: self.pat(pat) })
+                        .collect(),
+                    rest: dotdot.as_opt_usize().is_some(),
+                }
+            }
+            hir::PatKind::Path(ref qpath) => {
+                match res_def_id(self.typeck.qpath_res(qpath, pat.hir_id), ty) {
+                    Some(def_id) => PatKind::Path(self.tables.create_def_id(def_id)),
+                    None => PatKind::Err,
+                }
+            }
+            hir::PatKind::Tuple(pats, dotdot) => {
+                PatKind::Tuple { elems: self.pats(pats), rest: dotdot.as_opt_usize() }
+            }
+            hir::PatKind::Or(pats) => PatKind::Or(self.pats(pats)),
+            hir::PatKind::Box(inner) => PatKind::Box(Box::new(self.pat(inner))),
+            hir::PatKind::Ref(inner, mutability) => {
+                PatKind::Ref(Box::new(self.pat(inner)), mutability.stable(self.tables))
+            }
+            hir::PatKind::Lit(expr) => PatKind::Lit(self.boxed(expr)),
+            hir::PatKind::Range(lo, hi, end) => PatKind::Range {
+                lo: lo.map(|lo| self.boxed(lo)),
+                hi: hi.map(|hi| self.boxed(hi)),
+                inclusive: end == hir::RangeEnd::Included,
+            },
+            hir::PatKind::Slice(prefix, middle, suffix) => PatKind::Slice {
+                prefix: self.pats(prefix),
+                middle: middle.map(|middle| Box::new(self.pat(middle))),
+                suffix: self.pats(suffix),
+            },
+            hir::PatKind::Never => PatKind::Never,
+            hir::PatKind::Err(_) => PatKind::Err,
+        };
+        Pat { kind, ty: ty.stable(self.tables), span: pat.span.stable(self.tables) }
+    }
+
+    /// Returns the variant of an ADT that a struct expression or pattern refers to.
+    fn variant(
+        &self,
+        qpath: &hir::QPath<'_>,
+        hir_id: hir::HirId,
+        ty: Ty<'tcx>,
+    ) -> (rustc_target::abi::VariantIdx, &'tcx ty::VariantDef) {
+        let ty::Adt(adt_def, _) = ty.kind() else {
+            unreachable!("expected an ADT for a struct expression or pattern, found `{ty}`")
+        };
+        let variant = adt_def.variant_of_res(self.typeck.qpath_res(qpath, hir_id));
+        (adt_def.variant_index_with_id(variant.def_id), variant)
+    }
+}
+
+fn binding_id(hir_id: hir::HirId) -> BindingId {
+    BindingId(hir_id.local_id.as_usize())
+}
+
+fn label_name(label: Option<rustc_ast::Label>) -> Option<stable_mir::Symbol> {
+    label.map(|label| label.ident.to_string())
+}
+
+/// Returns the item that a path to a value resolves to. `Self` resolves to the constructor of the
+/// type.
+fn res_def_id(res: Res, ty: Ty<'_>) -> Option<DefId> {
+    match res {
+        Res::SelfCtor(_) => match *ty.kind() {
+            ty::FnDef(def_id, _) => Some(def_id),
+            _ => ty.ty_adt_def()?.non_enum_variant().ctor_def_id(),
+        },
+        _ => res.opt_def_id(),
+    }
+}
+
+fn bin_op(op: hir::BinOpKind) -> BinOp {
+    match op {
+        hir::BinOpKind::Add => BinOp::Add,
+        hir::BinOpKind::Sub => BinOp::Sub,
+        hir::BinOpKind::Mul => BinOp::Mul,
+        hir::BinOpKind::Div => BinOp::Div,
+        hir::BinOpKind::Rem => BinOp::Rem,
+        hir::BinOpKind::And => BinOp::And,
+        hir::BinOpKind::Or => BinOp::Or,
+        hir::BinOpKind::BitXor => BinOp::BitXor,
+        hir::BinOpKind::BitAnd => BinOp::BitAnd,
+        hir::BinOpKind::BitOr => BinOp::BitOr,
+        hir::BinOpKind::Shl => BinOp::Shl,
+        hir::BinOpKind::Shr => BinOp::Shr,
+        hir::BinOpKind::Eq => BinOp::Eq,
+        hir::BinOpKind::Lt => BinOp::Lt,
+        hir::BinOpKind::Le => BinOp::Le,
+        hir::BinOpKind::Ne => BinOp::Ne,
+        hir::BinOpKind::Ge => BinOp::Ge,
+        hir::BinOpKind::Gt => BinOp::Gt,
+    }
+}
+
+fn lit_kind(lit: &rustc_ast::LitKind) -> Option<LitKind> {
+    Some(match lit {
+        rustc_ast::LitKind::Str(symbol, _) => LitKind::Str(symbol.to_string()),
+        rustc_ast::LitKind::ByteStr(bytes, _) => LitKind::ByteStr(bytes.to_vec()),
+        rustc_ast::LitKind::CStr(bytes, _) => LitKind::CStr(bytes.to_vec()),
+        rustc_ast::LitKind::Byte(byte) => LitKind::Byte(*byte),
+        rustc_ast::LitKind::Char(char) => LitKind::Char(*char),
+        rustc_ast::LitKind::Int(int, _) => LitKind::Int(int.get()),
+        rustc_ast::LitKind::Float(symbol, _) => LitKind::Float(symbol.to_string()),
+        rustc_ast::LitKind::Bool(bool) => LitKind::Bool(*bool),
+        rustc_ast::LitKind::Err(_) => return None,
+    })
+}
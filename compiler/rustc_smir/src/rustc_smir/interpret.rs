@@ -0,0 +1,548 @@
+//! Execution of instances with the MIR interpreter of the compiler.
+//!
+//! The interpreter is driven by [SmirMachine], which forwards memory accesses and calls of
+//! foreign functions to the [Machine] of the user.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::iter;
+
+use rustc_ast::expand::allocator::NO_ALLOC_SHIM_IS_UNSTABLE;
+use rustc_const_eval::const_eval::{self, ConstEvalErrKind};
+use rustc_const_eval::interpret::{
+    self, alloc_range, compile_time_machine, format_interp_error, AllocId, AllocRange,
+    CtfeProvenance, FnArg, Frame, GlobalAlloc, ImmTy, Immediate, InterpCx, InterpError,
+    InterpErrorInfo, InterpResult, MPlaceTy, MemoryKind, OpTy, PlaceTy, Pointer, Projectable,
+    ResourceExhaustionInfo, Scalar, StackPopCleanup, UndefinedBehaviorInfo, UnsupportedOpInfo,
+    Writeable,
+};
+use rustc_errors::{DiagArgName, DiagArgValue, DiagMessage};
+use rustc_hir::def_id::DefId;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir;
+use rustc_middle::query::TyCtxtAt;
+use rustc_middle::ty::layout::{LayoutOf, TyAndLayout};
+use rustc_middle::ty::{self, Instance, ParamEnv};
+use rustc_span::sym;
+use rustc_target::abi::{Align, Size};
+use rustc_target::spec::abi::Abi as CallAbi;
+use stable_mir::mir::interpret::{Machine, MemoryAccess};
+use stable_mir::ty::Allocation;
+use stable_mir::Error;
+
+use crate::rustc_smir::alloc::{allocation_filter, internal_allocation};
+use crate::rustc_smir::context::TablesWrapper;
+use crate::rustc_smir::Stable;
+
+/// Executes `instance` with the given arguments, and returns the value that it returns.
+pub(crate) fn interpret_instance<'tcx>(
+    wrapper: &TablesWrapper<'tcx>,
+    instance: Instance<'tcx>,
+    args: &[Allocation],
+    machine: &mut dyn Machine,
+) -> Result<Allocation, Error> {
+    let tcx = wrapper.0.borrow().tcx;
+    if !wrapper.0.borrow().has_body(instance) {
+        return Err(Error::new(format!("Item does not have a body: `{instance}`")));
+    }
+    let body = tcx.instance_mir(instance.def);
+    if body.spread_arg.is_some() {
+        return Err(Error::new("Instances with spread arguments are not supported".to_string()));
+    }
+    if args.len() != body.arg_count {
+        return Err(Error::new(format!(
+            "Expected {} arguments for `{instance}`, found {}",
+            body.arg_count,
+            args.len()
+        )));
+    }
+
+    let machine = SmirMachine {
+        tables: wrapper,
+        machine: RefCell::new(machine),
+        stack: Vec::new(),
+        report_accesses: false,
+    };
+    let mut ecx =
+        InterpCx::new(tcx, tcx.def_span(instance.def_id()), ParamEnv::reveal_all(), machine);
+    interpret_body(&mut ecx, instance, body, args).map_err(|err| {
+        let stop = match err.kind() {
+            InterpError::MachineStop(stop) => stop.downcast_ref::<MachineError>(),
+            _ => None,
+        };
+        match stop {
+            Some(MachineError(error)) => error.clone(),
+            None => Error::new(format_interp_error(tcx.dcx(), err)),
+        }
+    })?
+}
+
+fn interpret_body<'mir, 'tcx: 'mir>(
+    ecx: &mut InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    instance: Instance<'tcx>,
+    body: &'mir mir::Body<'tcx>,
+    args: &[Allocation],
+) -> InterpResult<'tcx, Result<Allocation, Error>> {
+    let return_ty = instance.instantiate_mir_and_normalize_erasing_regions(
+        *ecx.tcx,
+        ParamEnv::reveal_all(),
+        ty::EarlyBinder::bind(body.return_ty()),
+    );
+    let ret = ecx.allocate(ecx.layout_of(return_ty)?, MemoryKind::Stack)?;
+    ecx.push_stack_frame(
+        instance,
+        body,
+        &ret.clone().into(),
+        StackPopCleanup::Root { cleanup: false },
+    )?;
+    for (local, arg) in body.args_iter().zip(args) {
+        ecx.storage_live(local)?;
+        let dest = ecx.local_to_place(ecx.frame_idx(), local)?;
+        if let Err(error) = write_allocation(ecx, arg, &dest)? {
+            return Ok(Err(error));
+        }
+    }
+    ecx.storage_live_for_always_live_locals()?;
+
+    ecx.machine.report_accesses = true;
+    while ecx.step()? {}
+    ecx.machine.report_accesses = false;
+
+    let (alloc_id, offset, _) = ecx.ptr_get_alloc_id(ret.ptr())?;
+    let (_, alloc) = ecx.memory.alloc_map().get(&alloc_id).unwrap();
+    let mut tables = ecx.machine.tables.0.borrow_mut();
+    Ok(Ok(allocation_filter(alloc, alloc_range(offset, ret.layout.size), &mut *tables)))
+}
+
+/// Reads the value of `op` into an `Allocation`, without reporting the memory accesses.
+fn read_allocation<'mir, 'tcx: 'mir>(
+    ecx: &mut InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    op: &OpTy<'tcx>,
+) -> InterpResult<'tcx, Allocation> {
+    let report_accesses = std::mem::replace(&mut ecx.machine.report_accesses, false);
+    let tmp = ecx.allocate(op.layout, MemoryKind::Stack)?;
+    ecx.copy_op(op, &tmp)?;
+    let (alloc_id, offset, _) = ecx.ptr_get_alloc_id(tmp.ptr())?;
+    let (_, alloc) = ecx.memory.alloc_map().get(&alloc_id).unwrap();
+    let allocation = allocation_filter(
+        alloc,
+        alloc_range(offset, op.layout.size),
+        &mut *ecx.machine.tables.0.borrow_mut(),
+    );
+    ecx.deallocate_ptr(tmp.ptr(), None, MemoryKind::Stack)?;
+    ecx.machine.report_accesses = report_accesses;
+    Ok(allocation)
+}
+
+/// Writes the value of `allocation` to `dest`, without reporting the memory accesses.
+fn write_allocation<'mir, 'tcx: 'mir>(
+    ecx: &mut InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    allocation: &Allocation,
+    dest: &impl Writeable<'tcx, interpret::CtfeProvenance>,
+) -> InterpResult<'tcx, Result<(), Error>> {
+    let layout = dest.layout();
+    if layout.is_unsized() || allocation.bytes.len() as u64 != layout.size.bytes() {
+        return Ok(Err(Error::new(format!(
+            "Expected a value of type `{}`, found {} bytes",
+            layout.ty,
+            allocation.bytes.len()
+        ))));
+    }
+    let alloc = {
+        let mut tables = ecx.machine.tables.0.borrow_mut();
+        match internal_allocation(allocation, &mut *tables) {
+            Ok(alloc) => alloc,
+            Err(error) => return Ok(Err(error)),
+        }
+    };
+    let tcx = *ecx.tcx;
+    let alloc_id = tcx.reserve_and_set_memory_alloc(tcx.mk_const_alloc(alloc));
+    let ptr = ecx.global_base_pointer(Pointer::from(alloc_id))?;
+    let src = ecx.ptr_to_mplace(ptr.into(), layout);
+    let report_accesses = std::mem::replace(&mut ecx.machine.report_accesses, false);
+    ecx.copy_op(&src, dest)?;
+    ecx.machine.report_accesses = report_accesses;
+    Ok(Ok(()))
+}
+
+/// An error of the machine of the user, which stops the execution.
+#[derive(Debug)]
+struct MachineError(Error);
+
+impl interpret::MachineStopType for MachineError {
+    fn diagnostic_message(&self) -> DiagMessage {
+        self.0.to_string().into()
+    }
+
+    fn add_args(self: Box<Self>, _: &mut dyn FnMut(DiagArgName, DiagArgValue)) {}
+}
+
+fn machine_stop<'tcx>(error: Error) -> InterpErrorInfo<'tcx> {
+    InterpError::MachineStop(Box::new(MachineError(error))).into()
+}
+
+fn unsupported<'tcx>(msg: String) -> InterpErrorInfo<'tcx> {
+    InterpError::Unsupported(UnsupportedOpInfo::Unsupported(msg)).into()
+}
+
+fn undefined_behavior<'tcx>(msg: String) -> InterpErrorInfo<'tcx> {
+    InterpError::UndefinedBehavior(UndefinedBehaviorInfo::Ub(msg)).into()
+}
+
+/// Emulates the functions of the global allocator, e.g. `__rust_alloc`, like Miri does. They are
+/// foreign functions, but a stable [Machine] can't create memory in the interpreter. Returns
+/// whether `def_id` is one of these functions.
+///
+/// The memory is allocated by the interpreter even if the crate has a `#[global_allocator]`.
+fn emulate_allocator_fn<'mir, 'tcx: 'mir>(
+    ecx: &mut InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    def_id: DefId,
+    args: &[OpTy<'tcx>],
+    dest: &PlaceTy<'tcx>,
+) -> InterpResult<'tcx, bool> {
+    let flags = ecx.tcx.codegen_fn_attrs(def_id).flags;
+    let zeroed = flags.contains(CodegenFnAttrFlags::ALLOCATOR_ZEROED);
+    let heap = MemoryKind::Machine(const_eval::MemoryKind::Heap);
+    match args {
+        [size, align] if zeroed || flags.contains(CodegenFnAttrFlags::ALLOCATOR) => {
+            let (size, align) = read_size_and_align(ecx, size, align)?;
+            let ptr = ecx.allocate_ptr(size, align, heap)?;
+            if zeroed {
+                ecx.write_bytes_ptr(ptr.into(), iter::repeat(0).take(size.bytes_usize()))?;
+            }
+            ecx.write_pointer(ptr, dest)?;
+        }
+        [ptr, size, align] if flags.contains(CodegenFnAttrFlags::DEALLOCATOR) => {
+            let ptr = ecx.read_pointer(ptr)?;
+            let size_and_align = read_size_and_align(ecx, size, align)?;
+            ecx.deallocate_ptr(ptr, Some(size_and_align), heap)?;
+        }
+        [ptr, old_size, align, new_size] if flags.contains(CodegenFnAttrFlags::REALLOCATOR) => {
+            let ptr = ecx.read_pointer(ptr)?;
+            let (old_size, align) = read_size_and_align(ecx, old_size, align)?;
+            let new_size = Size::from_bytes(ecx.read_target_usize(new_size)?);
+            let ptr = ecx.reallocate_ptr(ptr, Some((old_size, align)), new_size, align, heap)?;
+            ecx.write_pointer(ptr, dest)?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn read_size_and_align<'mir, 'tcx: 'mir>(
+    ecx: &InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    size: &OpTy<'tcx>,
+    align: &OpTy<'tcx>,
+) -> InterpResult<'tcx, (Size, Align)> {
+    let size = Size::from_bytes(ecx.read_target_usize(size)?);
+    let align = ecx.read_target_usize(align)?;
+    let align = Align::from_bytes(align)
+        .map_err(|_| undefined_behavior(format!("invalid alignment for an allocation: {align}")))?;
+    Ok((size, align))
+}
+
+/// Whether `place` is the foreign static that `alloc::alloc::alloc` reads to make sure that the
+/// allocator shim is linked. It doesn't have any memory in the interpreter.
+fn is_alloc_shim_marker<'mir, 'tcx: 'mir>(
+    ecx: &InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    place: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx, bool> {
+    let (alloc_id, _, _) = ecx.ptr_get_alloc_id(place.ptr())?;
+    Ok(match ecx.tcx.try_get_global_alloc(alloc_id) {
+        Some(GlobalAlloc::Static(def_id)) => {
+            ecx.tcx.is_foreign_item(def_id)
+                && ecx.tcx.item_name(def_id).as_str() == NO_ALLOC_SHIM_IS_UNSTABLE
+        }
+        _ => false,
+    })
+}
+
+/// Compares the scalars of two pointers, or of their metadata.
+///
+/// Pointers only have an address relative to their allocation, so they are only ordered if they
+/// point into the same allocation, or if both are integers. Like at runtime, pointers into
+/// different allocations are never equal, and neither are pointers and integers.
+fn compare_ptr_scalars<'mir, 'tcx: 'mir>(
+    ecx: &InterpCx<'mir, 'tcx, SmirMachine<'_, 'mir, 'tcx>>,
+    left: Scalar,
+    right: Scalar,
+) -> InterpResult<'tcx, Option<Ordering>> {
+    let (left_prov, left_offset) = left.to_pointer(ecx)?.into_parts();
+    let (right_prov, right_offset) = right.to_pointer(ecx)?.into_parts();
+    let alloc_id = |prov: Option<CtfeProvenance>| prov.map(|prov| prov.alloc_id());
+    if alloc_id(left_prov) == alloc_id(right_prov) {
+        Ok(Some(left_offset.cmp(&right_offset)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The interpreter machine that forwards its hooks to a stable [Machine].
+struct SmirMachine<'a, 'mir, 'tcx> {
+    tables: &'a TablesWrapper<'tcx>,
+    machine: RefCell<&'a mut dyn Machine>,
+    stack: Vec<Frame<'mir, 'tcx>>,
+    /// Whether memory accesses are reported to `machine`. Accesses are not reported while the
+    /// arguments and the return value are moved in and out of the interpreter.
+    report_accesses: bool,
+}
+
+impl<'a, 'mir, 'tcx> SmirMachine<'a, 'mir, 'tcx> {
+    /// The access to report for `range` of `alloc_id`, if any.
+    ///
+    /// Note that the tables must not be borrowed while the stable machine is called, since it
+    /// may use the stable APIs.
+    fn memory_access(&self, alloc_id: AllocId, range: AllocRange) -> Option<MemoryAccess> {
+        if !self.report_accesses || range.size.bytes() == 0 {
+            return None;
+        }
+        let alloc = self.tables.0.borrow_mut().create_alloc_id(alloc_id);
+        Some(MemoryAccess {
+            alloc,
+            offset: range.start.bytes_usize(),
+            size: range.size.bytes_usize(),
+        })
+    }
+}
+
+impl<'a, 'mir, 'tcx: 'mir> interpret::Machine<'mir, 'tcx> for SmirMachine<'a, 'mir, 'tcx> {
+    compile_time_machine!(<'mir, 'tcx>);
+    type MemoryKind = const_eval::MemoryKind;
+    const PANIC_ON_ALLOC_FAIL: bool = false;
+
+    #[inline(always)]
+    fn enforce_alignment(_ecx: &InterpCx<'mir, 'tcx, Self>) -> bool {
+        false
+    }
+
+    fn enforce_validity(_ecx: &InterpCx<'mir, 'tcx, Self>, _layout: TyAndLayout<'tcx>) -> bool {
+        false
+    }
+
+    fn find_mir_or_eval_fn(
+        ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        instance: Instance<'tcx>,
+        _abi: CallAbi,
+        args: &[FnArg<'tcx>],
+        destination: &PlaceTy<'tcx>,
+        target: Option<mir::BasicBlock>,
+        _unwind: mir::UnwindAction,
+    ) -> InterpResult<'tcx, Option<(&'mir mir::Body<'tcx>, Instance<'tcx>)>> {
+        let tcx = *ecx.tcx;
+        let def_id = instance.def_id();
+        if Some(def_id) == tcx.lang_items().panic_fmt()
+            || Some(def_id) == tcx.lang_items().begin_panic_fn()
+        {
+            return Err(machine_stop(Error::new("The interpreted function panicked".to_string())));
+        }
+        if !tcx.is_foreign_item(def_id) {
+            if !ecx.machine.tables.0.borrow().has_body(instance) {
+                return Err(unsupported(format!(
+                    "calling a function without a body: `{instance}`"
+                )));
+            }
+            return Ok(Some((ecx.load_mir(instance.def, None)?, instance)));
+        }
+
+        let args = ecx.copy_fn_args(args)?;
+        let report_accesses = std::mem::replace(&mut ecx.machine.report_accesses, false);
+        let emulated = emulate_allocator_fn(ecx, def_id, &args, destination)?;
+        ecx.machine.report_accesses = report_accesses;
+        if emulated {
+            ecx.return_to_block(target)?;
+            return Ok(None);
+        }
+        let args = args
+            .iter()
+            .map(|arg| read_allocation(ecx, arg))
+            .collect::<InterpResult<'tcx, Vec<_>>>()?;
+        let instance = instance.stable(&mut *ecx.machine.tables.0.borrow_mut());
+        let value = ecx.machine.machine.get_mut().call_foreign(instance, &args);
+        write_allocation(ecx, &value.map_err(machine_stop)?, destination)?.map_err(machine_stop)?;
+        ecx.return_to_block(target)?;
+        Ok(None)
+    }
+
+    fn call_intrinsic(
+        ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        instance: Instance<'tcx>,
+        args: &[OpTy<'tcx>],
+        destination: &PlaceTy<'tcx>,
+        target: Option<mir::BasicBlock>,
+        _unwind: mir::UnwindAction,
+    ) -> InterpResult<'tcx> {
+        if ecx.emulate_intrinsic(instance, args, destination, target)? {
+            return Ok(());
+        }
+        let intrinsic_name = ecx.tcx.item_name(instance.def_id());
+        match (intrinsic_name, target) {
+            // The values are never known at compile time, as they are provided by the user.
+            (sym::is_val_statically_known, Some(ret)) => {
+                ecx.write_scalar(Scalar::from_bool(false), destination)?;
+                ecx.go_to_block(ret);
+                Ok(())
+            }
+            // Like Miri, the marker of the allocator shim is read as 0.
+            (sym::volatile_load, Some(ret)) => {
+                let [ptr] = args else {
+                    return Err(unsupported("invalid arguments of `volatile_load`".to_string()));
+                };
+                let place = ecx.deref_pointer(ptr)?;
+                if is_alloc_shim_marker(ecx, &place)? {
+                    ecx.write_scalar(Scalar::from_u8(0), destination)?;
+                } else {
+                    ecx.copy_op(&place, destination)?;
+                }
+                ecx.go_to_block(ret);
+                Ok(())
+            }
+            _ => Err(unsupported(format!(
+                "intrinsic `{intrinsic_name}` is not supported by the interpreter"
+            ))),
+        }
+    }
+
+    fn assert_panic(
+        ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        msg: &mir::AssertMessage<'tcx>,
+        _unwind: mir::UnwindAction,
+    ) -> InterpResult<'tcx> {
+        use rustc_middle::mir::AssertKind::*;
+        // Convert `AssertKind<Operand>` to `AssertKind<Scalar>`, like for const evaluation.
+        let eval_to_int =
+            |op| ecx.read_immediate(&ecx.eval_operand(op, None)?).map(|x| x.to_const_int());
+        let err = match msg {
+            BoundsCheck { len, index } => {
+                let len = eval_to_int(len)?;
+                let index = eval_to_int(index)?;
+                BoundsCheck { len, index }
+            }
+            Overflow(op, l, r) => Overflow(*op, eval_to_int(l)?, eval_to_int(r)?),
+            OverflowNeg(op) => OverflowNeg(eval_to_int(op)?),
+            DivisionByZero(op) => DivisionByZero(eval_to_int(op)?),
+            RemainderByZero(op) => RemainderByZero(eval_to_int(op)?),
+            ResumedAfterReturn(coroutine_kind) => ResumedAfterReturn(*coroutine_kind),
+            ResumedAfterPanic(coroutine_kind) => ResumedAfterPanic(*coroutine_kind),
+            MisalignedPointerDereference { ref required, ref found } => {
+                MisalignedPointerDereference {
+                    required: eval_to_int(required)?,
+                    found: eval_to_int(found)?,
+                }
+            }
+        };
+        Err(ConstEvalErrKind::AssertFailure(err).into())
+    }
+
+    fn panic_nounwind(_ecx: &mut InterpCx<'mir, 'tcx, Self>, msg: &str) -> InterpResult<'tcx> {
+        Err(machine_stop(Error::new(format!("The interpreted function panicked: {msg}"))))
+    }
+
+    /// Compares pointers, see [compare_ptr_scalars]. Offsets are computed by the interpreter
+    /// itself, and the other operations are only used by atomic intrinsics.
+    fn binary_ptr_op(
+        ecx: &InterpCx<'mir, 'tcx, Self>,
+        bin_op: mir::BinOp,
+        left: &ImmTy<'tcx>,
+        right: &ImmTy<'tcx>,
+    ) -> InterpResult<'tcx, (ImmTy<'tcx>, bool)> {
+        use rustc_middle::mir::BinOp::*;
+        if !matches!(bin_op, Eq | Ne | Lt | Le | Gt | Ge) {
+            return Err(unsupported(format!(
+                "pointer operation `{bin_op:?}` is not supported by the interpreter"
+            )));
+        }
+        // Wide pointers are compared lexicographically, by their data pointer and then by their
+        // metadata.
+        let ordering = match (**left, **right) {
+            (Immediate::Scalar(left), Immediate::Scalar(right)) => {
+                compare_ptr_scalars(ecx, left, right)?
+            }
+            (Immediate::ScalarPair(left, left_meta), Immediate::ScalarPair(right, right_meta)) => {
+                match compare_ptr_scalars(ecx, left, right)? {
+                    Some(Ordering::Equal) => compare_ptr_scalars(ecx, left_meta, right_meta)?,
+                    ordering => ordering,
+                }
+            }
+            _ => {
+                return Err(unsupported(format!(
+                    "comparing pointers of different kinds is not supported by the interpreter: \
+                     `{}` and `{}`",
+                    left.layout.ty, right.layout.ty
+                )));
+            }
+        };
+        let result = match (bin_op, ordering) {
+            (Eq, _) => ordering == Some(Ordering::Equal),
+            (Ne, _) => ordering != Some(Ordering::Equal),
+            (Lt, Some(ordering)) => ordering.is_lt(),
+            (Le, Some(ordering)) => ordering.is_le(),
+            (Gt, Some(ordering)) => ordering.is_gt(),
+            (Ge, Some(ordering)) => ordering.is_ge(),
+            _ => {
+                return Err(unsupported(
+                    "comparing pointers into different allocations is not supported by the \
+                     interpreter"
+                        .to_string(),
+                ));
+            }
+        };
+        Ok((ImmTy::from_bool(result, *ecx.tcx), false))
+    }
+
+    fn expose_ptr(
+        _ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        _ptr: Pointer<Self::Provenance>,
+    ) -> InterpResult<'tcx> {
+        Err(unsupported("exposing pointers is not supported by the interpreter".to_string()))
+    }
+
+    #[inline(always)]
+    fn init_frame_extra(
+        ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        frame: Frame<'mir, 'tcx>,
+    ) -> InterpResult<'tcx, Frame<'mir, 'tcx>> {
+        // Enforce stack size limit. Add 1 because this is run before the new frame is pushed.
+        if !ecx.recursion_limit.value_within_limit(ecx.stack().len() + 1) {
+            Err(InterpError::ResourceExhaustion(ResourceExhaustionInfo::StackFrameLimitReached)
+                .into())
+        } else {
+            Ok(frame)
+        }
+    }
+
+    #[inline(always)]
+    fn stack<'b>(
+        ecx: &'b InterpCx<'mir, 'tcx, Self>,
+    ) -> &'b [Frame<'mir, 'tcx, Self::Provenance, Self::FrameExtra>] {
+        &ecx.machine.stack
+    }
+
+    #[inline(always)]
+    fn stack_mut<'b>(
+        ecx: &'b mut InterpCx<'mir, 'tcx, Self>,
+    ) -> &'b mut Vec<Frame<'mir, 'tcx, Self::Provenance, Self::FrameExtra>> {
+        &mut ecx.machine.stack
+    }
+
+    fn before_memory_read(
+        _tcx: TyCtxtAt<'tcx>,
+        machine: &Self,
+        _alloc_extra: &Self::AllocExtra,
+        (alloc_id, _): (AllocId, Self::ProvenanceExtra),
+        range: AllocRange,
+    ) -> InterpResult<'tcx> {
+        let Some(access) = machine.memory_access(alloc_id, range) else { return Ok(()) };
+        machine.machine.borrow_mut().before_memory_read(access).map_err(machine_stop)
+    }
+
+    fn before_memory_write(
+        _tcx: TyCtxtAt<'tcx>,
+        machine: &mut Self,
+        _alloc_extra: &mut Self::AllocExtra,
+        (alloc_id, _): (AllocId, Self::ProvenanceExtra),
+        range: AllocRange,
+    ) -> InterpResult<'tcx> {
+        let Some(access) = machine.memory_access(alloc_id, range) else { return Ok(()) };
+        machine.machine.get_mut().before_memory_write(access).map_err(machine_stop)
+    }
+}
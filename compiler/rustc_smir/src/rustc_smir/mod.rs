@@ -25,6 +25,8 @@
 mod builder;
 pub(crate) mod context;
 mod convert;
+mod hir;
+mod interpret;
 
 pub struct Tables<'tcx> {
     pub(crate) tcx: TyCtxt<'tcx>,
@@ -64,7 +64,7 @@ fn layout_of<'tcx>(
 
     // If we are running with `-Zprint-type-sizes`, maybe record layouts
     // for dumping later.
-    if cx.tcx.sess.opts.unstable_opts.print_type_sizes {
+    if cx.tcx.sess.opts.unstable_opts.print_type_sizes.is_some() {
         record_layout_for_printing(&cx, layout);
     }
 
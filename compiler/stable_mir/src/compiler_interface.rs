@@ -6,19 +6,22 @@
 use std::cell::Cell;
 
 use crate::abi::{FnAbi, Layout, LayoutShape};
+use crate::crate_def::{Attribute, Visibility};
 use crate::mir::alloc::{AllocId, GlobalAlloc};
-use crate::mir::mono::{Instance, InstanceDef, StaticDef};
+use crate::mir::borrowck::BorrowckFacts;
+use crate::mir::interpret::Machine;
+use crate::mir::mono::{CodegenUnit, Instance, InstanceDef, StaticDef};
 use crate::mir::Body;
 use crate::target::MachineInfo;
 use crate::ty::{
     AdtDef, AdtKind, Allocation, ClosureDef, ClosureKind, Const, FieldDef, FnDef, ForeignDef,
     ForeignItemKind, ForeignModule, ForeignModuleDef, GenericArgs, GenericPredicates, Generics,
-    ImplDef, ImplTrait, LineInfo, PolyFnSig, RigidTy, Span, TraitDecl, TraitDef, Ty, TyKind,
-    VariantDef,
+    ImplDef, ImplSource, ImplTrait, LineInfo, ParamEnv, PolyFnSig, RigidTy, Span, TraitDecl,
+    TraitDef, TraitRef, Ty, TyKind, VariantDef,
 };
 use crate::{
-    mir, Crate, CrateItem, CrateItems, CrateNum, DefId, Error, Filename, ImplTraitDecls, ItemKind,
-    Symbol, TraitDecls,
+    hir, mir, Crate, CrateItem, CrateItems, CrateNum, DefId, Error, Filename, ImplTraitDecls,
+    ItemKind, ModuleDef, Symbol, TraitDecls,
 };
 
 /// This trait defines the interface between stable_mir and the Rust compiler.
@@ -32,6 +35,10 @@ pub trait Context {
     fn mir_body(&self, item: DefId) -> mir::Body;
     /// Check whether the body of a function is available.
     fn has_body(&self, item: DefId) -> bool;
+    /// Retrieve the borrow checker facts of a local function, if they were collected.
+    fn borrowck_facts(&self, item: DefId) -> Result<BorrowckFacts, Error>;
+    /// Retrieve the body of a local item at the level of the source code.
+    fn hir_body(&self, item: DefId) -> Result<hir::Body, Error>;
     fn foreign_modules(&self, crate_num: CrateNum) -> Vec<ForeignModuleDef>;
     fn foreign_module(&self, mod_def: ForeignModuleDef) -> ForeignModule;
     fn foreign_items(&self, mod_def: ForeignModuleDef) -> Vec<ForeignDef>;
@@ -44,6 +51,16 @@ pub trait Context {
     fn generics_of(&self, def_id: DefId) -> Generics;
     fn predicates_of(&self, def_id: DefId) -> GenericPredicates;
     fn explicit_predicates_of(&self, def_id: DefId) -> GenericPredicates;
+    /// Check whether a trait reference holds, ignoring region constraints.
+    fn is_trait_implemented(&self, trait_ref: &TraitRef, param_env: ParamEnv) -> bool;
+    /// Select the implementation of a monomorphic trait reference.
+    fn select_trait_impl(
+        &self,
+        trait_ref: &TraitRef,
+        param_env: ParamEnv,
+    ) -> Result<ImplSource, Error>;
+    /// Normalize the associated type projections in a type and erase its regions.
+    fn normalize_ty(&self, ty: Ty, param_env: ParamEnv) -> Result<Ty, Error>;
     /// Get information about the local crate.
     fn local_crate(&self) -> Crate;
     /// Retrieve a list of all external crates.
@@ -64,6 +81,21 @@ pub trait Context {
     /// Return lines corresponding to this `Span`
     fn get_lines(&self, span: &Span) -> LineInfo;
 
+    /// Retrieve the attributes of a definition with the given path, e.g. `["kani", "proof"]`.
+    fn get_attrs_by_path(&self, def_id: DefId, attr: &[Symbol]) -> Vec<Attribute>;
+
+    /// Retrieve all attributes of a definition.
+    fn get_all_attrs(&self, def_id: DefId) -> Vec<Attribute>;
+
+    /// Retrieve the documentation of a definition.
+    fn doc_comment(&self, def_id: DefId) -> Option<String>;
+
+    /// Retrieve the visibility of a definition, if it has one.
+    fn visibility(&self, def_id: DefId) -> Option<Visibility>;
+
+    /// Retrieve the module that contains a definition.
+    fn parent_module(&self, def_id: DefId) -> Option<ModuleDef>;
+
     /// Returns the `kind` of given `DefId`
     fn item_kind(&self, item: CrateItem) -> ItemKind;
 
@@ -170,12 +202,23 @@ fn resolve_closure(
         kind: ClosureKind,
     ) -> Option<Instance>;
 
+    /// Collect the mono items of the local crate and partition them into codegen units.
+    fn codegen_units(&self) -> Vec<CodegenUnit>;
+
     /// Evaluate a static's initializer.
     fn eval_static_initializer(&self, def: StaticDef) -> Result<Allocation, Error>;
 
     /// Try to evaluate an instance into a constant.
     fn eval_instance(&self, def: InstanceDef, const_ty: Ty) -> Result<Allocation, Error>;
 
+    /// Execute an instance with the interpreter, calling the hooks of `machine`.
+    fn interpret_instance(
+        &self,
+        def: InstanceDef,
+        args: &[Allocation],
+        machine: &mut dyn Machine,
+    ) -> Result<Allocation, Error>;
+
     /// Retrieve global allocation for the given allocation ID.
     fn global_alloc(&self, id: AllocId) -> GlobalAlloc;
 
@@ -213,6 +256,19 @@ pub fn run<F, T>(context: &dyn Context, f: F) -> Result<T, Error>
     }
 }
 
+/// Execute the given function with the given context, even if another context is already running.
+///
+/// This is needed when the compiler itself invokes StableMIR code, e.g. to transform a MIR body,
+/// while it is executing a method of another context. The other context is restored once `f`
+/// returns. `f` must not use any item that was created by the other context.
+pub fn run_nested<F, T>(context: &dyn Context, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let ptr: *const () = std::ptr::addr_of!(context) as _;
+    TLV.set(&Cell::new(ptr), f)
+}
+
 /// Execute the given function with access the compiler [Context].
 ///
 /// I.e., This function will load the current context and calls a function with it.
@@ -2,10 +2,11 @@
 //! such as, a function, a trait, an enum, and any other definitions.
 
 use crate::ty::Span;
-use crate::{with, Crate, Symbol};
+use crate::{with, Crate, ModuleDef, Symbol};
+use serde::{Deserialize, Serialize};
 
 /// A unique identification number for each item accessible for the current compilation unit.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DefId(pub(crate) usize);
 
 /// A trait for retrieving information about a particular definition.
@@ -50,6 +51,63 @@ fn span(&self) -> Span {
         let def_id = self.def_id();
         with(|cx| cx.span_of_an_item(def_id))
     }
+
+    /// Return the attributes of this definition with the given path.
+    ///
+    /// For example, `def.attrs_by_path(&["kani".into(), "proof".into()])` returns the
+    /// `#[kani::proof]` attributes of `def`, and an empty vector if there are none.
+    fn attrs_by_path(&self, attr: &[Symbol]) -> Vec<Attribute> {
+        let def_id = self.def_id();
+        with(|cx| cx.get_attrs_by_path(def_id, attr))
+    }
+
+    /// Return all attributes of this definition, including its doc comments.
+    fn all_attrs(&self) -> Vec<Attribute> {
+        let def_id = self.def_id();
+        with(|cx| cx.get_all_attrs(def_id))
+    }
+
+    /// Return the documentation of this definition, i.e. the text of its doc comments and
+    /// `#[doc = "..."]` attributes, one line per line of documentation.
+    fn doc_comment(&self) -> Option<String> {
+        let def_id = self.def_id();
+        with(|cx| cx.doc_comment(def_id))
+    }
+
+    /// Return where this definition can be accessed from.
+    ///
+    /// This is `None` for definitions that don't have a visibility, like impls, closures and
+    /// generic parameters.
+    fn visibility(&self) -> Option<Visibility> {
+        let def_id = self.def_id();
+        with(|cx| cx.visibility(def_id))
+    }
+
+    /// Return the module that contains this definition.
+    ///
+    /// This is `None` for the root module of a crate.
+    fn parent_module(&self) -> Option<ModuleDef> {
+        let def_id = self.def_id();
+        with(|cx| cx.parent_module(def_id))
+    }
+}
+
+/// An attribute of a definition, such as `#[inline]`, `#[kani::proof]` or a doc comment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribute {
+    /// The attribute as it is written in the source, e.g. `#[inline(always)]` or `/// Docs.`.
+    pub value: String,
+    pub span: Span,
+}
+
+/// Where a definition can be accessed from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Visibility {
+    /// The definition is visible everywhere.
+    Public,
+    /// The definition is only visible in the given module and its descendants, e.g. because it
+    /// is private or `pub(crate)`.
+    Restricted(ModuleDef),
 }
 
 macro_rules! crate_def {
@@ -57,7 +115,7 @@ macro_rules! crate_def {
       $vis:vis $name:ident $(;)?
     ) => {
         $(#[$attr])*
-        #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, serde::Serialize, serde::Deserialize)]
         $vis struct $name(pub DefId);
 
         impl CrateDef for $name {
@@ -0,0 +1,500 @@
+//! A read-only view of the bodies of local items at the level of the source code, i.e., before
+//! they are lowered to MIR.
+//!
+//! This follows the HIR of the compiler, with the types computed by type checking. Expressions
+//! and patterns use the same [Ty] and [Span] handles as the MIR [Body](crate::mir::Body) of the
+//! item, so they can be cross-referenced with it. Some desugarings are visible, e.g., a `for` loop
+//! is a [Loop](ExprKind::Loop) whose source is [LoopSource::ForLoop].
+
+use crate::mir::{BorrowKind, FieldIdx, Mutability};
+use crate::ty::{ClosureDef, ClosureKind, CoroutineDef, FnDef, GenericArgs, Span, Ty, VariantIdx};
+use crate::{DefId, Opaque, Symbol};
+use serde::{Deserialize, Serialize};
+
+/// The body of a function, closure, constant or static.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Body {
+    pub params: Vec<Param>,
+    pub value: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Param {
+    pub pat: Pat,
+    pub span: Span,
+}
+
+/// Identifies a variable that is introduced by a [binding](PatKind::Binding). It is unique in the
+/// body of an item, including the closures it contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct BindingId(pub usize);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Expr {
+    pub kind: ExprKind,
+    /// The type of the expression, before adjustments like auto-dereferencing and coercions.
+    pub ty: Ty,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExprKind {
+    /// An array, e.g., `[a, b, c]`.
+    Array(Vec<Expr>),
+    /// An array with repeated elements, e.g., `[a; N]`. The count is part of the type.
+    Repeat(Box<Expr>),
+    Tuple(Vec<Expr>),
+    /// A call of a function, closure or function pointer. The type of the callee determines which.
+    Call(Box<Expr>, Vec<Expr>),
+    MethodCall {
+        method: FnDef,
+        generic_args: GenericArgs,
+        name: Symbol,
+        receiver: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// A binary operation. For types that overload the operator, this is a call to the method of
+    /// the operator trait.
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Lit(LitKind),
+    /// A cast to the type of the expression, e.g., `a as u8`.
+    Cast(Box<Expr>),
+    /// A `let` expression in the condition of an `if` or `while`.
+    Let {
+        pat: Box<Pat>,
+        init: Box<Expr>,
+    },
+    If {
+        cond: Box<Expr>,
+        then: Box<Expr>,
+        els: Option<Box<Expr>>,
+    },
+    /// A loop. `while` and `for` loops are desugared to a `loop`.
+    Loop {
+        body: Block,
+        label: Option<Symbol>,
+        source: LoopSource,
+    },
+    /// A `match`. The `?` operator and `for` loops are desugared to a `match`.
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<Arm>,
+        source: MatchSource,
+    },
+    Closure(Closure),
+    /// A coroutine, e.g., an `async` block.
+    Coroutine(Coroutine),
+    Block(Block, Option<Symbol>),
+    Assign(Box<Expr>, Box<Expr>),
+    /// A compound assignment, e.g., `a += b`.
+    AssignOp(BinOp, Box<Expr>, Box<Expr>),
+    Field {
+        base: Box<Expr>,
+        name: Symbol,
+        field: FieldIdx,
+    },
+    Index(Box<Expr>, Box<Expr>),
+    /// A local variable, including the variables that are captured by a closure.
+    Local(BindingId),
+    /// A path to an item, e.g., a function, a constant, a static, or a unit struct or variant.
+    Path {
+        def: DefId,
+        generic_args: GenericArgs,
+    },
+    /// A borrow, e.g., `&a`, or a raw borrow, e.g., `&raw const a`.
+    AddrOf {
+        raw: bool,
+        mutability: Mutability,
+        expr: Box<Expr>,
+    },
+    Break {
+        label: Option<Symbol>,
+        value: Option<Box<Expr>>,
+    },
+    Continue {
+        label: Option<Symbol>,
+    },
+    Return(Option<Box<Expr>>),
+    /// A struct or variant expression, e.g., `S { a, ..base }`.
+    Struct {
+        variant: VariantIdx,
+        fields: Vec<FieldExpr>,
+        base: Option<Box<Expr>>,
+    },
+    /// An expression that this view doesn't support yet, e.g., inline assembly.
+    Other(Opaque),
+    /// An expression that could not be type checked.
+    Err,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldExpr {
+    pub field: FieldIdx,
+    pub expr: Expr,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Arm {
+    pub pat: Pat,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Closure {
+    pub def: ClosureDef,
+    /// The closure trait that was inferred for the closure.
+    pub kind: ClosureKind,
+    pub params: Vec<Param>,
+    pub body: Box<Expr>,
+    /// Whether this is a `move` closure.
+    pub by_move: bool,
+    pub captures: Vec<Capture>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coroutine {
+    pub def: CoroutineDef,
+    pub body: Box<Expr>,
+    /// Whether this is a `move` coroutine.
+    pub by_move: bool,
+    pub captures: Vec<Capture>,
+}
+
+/// A variable, or a part of it, that is captured by a closure or coroutine.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capture {
+    pub var: BindingId,
+    /// How the variable is captured, which is inferred from its uses unless the closure is a
+    /// `move` closure. Unique immutable borrows are represented like mutable borrows of closure
+    /// captures.
+    pub kind: CaptureKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureKind {
+    ByValue,
+    ByRef(BorrowKind),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    /// The expression at the end of the block that is its value.
+    pub expr: Option<Box<Expr>>,
+    /// Whether this is an `unsafe` block.
+    pub is_unsafe: bool,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+/// A statement. The items that are declared in a block are not part of the body.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StmtKind {
+    /// A `let` statement, e.g., `let Some(a) = b else { return }`.
+    Let {
+        pat: Pat,
+        init: Option<Expr>,
+        els: Option<Block>,
+    },
+    /// An expression without a trailing semicolon, whose type is `()`.
+    Expr(Expr),
+    Semi(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pat {
+    pub kind: PatKind,
+    pub ty: Ty,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatKind {
+    Wild,
+    Binding {
+        id: BindingId,
+        name: Symbol,
+        mode: BindingMode,
+        subpattern: Option<Box<Pat>>,
+    },
+    /// A struct or variant pattern with named fields, e.g., `S { a, .. }`.
+    Struct {
+        variant: VariantIdx,
+        fields: Vec<FieldPat>,
+        rest: bool,
+    },
+    /// A tuple struct or variant pattern, e.g., `Some(a)`.
+    TupleStruct {
+        variant: VariantIdx,
+        fields: Vec<FieldPat>,
+        rest: bool,
+    },
+    /// A path to a unit struct or variant, or to a constant.
+    Path(DefId),
+    /// A tuple pattern. `rest` is the position of `..`, if there is one.
+    Tuple {
+        elems: Vec<Pat>,
+        rest: Option<usize>,
+    },
+    Or(Vec<Pat>),
+    Box(Box<Pat>),
+    Ref(Box<Pat>, Mutability),
+    Lit(Box<Expr>),
+    Range {
+        lo: Option<Box<Expr>>,
+        hi: Option<Box<Expr>>,
+        inclusive: bool,
+    },
+    /// A slice or array pattern, e.g., `[a, rest @ .., b]`.
+    Slice {
+        prefix: Vec<Pat>,
+        middle: Option<Box<Pat>>,
+        suffix: Vec<Pat>,
+    },
+    /// The never pattern `!`.
+    Never,
+    Err,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldPat {
+    pub field: FieldIdx,
+    pub pat: Pat,
+}
+
+/// How a binding binds the matched value, which may differ from its annotation due to default
+/// binding modes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindingMode {
+    ByValue(Mutability),
+    ByRef(Mutability),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LitKind {
+    Str(Symbol),
+    ByteStr(Vec<u8>),
+    CStr(Vec<u8>),
+    Byte(u8),
+    Char(char),
+    /// An integer, whose type is the type of the expression.
+    Int(u128),
+    /// A float, as it is written in the source code.
+    Float(Symbol),
+    Bool(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    /// The lazy boolean operator `&&`.
+    And,
+    /// The lazy boolean operator `||`.
+    Or,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Lt,
+    Le,
+    Ne,
+    Ge,
+    Gt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnOp {
+    Deref,
+    Not,
+    Neg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoopSource {
+    Loop,
+    While,
+    ForLoop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchSource {
+    Normal,
+    ForLoopDesugar,
+    TryDesugar,
+    AwaitDesugar,
+    FormatArgs,
+}
+
+/// A visitor of the expressions, patterns, blocks and statements of a [Body].
+///
+/// The `visit_*` methods can be overridden to inspect nodes. They call the corresponding
+/// `super_*` method, which visits the children of the node.
+pub trait HirVisitor {
+    fn visit_body(&mut self, body: &Body) {
+        self.super_body(body)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.super_expr(expr)
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        self.super_pat(pat)
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.super_block(block)
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.super_stmt(stmt)
+    }
+
+    fn super_body(&mut self, body: &Body) {
+        for param in &body.params {
+            self.visit_pat(&param.pat);
+        }
+        self.visit_expr(&body.value);
+    }
+
+    fn super_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Array(exprs) | ExprKind::Tuple(exprs) => {
+                exprs.iter().for_each(|expr| self.visit_expr(expr))
+            }
+            ExprKind::Repeat(expr)
+            | ExprKind::Unary(_, expr)
+            | ExprKind::Cast(expr)
+            | ExprKind::Field { base: expr, .. }
+            | ExprKind::AddrOf { expr, .. } => self.visit_expr(expr),
+            ExprKind::Call(callee, args) => {
+                self.visit_expr(callee);
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            }
+            ExprKind::MethodCall { receiver, args, .. } => {
+                self.visit_expr(receiver);
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            }
+            ExprKind::Binary(_, lhs, rhs)
+            | ExprKind::Assign(lhs, rhs)
+            | ExprKind::AssignOp(_, lhs, rhs)
+            | ExprKind::Index(lhs, rhs) => {
+                self.visit_expr(lhs);
+                self.visit_expr(rhs);
+            }
+            ExprKind::Let { pat, init } => {
+                self.visit_expr(init);
+                self.visit_pat(pat);
+            }
+            ExprKind::If { cond, then, els } => {
+                self.visit_expr(cond);
+                self.visit_expr(then);
+                if let Some(els) = els {
+                    self.visit_expr(els);
+                }
+            }
+            ExprKind::Loop { body: block, .. } | ExprKind::Block(block, _) => {
+                self.visit_block(block)
+            }
+            ExprKind::Match { scrutinee, arms, .. } => {
+                self.visit_expr(scrutinee);
+                for arm in arms {
+                    self.visit_pat(&arm.pat);
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&arm.body);
+                }
+            }
+            ExprKind::Closure(closure) => {
+                for param in &closure.params {
+                    self.visit_pat(&param.pat);
+                }
+                self.visit_expr(&closure.body);
+            }
+            ExprKind::Coroutine(coroutine) => self.visit_expr(&coroutine.body),
+            ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::Struct { fields, base, .. } => {
+                fields.iter().for_each(|field| self.visit_expr(&field.expr));
+                if let Some(base) = base {
+                    self.visit_expr(base);
+                }
+            }
+            ExprKind::Lit(_)
+            | ExprKind::Local(_)
+            | ExprKind::Path { .. }
+            | ExprKind::Continue { .. }
+            | ExprKind::Other(_)
+            | ExprKind::Err => {}
+        }
+    }
+
+    fn super_pat(&mut self, pat: &Pat) {
+        match &pat.kind {
+            PatKind::Binding { subpattern: Some(pat), .. }
+            | PatKind::Box(pat)
+            | PatKind::Ref(pat, _) => self.visit_pat(pat),
+            PatKind::Struct { fields, .. } | PatKind::TupleStruct { fields, .. } => {
+                fields.iter().for_each(|field| self.visit_pat(&field.pat))
+            }
+            PatKind::Tuple { elems: pats, .. } | PatKind::Or(pats) => {
+                pats.iter().for_each(|pat| self.visit_pat(pat))
+            }
+            PatKind::Lit(expr) => self.visit_expr(expr),
+            PatKind::Range { lo, hi, .. } => {
+                lo.iter().chain(hi).for_each(|expr| self.visit_expr(expr))
+            }
+            PatKind::Slice { prefix, middle, suffix } => {
+                prefix.iter().chain(middle.as_deref()).chain(suffix).for_each(|pat| {
+                    self.visit_pat(pat);
+                })
+            }
+            PatKind::Wild
+            | PatKind::Binding { subpattern: None, .. }
+            | PatKind::Path(_)
+            | PatKind::Never
+            | PatKind::Err => {}
+        }
+    }
+
+    fn super_block(&mut self, block: &Block) {
+        block.stmts.iter().for_each(|stmt| self.visit_stmt(stmt));
+        if let Some(expr) = &block.expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    fn super_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { pat, init, els } => {
+                if let Some(init) = init {
+                    self.visit_expr(init);
+                }
+                self.visit_pat(pat);
+                if let Some(els) = els {
+                    self.visit_block(els);
+                }
+            }
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => self.visit_expr(expr),
+        }
+    }
+}
@@ -27,10 +27,13 @@
 pub use crate::crate_def::CrateDef;
 pub use crate::crate_def::DefId;
 pub use crate::error::*;
+use crate::mir::borrowck::BorrowckFacts;
+use crate::mir::mono::CodegenUnit;
 use crate::mir::pretty::function_name;
 use crate::mir::Body;
 use crate::mir::Mutability;
 use crate::ty::{ForeignModuleDef, ImplDef, IndexedVal, Span, TraitDef, Ty};
+use serde::{Deserialize, Serialize};
 
 pub mod abi;
 #[macro_use]
@@ -38,7 +41,9 @@
 pub mod compiler_interface;
 #[macro_use]
 pub mod error;
+pub mod hir;
 pub mod mir;
+pub mod snapshot;
 pub mod target;
 pub mod ty;
 pub mod visitor;
@@ -78,7 +83,7 @@ fn to_index(&self) -> usize {
 pub type ImplTraitDecls = Vec<ImplDef>;
 
 /// Holds information about a crate.
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct Crate {
     pub id: CrateNum,
     pub name: Symbol,
@@ -102,7 +107,7 @@ pub fn trait_impls(&self) -> ImplTraitDecls {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum ItemKind {
     Fn,
     Static,
@@ -110,7 +115,7 @@ pub enum ItemKind {
     Ctor(CtorKind),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum CtorKind {
     Const,
     Fn,
@@ -128,6 +133,21 @@ pub fn body(&self) -> mir::Body {
         with(|cx| cx.mir_body(self.0))
     }
 
+    /// The facts computed by the borrow checker for the body of this item.
+    ///
+    /// They are only available if the tool asked the compiler to collect them before it started,
+    /// e.g. with `rustc_internal::borrowck::collect_borrowck_facts`.
+    pub fn borrowck_facts(&self) -> Result<BorrowckFacts, Error> {
+        with(|cx| cx.borrowck_facts(self.0))
+    }
+
+    /// The body of this item at the level of the source code, see [hir].
+    ///
+    /// This is only available for local items that have a body, e.g., not for constructors.
+    pub fn hir_body(&self) -> Result<hir::Body, Error> {
+        with(|cx| cx.hir_body(self.0))
+    }
+
     pub fn span(&self) -> Span {
         with(|cx| cx.span_of_an_item(self.0))
     }
@@ -154,6 +174,11 @@ pub fn dump<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
     }
 }
 
+crate_def! {
+    /// A module, including the root module of a crate.
+    pub ModuleDef;
+}
+
 /// Return the function where execution starts if the current
 /// crate defines that. This is usually `main`, but could be
 /// `start` if the crate is a no-std crate.
@@ -189,8 +214,16 @@ pub fn all_trait_impls() -> ImplTraitDecls {
     with(|cx| cx.all_trait_impls())
 }
 
+/// Collect all mono items needed to compile the local crate, and partition them into codegen
+/// units the same way code generation does.
+///
+/// Items can be part of more than one codegen unit, e.g., when they are inlined.
+pub fn codegen_units() -> Vec<CodegenUnit> {
+    with(|cx| cx.codegen_units())
+}
+
 /// A type that provides internal information but that can still be used for debug purpose.
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Opaque(String);
 
 impl std::fmt::Display for Opaque {
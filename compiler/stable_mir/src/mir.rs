@@ -1,8 +1,12 @@
 pub mod alloc;
 mod body;
+pub mod borrowck;
+pub mod call_graph;
+pub mod dataflow;
+pub mod interpret;
 pub mod mono;
 pub mod pretty;
 pub mod visit;
 
 pub use body::*;
-pub use visit::MirVisitor;
+pub use visit::{MirVisitor, MutMirVisitor};
@@ -3,11 +3,12 @@
 use crate::target::{Endian, MachineInfo};
 use crate::ty::{Allocation, Binder, ExistentialTraitRef, IndexedVal, Ty};
 use crate::{with, Error};
+use serde::{Deserialize, Serialize};
 use std::io::Read;
 
 /// An allocation in the SMIR global memory can be either a function pointer,
 /// a static, or a "real" allocation with some data in it.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum GlobalAlloc {
     /// The alloc ID is used as a function pointer.
     Function(Instance),
@@ -40,7 +41,7 @@ pub fn vtable_allocation(&self) -> Option<AllocId> {
 }
 
 /// A unique identification number for each provenance
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub struct AllocId(usize);
 
 impl IndexedVal for AllocId {
@@ -81,3 +82,15 @@ pub(crate) fn read_target_int(mut bytes: &[u8]) -> Result<i128, Error> {
         }
     }
 }
+
+/// Utility function used to write an unsigned integer into `size` bytes of allocation data. The
+/// value is truncated to that size.
+pub(crate) fn write_target_uint(value: u128, size: usize) -> Result<Vec<u8>, Error> {
+    if size > std::mem::size_of::<u128>() {
+        return Err(error!("Expected size <= 16 bytes, but found {size} instead"));
+    }
+    match MachineInfo::target_endianess() {
+        Endian::Little => Ok(value.to_le_bytes()[..size].to_vec()),
+        Endian::Big => Ok(value.to_be_bytes()[16 - size..].to_vec()),
+    }
+}
@@ -0,0 +1,91 @@
+//! The facts computed by the borrow checker for a body, in the form they are dumped by
+//! `-Znll-facts` and given to Polonius.
+//!
+//! The borrow checker runs on the body before it is optimized, where every region is a region
+//! variable ([RegionKind::ReVar](crate::ty::RegionKind::ReVar)). The facts refer to the locals,
+//! regions and locations of that body, which is part of the [BorrowckFacts].
+
+use crate::mir::dataflow::ProgramPoint;
+use crate::mir::{Body, BorrowKind, Local, Place};
+use crate::ty::RegionVid;
+use serde::{Deserialize, Serialize};
+
+/// The index of a loan in [BorrowckFacts::loans].
+pub type LoanIdx = usize;
+
+/// A point of the control flow graph of the borrow checker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub location: ProgramPoint,
+    pub kind: PointKind,
+}
+
+/// Every statement and terminator has two points: one at its start, and one in the middle where
+/// it takes effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PointKind {
+    Start,
+    Mid,
+}
+
+/// A borrow in the body, which creates a loan of the borrowed place.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loan {
+    pub kind: BorrowKind,
+    /// The region of the reference that is created by the borrow.
+    pub region: RegionVid,
+    pub borrowed_place: Place,
+    /// The place the reference is assigned to.
+    pub assigned_place: Place,
+    /// The location of the borrow, where the loan is reserved.
+    pub reserve_location: ProgramPoint,
+    pub activation: TwoPhaseActivation,
+}
+
+/// Where a two-phase borrow is activated. A two-phase borrow, e.g. the implicit `&mut vec` of
+/// `vec.push(vec.len())`, only conflicts with reads of the borrowed place once it is activated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TwoPhaseActivation {
+    NotTwoPhase,
+    /// The reference is never used.
+    NotActivated,
+    ActivatedAt(ProgramPoint),
+}
+
+/// The facts of the borrow checker for one body, see the [module documentation](self).
+///
+/// The facts about move paths are not included.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BorrowckFacts {
+    /// The body that was borrow checked.
+    pub body: Body,
+    pub loans: Vec<Loan>,
+    /// The regions that are not local to the body, like `'static` and the lifetime parameters.
+    pub universal_region: Vec<RegionVid>,
+    /// `(region, loan, point)`: The loan is created at the point, and its reference has the
+    /// region.
+    pub loan_issued_at: Vec<(RegionVid, LoanIdx, Point)>,
+    /// `(loan, point)`: The loan is killed at the point, because the borrowed place, or a prefix of
+    /// it, is overwritten.
+    pub loan_killed_at: Vec<(LoanIdx, Point)>,
+    /// `(point, loan)`: The point accesses the borrowed place in a way that is an error while the
+    /// loan is live.
+    pub loan_invalidated_at: Vec<(Point, LoanIdx)>,
+    /// `(sup, sub, point)`: The outlives constraint `sup: sub` is required at the point.
+    pub subset_base: Vec<(RegionVid, RegionVid, Point)>,
+    /// `(sup, sub)`: The universal regions that are known to outlive each other.
+    pub known_placeholder_subset: Vec<(RegionVid, RegionVid)>,
+    /// `(from, to)`: The edges of the control flow graph, including the edges of false edges and
+    /// false unwinds that are not part of the [body](Self::body).
+    pub cfg_edge: Vec<(Point, Point)>,
+    /// `(local, point)`: The local is used at the point.
+    pub var_used_at: Vec<(Local, Point)>,
+    /// `(local, point)`: The local is assigned or goes out of scope at the point.
+    pub var_defined_at: Vec<(Local, Point)>,
+    /// `(local, point)`: The local is dropped at the point.
+    pub var_dropped_at: Vec<(Local, Point)>,
+    /// `(local, region)`: Using the local requires the region to be live.
+    pub use_of_var_derefs_origin: Vec<(Local, RegionVid)>,
+    /// `(local, region)`: Dropping the local requires the region to be live.
+    pub drop_of_var_derefs_origin: Vec<(Local, RegionVid)>,
+}
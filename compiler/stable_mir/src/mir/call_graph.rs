@@ -0,0 +1,418 @@
+//! A whole-program call graph over the [Instance]s that are reachable from a set of roots.
+//!
+//! The graph is built from the monomorphized bodies of the instances, and it contains an edge
+//! for every:
+//! - call to a function that is known statically, including compiler generated shims;
+//! - drop of a value whose type has drop glue;
+//! - call through a vtable, to the method of every type that is unsized to the trait object type
+//!   somewhere in the graph;
+//! - function or non-capturing closure that is coerced to a function pointer;
+//! - function pointer or vtable in a constant, or in a static that a constant points to.
+//!
+//! Calls through function pointers are not resolved, the functions that they may call are
+//! represented by the edges to the functions whose address is taken instead.
+
+use crate::mir::alloc::{AllocId, GlobalAlloc};
+use crate::mir::mono::{Instance, InstanceKind};
+use crate::mir::visit::Location;
+use crate::mir::{
+    Body, CastKind, Constant, MirVisitor, PointerCoercion, Rvalue, Terminator, TerminatorKind,
+};
+use crate::target::MachineInfo;
+use crate::ty::{
+    Allocation, Binder, ClosureKind, ConstantKind, ExistentialTraitRef, GenericArgKind, LineInfo,
+    RigidTy, Span, Ty, TyKind,
+};
+use crate::{Filename, Symbol};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+
+/// How the caller of an edge of the [CallGraph] may call its callee.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CallKind {
+    /// A call to a function that is known statically.
+    Direct,
+    /// A call to the drop glue of a type, either directly or through the vtable of a trait object.
+    DropGlue,
+    /// A call to a trait object method, to the implementation of one of the types that are unsized
+    /// to the trait object type.
+    Virtual,
+    /// A coercion of the callee to a function pointer, that may then be called.
+    FnPtr,
+}
+
+/// A call site, or a place where the address of a function is taken.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallEdge {
+    pub caller: Instance,
+    pub callee: Instance,
+    pub kind: CallKind,
+    pub span: Span,
+}
+
+/// A call graph over the instances that are reachable from a set of roots.
+#[derive(Clone, Debug, Default)]
+pub struct CallGraph {
+    /// The instances in the order they were reached in.
+    nodes: Vec<Instance>,
+    edges: Vec<CallEdge>,
+}
+
+impl CallGraph {
+    /// Builds the call graph of the instances that are reachable from `roots`, e.g. from the
+    /// instance of the entry function of a binary.
+    pub fn new(roots: impl IntoIterator<Item = Instance>) -> CallGraph {
+        let mut builder = Builder::default();
+        for root in roots {
+            builder.add_node(root);
+        }
+        while let Some(instance) = builder.queue.pop_front() {
+            if let Some(body) = instance.body() {
+                CallCollector { builder: &mut builder, caller: instance, body: &body }
+                    .visit_body(&body);
+            }
+        }
+        builder.graph
+    }
+
+    /// The instances of the graph, starting with its roots.
+    pub fn nodes(&self) -> &[Instance] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[CallEdge] {
+        &self.edges
+    }
+
+    /// The edges from `caller` to the instances it may call.
+    pub fn callees(&self, caller: Instance) -> impl Iterator<Item = &CallEdge> + '_ {
+        self.edges.iter().filter(move |edge| edge.caller == caller)
+    }
+
+    /// The edges from the instances that may call `callee`.
+    pub fn callers(&self, callee: Instance) -> impl Iterator<Item = &CallEdge> + '_ {
+        self.edges.iter().filter(move |edge| edge.callee == callee)
+    }
+
+    /// Writes the graph in the DOT format of Graphviz. Edges of the same kind between the same
+    /// instances are only written once.
+    pub fn write_dot<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        let ids = self.node_ids();
+        writeln!(w, "digraph call_graph {{")?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            writeln!(w, "    n{id} [label=\"{}\"];", escape_dot(&node.name()))?;
+        }
+        let mut written = HashSet::new();
+        for edge in &self.edges {
+            let (caller, callee) = (ids[&edge.caller], ids[&edge.callee]);
+            if !written.insert((caller, callee, edge.kind)) {
+                continue;
+            }
+            let style = match edge.kind {
+                CallKind::Direct => "solid",
+                CallKind::DropGlue => "bold",
+                CallKind::Virtual => "dashed",
+                CallKind::FnPtr => "dotted",
+            };
+            writeln!(w, "    n{caller} -> n{callee} [label=\"{:?}\", style={style}];", edge.kind)?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Writes the graph as JSON. Nodes are referred to by their index in the `nodes` list.
+    pub fn write_json<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        let ids = self.node_ids();
+        let graph = CallGraphJson {
+            nodes: self
+                .nodes
+                .iter()
+                .map(|node| NodeJson {
+                    name: node.name(),
+                    mangled_name: node.mangled_name(),
+                    kind: node.kind,
+                    has_body: node.has_body(),
+                })
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .map(|edge| EdgeJson {
+                    caller: ids[&edge.caller],
+                    callee: ids[&edge.callee],
+                    kind: edge.kind,
+                    file: edge.span.get_filename(),
+                    lines: edge.span.get_lines(),
+                })
+                .collect(),
+        };
+        serde_json::to_writer(w, &graph).map_err(io::Error::from)
+    }
+
+    fn node_ids(&self) -> HashMap<Instance, usize> {
+        self.nodes.iter().enumerate().map(|(id, node)| (*node, id)).collect()
+    }
+}
+
+#[derive(Serialize)]
+struct CallGraphJson {
+    nodes: Vec<NodeJson>,
+    edges: Vec<EdgeJson>,
+}
+
+#[derive(Serialize)]
+struct NodeJson {
+    name: Symbol,
+    mangled_name: Symbol,
+    kind: InstanceKind,
+    has_body: bool,
+}
+
+#[derive(Serialize)]
+struct EdgeJson {
+    caller: usize,
+    callee: usize,
+    kind: CallKind,
+    file: Filename,
+    lines: LineInfo,
+}
+
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The principal trait of a trait object type, which determines the layout of its vtables. It is
+/// `None` for trait objects of auto traits only.
+type Principal = Option<Binder<ExistentialTraitRef>>;
+
+#[derive(Default)]
+struct Builder {
+    graph: CallGraph,
+    visited: HashSet<Instance>,
+    /// The instances whose body still has to be visited.
+    queue: VecDeque<Instance>,
+    /// The vtables that are created by the instances visited so far.
+    vtables: HashSet<AllocId>,
+    /// The functions in the vtables created so far, with their index in the vtable.
+    vtable_entries: Vec<(Principal, usize, Instance)>,
+    /// The calls through vtables found so far, which also call the methods of vtables that are
+    /// only created later on.
+    virtual_calls: Vec<VirtualCall>,
+}
+
+struct VirtualCall {
+    caller: Instance,
+    principal: Principal,
+    idx: usize,
+    kind: CallKind,
+    span: Span,
+}
+
+impl Builder {
+    fn add_node(&mut self, instance: Instance) {
+        if self.visited.insert(instance) {
+            self.graph.nodes.push(instance);
+            self.queue.push_back(instance);
+        }
+    }
+
+    fn add_edge(&mut self, caller: Instance, callee: Instance, kind: CallKind, span: Span) {
+        self.add_node(callee);
+        self.graph.edges.push(CallEdge { caller, callee, kind, span });
+    }
+
+    /// Adds the edges of a call through the vtable of a trait object.
+    fn add_virtual_call(&mut self, call: VirtualCall) {
+        let callees: Vec<_> = self
+            .vtable_entries
+            .iter()
+            .filter(|(principal, idx, _)| *principal == call.principal && *idx == call.idx)
+            .map(|(_, _, callee)| *callee)
+            .collect();
+        for callee in callees {
+            self.add_edge(call.caller, callee, call.kind, call.span);
+        }
+        self.virtual_calls.push(call);
+    }
+
+    /// Adds the vtable of `ty` for trait objects with the given principal trait, and the edges
+    /// from the calls through these vtables to its methods.
+    fn add_vtable(&mut self, ty: Ty, principal: Principal) {
+        let Some(alloc_id) = GlobalAlloc::VTable(ty, principal.clone()).vtable_allocation() else {
+            return;
+        };
+        if !self.vtables.insert(alloc_id) {
+            return;
+        }
+        let GlobalAlloc::Memory(allocation) = GlobalAlloc::from(alloc_id) else { return };
+        let pointer_size = MachineInfo::target_pointer_width().bytes();
+        for (offset, prov) in allocation.provenance.ptrs {
+            let GlobalAlloc::Function(callee) = GlobalAlloc::from(prov.0) else { continue };
+            let idx = offset / pointer_size;
+            let calls: Vec<_> = self
+                .virtual_calls
+                .iter()
+                .filter(|call| call.principal == principal && call.idx == idx)
+                .map(|call| (call.caller, call.kind, call.span))
+                .collect();
+            for (caller, kind, span) in calls {
+                self.add_edge(caller, callee, kind, span);
+            }
+            self.vtable_entries.push((principal.clone(), idx, callee));
+        }
+    }
+
+    /// Adds the functions and vtables that `allocation` points to, directly or through other
+    /// allocations and statics, e.g. the vtable in a static of type `&dyn Trait`.
+    fn add_allocation(&mut self, caller: Instance, allocation: &Allocation, span: Span) {
+        let mut visited = HashSet::new();
+        let mut allocations = vec![allocation.clone()];
+        while let Some(allocation) = allocations.pop() {
+            for (_, prov) in allocation.provenance.ptrs {
+                if !visited.insert(prov.0) {
+                    continue;
+                }
+                match GlobalAlloc::from(prov.0) {
+                    GlobalAlloc::Function(callee) => {
+                        self.add_edge(caller, callee, CallKind::FnPtr, span)
+                    }
+                    GlobalAlloc::VTable(ty, principal) => self.add_vtable(ty, principal),
+                    GlobalAlloc::Static(def) => {
+                        if let Ok(initializer) = def.eval_initializer() {
+                            allocations.push(initializer);
+                        }
+                    }
+                    GlobalAlloc::Memory(allocation) => allocations.push(allocation),
+                }
+            }
+        }
+    }
+}
+
+/// Collects the edges from the body of an instance.
+struct CallCollector<'a> {
+    builder: &'a mut Builder,
+    caller: Instance,
+    body: &'a Body,
+}
+
+impl MirVisitor for CallCollector<'_> {
+    fn visit_terminator(&mut self, term: &Terminator, location: Location) {
+        match &term.kind {
+            TerminatorKind::Call { func, .. } => {
+                let Ok(ty) = func.ty(self.body.locals()) else { return };
+                let TyKind::RigidTy(RigidTy::FnDef(def, args)) = ty.kind() else { return };
+                let Ok(callee) = Instance::resolve(def, &args) else { return };
+                match callee.kind {
+                    InstanceKind::Virtual { idx } => {
+                        let Some(GenericArgKind::Type(self_ty)) = callee.args().0.first().cloned()
+                        else {
+                            return;
+                        };
+                        self.builder.add_virtual_call(VirtualCall {
+                            caller: self.caller,
+                            principal: self_ty.kind().trait_principal(),
+                            idx,
+                            kind: CallKind::Virtual,
+                            span: term.span,
+                        });
+                    }
+                    _ => self.builder.add_edge(self.caller, callee, CallKind::Direct, term.span),
+                }
+            }
+            TerminatorKind::Drop { place, .. } => {
+                let Ok(ty) = place.ty(self.body.locals()) else { return };
+                let kind = ty.kind();
+                if kind.is_trait() {
+                    // The drop glue of the type is the first entry of the vtable.
+                    self.builder.add_virtual_call(VirtualCall {
+                        caller: self.caller,
+                        principal: kind.trait_principal(),
+                        idx: 0,
+                        kind: CallKind::DropGlue,
+                        span: term.span,
+                    });
+                } else {
+                    let callee = Instance::resolve_drop_in_place(ty);
+                    if !callee.is_empty_shim() {
+                        self.builder.add_edge(self.caller, callee, CallKind::DropGlue, term.span);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.super_terminator(term, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue, location: Location) {
+        if let Rvalue::Cast(kind, operand, target) = rvalue {
+            if let Ok(source) = operand.ty(self.body.locals()) {
+                match (kind, source.kind()) {
+                    (
+                        CastKind::PointerCoercion(PointerCoercion::ReifyFnPointer),
+                        TyKind::RigidTy(RigidTy::FnDef(def, args)),
+                    ) => {
+                        if let Ok(callee) = Instance::resolve_for_fn_ptr(def, &args) {
+                            let span = location.span();
+                            self.builder.add_edge(self.caller, callee, CallKind::FnPtr, span);
+                        }
+                    }
+                    (
+                        CastKind::PointerCoercion(PointerCoercion::ClosureFnPointer(_)),
+                        TyKind::RigidTy(RigidTy::Closure(def, args)),
+                    ) => {
+                        if let Ok(callee) =
+                            Instance::resolve_closure(def, &args, ClosureKind::FnOnce)
+                        {
+                            let span = location.span();
+                            self.builder.add_edge(self.caller, callee, CallKind::FnPtr, span);
+                        }
+                    }
+                    (CastKind::PointerCoercion(PointerCoercion::Unsize) | CastKind::DynStar, _) => {
+                        if let Some((ty, dyn_ty)) = vtable_types(source, *target) {
+                            self.builder.add_vtable(ty, dyn_ty.kind().trait_principal());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_constant(&mut self, constant: &Constant, location: Location) {
+        if let ConstantKind::Allocated(allocation) = constant.literal.kind() {
+            self.builder.add_allocation(self.caller, allocation, constant.span);
+        }
+        self.super_constant(constant, location);
+    }
+}
+
+/// Finds the type and trait object type of the vtable that is created by an unsizing coercion
+/// from `source` to `target`, e.g. `Foo` and `dyn Trait` for a coercion from `Box<Foo>` to
+/// `Box<dyn Trait>`.
+fn vtable_types(source: Ty, target: Ty) -> Option<(Ty, Ty)> {
+    match (source.kind().rigid()?, target.kind().rigid()?) {
+        (RigidTy::Dynamic(..), _) => None,
+        (_, RigidTy::Dynamic(..)) => Some((source, target)),
+        (
+            RigidTy::Ref(_, source, _) | RigidTy::RawPtr(source, _),
+            RigidTy::Ref(_, target, _) | RigidTy::RawPtr(target, _),
+        ) => vtable_types(*source, *target),
+        // Smart pointers, e.g. `Box` or `Rc`, are unsized through the generic argument that
+        // differs between both types.
+        (RigidTy::Adt(source_def, source_args), RigidTy::Adt(target_def, target_args))
+            if source_def == target_def =>
+        {
+            source_args.0.iter().zip(&target_args.0).find_map(|args| match args {
+                (GenericArgKind::Type(source), GenericArgKind::Type(target))
+                    if source != target =>
+                {
+                    vtable_types(*source, *target)
+                }
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,328 @@
+//! A framework for dataflow analyses on the MIR [Body] of a function.
+//!
+//! An analysis implements [Analysis] by describing how each statement and terminator changes its
+//! state. [Analysis::iterate_to_fixpoint] then computes the state at the start of every basic
+//! block, and the [Results] give access to the state before and after every statement.
+//!
+//! This follows the dataflow framework of the compiler, and the analyses in this module mirror
+//! some of its analyses:
+//! - [MaybeLiveLocals], the locals whose current value may be read later on.
+//! - [MaybeInitializedLocals], the locals that may be (partially) initialized.
+//! - [MaybeBorrowedLocals], the locals that may have been borrowed, or had their address taken.
+
+use crate::mir::{BasicBlockIdx, Body, Place, Statement, Terminator, TerminatorKind};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+
+mod impls;
+
+pub use impls::{MaybeBorrowedLocals, MaybeInitializedLocals, MaybeLiveLocals};
+
+/// The direction in which an analysis propagates its state through the control flow graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the start of the function towards its returns, e.g. to find initialized locals.
+    Forward,
+    /// From the returns of the function towards its start, e.g. to find live locals.
+    Backward,
+}
+
+/// The location of a statement or terminator in a body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProgramPoint {
+    pub block: BasicBlockIdx,
+    /// The index of the statement in the block. The terminator is at the index one past the last
+    /// statement.
+    pub statement_index: usize,
+}
+
+/// The state of an analysis, which has to form a join-semilattice.
+pub trait JoinSemiLattice: Clone + Eq {
+    /// Computes the least upper bound of `self` and `other`, and stores it in `self`. Returns
+    /// whether `self` changed.
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+/// A dataflow analysis.
+pub trait Analysis {
+    /// The state that is tracked at every point of the body.
+    type Domain: JoinSemiLattice;
+
+    const DIRECTION: Direction;
+
+    /// The initial state of every basic block, which is usually the empty state.
+    fn bottom_value(&self, body: &Body) -> Self::Domain;
+
+    /// Changes the state at the start of the function, e.g. to mark the arguments as initialized.
+    /// This is only used by forward analyses.
+    fn initialize_start_block(&self, body: &Body, state: &mut Self::Domain) {
+        let _ = (body, state);
+    }
+
+    /// Applies the effect of executing `statement` on `state`.
+    fn apply_statement_effect(
+        &mut self,
+        state: &mut Self::Domain,
+        statement: &Statement,
+        location: ProgramPoint,
+    );
+
+    /// Applies the effect of executing `terminator` on `state`, on all its outgoing edges.
+    fn apply_terminator_effect(
+        &mut self,
+        state: &mut Self::Domain,
+        terminator: &Terminator,
+        location: ProgramPoint,
+    );
+
+    /// Applies the effect of a call in `block` returning successfully, i.e. of the write to its
+    /// `destination`. This effect is only applied on the edge to the return target of the call.
+    fn apply_call_return_effect(
+        &mut self,
+        state: &mut Self::Domain,
+        block: BasicBlockIdx,
+        destination: &Place,
+    ) {
+        let _ = (state, block, destination);
+    }
+
+    /// Computes the state at the start of every basic block.
+    fn iterate_to_fixpoint(mut self, body: &Body) -> Results<Self>
+    where
+        Self: Sized,
+    {
+        let mut entry_sets: Vec<_> = body.blocks.iter().map(|_| self.bottom_value(body)).collect();
+        if Self::DIRECTION == Direction::Forward && !entry_sets.is_empty() {
+            self.initialize_start_block(body, &mut entry_sets[0]);
+        }
+
+        let mut predecessors = vec![vec![]; body.blocks.len()];
+        for (block, data) in body.blocks.iter().enumerate() {
+            for successor in data.terminator.successors() {
+                predecessors[successor].push(block);
+            }
+        }
+
+        // Visit the blocks in order first, so that most of them are only visited once in forward
+        // analyses of functions without loops.
+        let mut worklist: VecDeque<_> = match Self::DIRECTION {
+            Direction::Forward => (0..body.blocks.len()).collect(),
+            Direction::Backward => (0..body.blocks.len()).rev().collect(),
+        };
+        let mut in_worklist = vec![true; body.blocks.len()];
+        while let Some(block) = worklist.pop_front() {
+            in_worklist[block] = false;
+            let data = &body.blocks[block];
+            let mut state = entry_sets[block].clone();
+            let mut propagate = |target: BasicBlockIdx, state: &Self::Domain| {
+                if entry_sets[target].join(state) && !in_worklist[target] {
+                    in_worklist[target] = true;
+                    worklist.push_back(target);
+                }
+            };
+            match Self::DIRECTION {
+                Direction::Forward => {
+                    for index in 0..=data.statements.len() {
+                        let location = ProgramPoint { block, statement_index: index };
+                        apply_effect(&mut self, &mut state, body, location);
+                    }
+                    let call_return = call_return(&data.terminator);
+                    for successor in data.terminator.successors() {
+                        match call_return {
+                            Some((target, destination)) if target == successor => {
+                                let mut state = state.clone();
+                                self.apply_call_return_effect(&mut state, block, destination);
+                                propagate(successor, &state);
+                            }
+                            _ => propagate(successor, &state),
+                        }
+                    }
+                }
+                Direction::Backward => {
+                    for index in (0..=data.statements.len()).rev() {
+                        let location = ProgramPoint { block, statement_index: index };
+                        apply_effect(&mut self, &mut state, body, location);
+                    }
+                    for &predecessor in &predecessors[block] {
+                        match call_return(&body.blocks[predecessor].terminator) {
+                            Some((target, destination)) if target == block => {
+                                let mut state = state.clone();
+                                self.apply_call_return_effect(&mut state, predecessor, destination);
+                                propagate(predecessor, &state);
+                            }
+                            _ => propagate(predecessor, &state),
+                        }
+                    }
+                }
+            }
+        }
+        Results { analysis: self, entry_sets }
+    }
+}
+
+/// Returns the return target and destination of a call terminator.
+fn call_return(terminator: &Terminator) -> Option<(BasicBlockIdx, &Place)> {
+    match &terminator.kind {
+        TerminatorKind::Call { destination, target: Some(target), .. } => {
+            Some((*target, destination))
+        }
+        _ => None,
+    }
+}
+
+fn apply_effect<A: Analysis>(
+    analysis: &mut A,
+    state: &mut A::Domain,
+    body: &Body,
+    location: ProgramPoint,
+) {
+    let data = &body.blocks[location.block];
+    match data.statements.get(location.statement_index) {
+        Some(statement) => analysis.apply_statement_effect(state, statement, location),
+        None => analysis.apply_terminator_effect(state, &data.terminator, location),
+    }
+}
+
+/// The result of running an analysis until it reached a fixpoint.
+///
+/// Only the state at the start of each basic block is stored, the state at other points is
+/// recomputed from it when it is requested.
+pub struct Results<A: Analysis> {
+    pub analysis: A,
+    /// The state at the start of each block for forward analyses, and at the end of each block
+    /// for backward ones.
+    entry_sets: Vec<A::Domain>,
+}
+
+impl<A: Analysis> Results<A> {
+    /// The state before the statement or terminator at `location` is executed.
+    pub fn state_before(&mut self, body: &Body, location: ProgramPoint) -> A::Domain {
+        let ProgramPoint { block, statement_index } = location;
+        let mut state = self.entry_sets[block].clone();
+        let indices = match A::DIRECTION {
+            Direction::Forward => (0..statement_index).collect::<Vec<_>>(),
+            Direction::Backward => {
+                (statement_index..=body.blocks[block].statements.len()).rev().collect()
+            }
+        };
+        for index in indices {
+            let location = ProgramPoint { block, statement_index: index };
+            apply_effect(&mut self.analysis, &mut state, body, location);
+        }
+        state
+    }
+
+    /// The state after the statement or terminator at `location` was executed. For terminators,
+    /// this doesn't include the effect of a call returning.
+    pub fn state_after(&mut self, body: &Body, location: ProgramPoint) -> A::Domain {
+        let ProgramPoint { block, statement_index } = location;
+        let mut state = self.entry_sets[block].clone();
+        let indices = match A::DIRECTION {
+            Direction::Forward => (0..=statement_index).collect::<Vec<_>>(),
+            Direction::Backward => {
+                (statement_index + 1..=body.blocks[block].statements.len()).rev().collect()
+            }
+        };
+        for index in indices {
+            let location = ProgramPoint { block, statement_index: index };
+            apply_effect(&mut self.analysis, &mut state, body, location);
+        }
+        state
+    }
+
+    /// The state at the start of `block`.
+    pub fn state_at_block_start(&mut self, body: &Body, block: BasicBlockIdx) -> A::Domain {
+        match A::DIRECTION {
+            Direction::Forward => self.entry_sets[block].clone(),
+            Direction::Backward => {
+                self.state_before(body, ProgramPoint { block, statement_index: 0 })
+            }
+        }
+    }
+
+    /// The state at the end of `block`, after its terminator was executed.
+    pub fn state_at_block_end(&mut self, body: &Body, block: BasicBlockIdx) -> A::Domain {
+        match A::DIRECTION {
+            Direction::Forward => {
+                let statement_index = body.blocks[block].statements.len();
+                self.state_after(body, ProgramPoint { block, statement_index })
+            }
+            Direction::Backward => self.entry_sets[block].clone(),
+        }
+    }
+}
+
+/// A fixed-size set of indices, e.g. of the locals of a body.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    domain_size: usize,
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Creates an empty set that can hold the indices below `domain_size`.
+    pub fn new_empty(domain_size: usize) -> BitSet {
+        BitSet { domain_size, words: vec![0; domain_size.div_ceil(64)] }
+    }
+
+    pub fn domain_size(&self) -> usize {
+        self.domain_size
+    }
+
+    pub fn contains(&self, elem: usize) -> bool {
+        assert!(elem < self.domain_size);
+        self.words[elem / 64] & (1 << (elem % 64)) != 0
+    }
+
+    /// Inserts `elem`, and returns whether the set changed.
+    pub fn insert(&mut self, elem: usize) -> bool {
+        assert!(elem < self.domain_size);
+        let word = &mut self.words[elem / 64];
+        let old = *word;
+        *word |= 1 << (elem % 64);
+        *word != old
+    }
+
+    /// Removes `elem`, and returns whether the set changed.
+    pub fn remove(&mut self, elem: usize) -> bool {
+        assert!(elem < self.domain_size);
+        let word = &mut self.words[elem / 64];
+        let old = *word;
+        *word &= !(1 << (elem % 64));
+        *word != old
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Iterates over the elements in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.domain_size).filter(|elem| self.contains(*elem))
+    }
+
+    /// Adds all elements of `other`, and returns whether the set changed.
+    pub fn union(&mut self, other: &BitSet) -> bool {
+        assert_eq!(self.domain_size, other.domain_size);
+        let mut changed = false;
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            let old = *word;
+            *word |= *other;
+            changed |= *word != old;
+        }
+        changed
+    }
+}
+
+impl Debug for BitSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl JoinSemiLattice for BitSet {
+    fn join(&mut self, other: &Self) -> bool {
+        self.union(other)
+    }
+}
@@ -0,0 +1,265 @@
+//! Dataflow analyses of the locals of a body.
+
+use super::{Analysis, BitSet, Direction, ProgramPoint};
+use crate::mir::visit::{Location, PlaceContext};
+use crate::mir::{
+    BasicBlockIdx, Body, Local, MirVisitor, Operand, Place, ProjectionElem, Rvalue, Statement,
+    StatementKind, Terminator, TerminatorKind,
+};
+
+/// Finds the locals whose current value may be read later on.
+///
+/// A local is live before a statement if the statement reads it, or if it is live after the
+/// statement and not overwritten by it. Writing to a part of a local, e.g. to a field, doesn't
+/// end the liveness of the local, and neither does writing through a pointer stored in it.
+pub struct MaybeLiveLocals;
+
+impl Analysis for MaybeLiveLocals {
+    type Domain = BitSet;
+
+    const DIRECTION: Direction = Direction::Backward;
+
+    fn bottom_value(&self, body: &Body) -> BitSet {
+        BitSet::new_empty(body.locals().len())
+    }
+
+    fn apply_statement_effect(
+        &mut self,
+        state: &mut BitSet,
+        statement: &Statement,
+        _location: ProgramPoint,
+    ) {
+        let location = Location::new(statement.span);
+        match &statement.kind {
+            StatementKind::Assign(place, rvalue) => {
+                write_place(state, place);
+                InsertLocals(state).visit_rvalue(rvalue, location);
+            }
+            StatementKind::SetDiscriminant { place, .. } | StatementKind::Deinit(place) => {
+                write_place(state, place);
+            }
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                state.remove(*local);
+            }
+            StatementKind::FakeRead(..)
+            | StatementKind::Retag(..)
+            | StatementKind::Intrinsic(..) => {
+                InsertLocals(state).visit_statement(statement, location);
+            }
+            StatementKind::PlaceMention(_)
+            | StatementKind::AscribeUserType { .. }
+            | StatementKind::Coverage(_)
+            | StatementKind::ConstEvalCounter
+            | StatementKind::Nop => {}
+        }
+    }
+
+    fn apply_terminator_effect(
+        &mut self,
+        state: &mut BitSet,
+        terminator: &Terminator,
+        _location: ProgramPoint,
+    ) {
+        let location = Location::new(terminator.span);
+        match &terminator.kind {
+            // The destination is only written if the call returns.
+            TerminatorKind::Call { func, args, .. } => {
+                let mut uses = InsertLocals(state);
+                uses.visit_operand(func, location);
+                for arg in args {
+                    uses.visit_operand(arg, location);
+                }
+            }
+            TerminatorKind::InlineAsm { operands, .. } => {
+                for place in operands.iter().filter_map(|operand| operand.out_place.as_ref()) {
+                    write_place(state, place);
+                }
+                for value in operands.iter().filter_map(|operand| operand.in_value.as_ref()) {
+                    InsertLocals(state).visit_operand(value, location);
+                }
+            }
+            _ => InsertLocals(state).visit_terminator(terminator, location),
+        }
+    }
+
+    fn apply_call_return_effect(
+        &mut self,
+        state: &mut BitSet,
+        _block: BasicBlockIdx,
+        destination: &Place,
+    ) {
+        write_place(state, destination);
+    }
+}
+
+/// Applies the effect of writing to `place` on the live locals.
+fn write_place(state: &mut BitSet, place: &Place) {
+    if place.projection.is_empty() {
+        state.remove(place.local);
+    } else if is_indirect(place) {
+        state.insert(place.local);
+    }
+    for elem in &place.projection {
+        if let ProjectionElem::Index(local) = elem {
+            state.insert(*local);
+        }
+    }
+}
+
+/// Finds the locals that may be initialized, at least partially.
+///
+/// The arguments are initialized at the start of the function. A local is initialized by
+/// writing to it or to a part of it, and becomes uninitialized when it is moved out of as a
+/// whole, dropped, deinitialized or its storage ends.
+pub struct MaybeInitializedLocals;
+
+impl Analysis for MaybeInitializedLocals {
+    type Domain = BitSet;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom_value(&self, body: &Body) -> BitSet {
+        BitSet::new_empty(body.locals().len())
+    }
+
+    fn initialize_start_block(&self, body: &Body, state: &mut BitSet) {
+        for arg in 1..=body.arg_locals().len() {
+            state.insert(arg);
+        }
+    }
+
+    fn apply_statement_effect(
+        &mut self,
+        state: &mut BitSet,
+        statement: &Statement,
+        _location: ProgramPoint,
+    ) {
+        let location = Location::new(statement.span);
+        RemoveMoved(state).visit_statement(statement, location);
+        match &statement.kind {
+            StatementKind::Assign(place, _) | StatementKind::SetDiscriminant { place, .. } => {
+                if !is_indirect(place) {
+                    state.insert(place.local);
+                }
+            }
+            StatementKind::Deinit(place) if place.projection.is_empty() => {
+                state.remove(place.local);
+            }
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                state.remove(*local);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_terminator_effect(
+        &mut self,
+        state: &mut BitSet,
+        terminator: &Terminator,
+        _location: ProgramPoint,
+    ) {
+        RemoveMoved(state).visit_terminator(terminator, Location::new(terminator.span));
+        match &terminator.kind {
+            TerminatorKind::Drop { place, .. } if place.projection.is_empty() => {
+                state.remove(place.local);
+            }
+            TerminatorKind::InlineAsm { operands, .. } => {
+                for place in operands.iter().filter_map(|operand| operand.out_place.as_ref()) {
+                    if !is_indirect(place) {
+                        state.insert(place.local);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_call_return_effect(
+        &mut self,
+        state: &mut BitSet,
+        _block: BasicBlockIdx,
+        destination: &Place,
+    ) {
+        if !is_indirect(destination) {
+            state.insert(destination.local);
+        }
+    }
+}
+
+/// Finds the locals that may have been borrowed or had their address taken, and whose storage
+/// didn't end since.
+///
+/// Dropping a local counts as borrowing it, since its `Drop` implementation takes a mutable
+/// reference to it.
+pub struct MaybeBorrowedLocals;
+
+impl Analysis for MaybeBorrowedLocals {
+    type Domain = BitSet;
+
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn bottom_value(&self, body: &Body) -> BitSet {
+        BitSet::new_empty(body.locals().len())
+    }
+
+    fn apply_statement_effect(
+        &mut self,
+        state: &mut BitSet,
+        statement: &Statement,
+        _location: ProgramPoint,
+    ) {
+        match &statement.kind {
+            StatementKind::Assign(_, Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place)) => {
+                if !is_indirect(place) {
+                    state.insert(place.local);
+                }
+            }
+            StatementKind::StorageDead(local) => {
+                state.remove(*local);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_terminator_effect(
+        &mut self,
+        state: &mut BitSet,
+        terminator: &Terminator,
+        _location: ProgramPoint,
+    ) {
+        if let TerminatorKind::Drop { place, .. } = &terminator.kind {
+            if !is_indirect(place) {
+                state.insert(place.local);
+            }
+        }
+    }
+}
+
+/// Returns whether `place` is behind a pointer, so that accessing it doesn't access the memory
+/// of its local.
+fn is_indirect(place: &Place) -> bool {
+    place.projection.iter().any(|elem| matches!(elem, ProjectionElem::Deref))
+}
+
+/// Inserts all locals that are visited into a set.
+struct InsertLocals<'a>(&'a mut BitSet);
+
+impl MirVisitor for InsertLocals<'_> {
+    fn visit_local(&mut self, local: &Local, _ptx: PlaceContext, _location: Location) {
+        self.0.insert(*local);
+    }
+}
+
+/// Removes the locals that are moved out of as a whole from a set.
+struct RemoveMoved<'a>(&'a mut BitSet);
+
+impl MirVisitor for RemoveMoved<'_> {
+    fn visit_operand(&mut self, operand: &Operand, location: Location) {
+        if let Operand::Move(place) = operand {
+            if place.projection.is_empty() {
+                self.0.remove(place.local);
+            }
+        }
+        self.super_operand(operand, location)
+    }
+}
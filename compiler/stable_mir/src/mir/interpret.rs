@@ -0,0 +1,67 @@
+//! Interpretation of function instances with concrete arguments, see [Instance::interpret].
+//!
+//! The compiler's MIR interpreter executes the body of the instance, and of every function it
+//! calls. A [Machine] is notified of the memory accesses of the execution, and it provides the
+//! results of calls to foreign functions, which the interpreter can't execute. This allows
+//! tools to observe or to model the effects of an execution, e.g., to record the memory that a
+//! test input touches, without having to interpret MIR themselves.
+//!
+//! The functions of the global allocator, e.g. `__rust_alloc`, are foreign functions too, but
+//! they are emulated by the interpreter, so that boxes and collections can be used without the
+//! help of the [Machine]. A `#[global_allocator]` of the crate isn't called.
+//!
+//! Values are passed to and from the interpreter as the [Allocation] that holds their memory
+//! representation on the target.
+
+use crate::mir::alloc::AllocId;
+use crate::mir::mono::Instance;
+use crate::ty::Allocation;
+use crate::Error;
+
+/// The hooks of an execution of the interpreter.
+///
+/// Every method has a default implementation, which doesn't support foreign calls and allows
+/// all memory accesses. Returning an error from any hook stops the execution with that error.
+pub trait Machine {
+    /// Executes a call of a foreign function. The arguments hold the values that are passed to
+    /// the function, and the returned allocation holds the value of the call, which has to have
+    /// the size of the return type of the function.
+    ///
+    /// Diverging foreign functions, e.g., `abort`, are expected to return an error.
+    fn call_foreign(
+        &mut self,
+        instance: Instance,
+        _args: &[Allocation],
+    ) -> Result<Allocation, Error> {
+        Err(error!("Calls of foreign functions are not supported: `{}`", instance.name()))
+    }
+
+    /// Called before memory is read.
+    fn before_memory_read(&mut self, _access: MemoryAccess) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called before memory is written.
+    fn before_memory_write(&mut self, _access: MemoryAccess) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A machine without any hooks, see [Machine].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultMachine;
+
+impl Machine for DefaultMachine {}
+
+/// An access of `size` bytes at `offset` in an allocation.
+///
+/// Besides global allocations, e.g., of statics, the allocation can be memory that only exists
+/// during the execution, e.g., a local variable of a function. Those can't be retrieved with
+/// [GlobalAlloc](crate::mir::alloc::GlobalAlloc). Locals that the interpreter doesn't keep in
+/// memory, e.g., integers whose address is never taken, are not accessed through memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub alloc: AllocId,
+    pub offset: usize,
+    pub size: usize,
+}
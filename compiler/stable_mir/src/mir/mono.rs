@@ -1,18 +1,71 @@
 use crate::abi::FnAbi;
 use crate::crate_def::CrateDef;
+use crate::mir::interpret::Machine;
 use crate::mir::Body;
 use crate::ty::{Allocation, ClosureDef, ClosureKind, FnDef, GenericArgs, IndexedVal, Ty};
 use crate::{with, CrateItem, DefId, Error, ItemKind, Opaque, Symbol};
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MonoItem {
     Fn(Instance),
     Static(StaticDef),
     GlobalAsm(Opaque),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+/// A codegen unit, i.e., a set of mono items that are compiled to the same object file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodegenUnit {
+    pub name: Symbol,
+    /// The items of this codegen unit, in the order they are codegened in.
+    pub items: Vec<(MonoItem, MonoItemData)>,
+    /// The sum of the size estimates of all items.
+    pub size_estimate: usize,
+    /// Whether this codegen unit holds the items of a module of the local crate, as opposed to
+    /// being created for inlined copies of items only.
+    pub is_primary: bool,
+}
+
+/// How a mono item is defined in a codegen unit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonoItemData {
+    /// Whether the item is instantiated locally in every codegen unit that uses it, instead of
+    /// being shared between all of them.
+    pub inlined: bool,
+    pub linkage: Linkage,
+    pub visibility: Visibility,
+    /// An estimate of the size of the item, in number of MIR statements.
+    pub size_estimate: usize,
+}
+
+/// The linkage of a mono item.
+///
+/// See <https://llvm.org/docs/LangRef.html#linkage-types> for more details about these variants.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Linkage {
+    External,
+    AvailableExternally,
+    LinkOnceAny,
+    LinkOnceODR,
+    WeakAny,
+    WeakODR,
+    Appending,
+    Internal,
+    Private,
+    ExternalWeak,
+    Common,
+}
+
+/// The symbol visibility of a mono item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Visibility {
+    Default,
+    Hidden,
+    Protected,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Instance {
     /// The type of instance.
     pub kind: InstanceKind,
@@ -21,7 +74,7 @@ pub struct Instance {
     pub def: InstanceDef,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InstanceKind {
     /// A user defined item.
     Item,
@@ -146,6 +199,20 @@ pub fn is_empty_shim(&self) -> bool {
     pub fn try_const_eval(&self, const_ty: Ty) -> Result<Allocation, Error> {
         with(|cx| cx.eval_instance(self.def, const_ty))
     }
+
+    /// Execute this instance with the MIR interpreter of the compiler, see
+    /// [interpret](crate::mir::interpret).
+    ///
+    /// The arguments hold the values that are passed to the instance, and the returned
+    /// allocation holds its return value. The hooks of the `machine` are called during the
+    /// execution, except for the calls of the global allocator, which are emulated.
+    pub fn interpret(
+        &self,
+        args: &[Allocation],
+        machine: &mut dyn Machine,
+    ) -> Result<Allocation, Error> {
+        with(|cx| cx.interpret_instance(self.def, args, machine))
+    }
 }
 
 impl Debug for Instance {
@@ -209,7 +276,7 @@ fn from(value: StaticDef) -> Self {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct InstanceDef(usize);
 
 impl CrateDef for InstanceDef {
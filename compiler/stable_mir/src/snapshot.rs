@@ -0,0 +1,413 @@
+//! Snapshots of the stable MIR of a crate, that can be written to a file and analyzed later on
+//! without running the compiler.
+//!
+//! Stable MIR refers to types, spans, allocations and instances by handles, e.g. [Ty], that can
+//! only be resolved while the compiler is running. A [Snapshot] resolves all the handles used by
+//! the items of the local crate, and stores them in tables that are sorted by handle. For
+//! example, the kind of a type is looked up with [Snapshot::ty] instead of [Ty::kind], and the
+//! name of the function of a [RigidTy::FnDef] with [Snapshot::def] instead of [CrateDef::name].
+
+use crate::abi::{FnAbi, LayoutShape};
+use crate::mir::alloc::{AllocId, GlobalAlloc};
+use crate::mir::mono::Instance;
+use crate::mir::pretty::pretty_ty;
+use crate::mir::visit::Location;
+use crate::mir::{Body, MirVisitor};
+use crate::target::MachineInfo;
+use crate::ty::{
+    AdtKind, Allocation, Const, ConstantKind, ExistentialPredicate, GenericArgs, IndexedVal,
+    LineInfo, RigidTy, Span, Ty, TyKind,
+};
+use crate::visitor::{Visitable, Visitor};
+use crate::{Crate, CrateDef, CrateItem, DefId, Error, Filename, ItemKind, Symbol};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::ops::ControlFlow;
+
+/// The version of the snapshot format. It has to be increased whenever the serialization of one
+/// of the stable MIR types changes, which makes the `serialization` test fail.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// The stable MIR of the local crate, see the [module documentation](self).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The [SNAPSHOT_VERSION] of the compiler that took the snapshot.
+    pub version: u32,
+    pub krate: Crate,
+    pub machine: MachineInfo,
+    pub entry_fn: Option<CrateItem>,
+    /// The local items that have a body.
+    pub items: Vec<ItemSnapshot>,
+    pub types: Vec<TySnapshot>,
+    pub defs: Vec<DefSnapshot>,
+    pub allocs: Vec<AllocSnapshot>,
+    pub instances: Vec<InstanceSnapshot>,
+    pub spans: Vec<SpanSnapshot>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ItemSnapshot {
+    pub item: CrateItem,
+    pub name: Symbol,
+    pub kind: ItemKind,
+    pub span: Span,
+    pub body: Body,
+    /// The ABI of the item, if it is a function that doesn't require monomorphization.
+    pub fn_abi: Option<FnAbi>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TySnapshot {
+    pub ty: Ty,
+    /// The type as it is written in the textual stable MIR output.
+    pub name: String,
+    pub kind: TyKind,
+    /// The layout of the type, if it doesn't depend on generic parameters.
+    pub layout: Option<LayoutShape>,
+}
+
+/// A definition that is referred to by a type, a constant or an allocation, e.g. the function of
+/// a [RigidTy::FnDef].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DefSnapshot {
+    pub def: DefId,
+    /// The fully qualified name of the definition.
+    pub name: Symbol,
+    pub krate: Crate,
+    pub kind: DefKind,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum DefKind {
+    Fn,
+    Closure,
+    Coroutine,
+    CoroutineWitness,
+    Foreign,
+    Alias,
+    Trait,
+    Const,
+    /// A struct, enum or union, with the fields of each of its variants.
+    Adt {
+        kind: AdtKind,
+        variants: Vec<VariantSnapshot>,
+    },
+    /// A static, with its initial value if it could be evaluated.
+    Static {
+        initializer: Option<Allocation>,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VariantSnapshot {
+    pub name: Symbol,
+    pub fields: Vec<FieldSnapshot>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FieldSnapshot {
+    pub name: Symbol,
+    /// The type of the field, which may refer to the generic parameters of the ADT.
+    pub ty: Ty,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AllocSnapshot {
+    pub id: AllocId,
+    pub alloc: GlobalAlloc,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    pub instance: Instance,
+    pub name: Symbol,
+    pub mangled_name: Symbol,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpanSnapshot {
+    pub span: Span,
+    pub file: Filename,
+    pub lines: LineInfo,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of the local crate. This can only be called while the compiler is running,
+    /// snapshots can also be written by the compiler with `-Zunpretty=smir-snapshot`.
+    pub fn take() -> Snapshot {
+        let mut collector = Collector::default();
+        let mut items = vec![];
+        for item in crate::all_local_items() {
+            let body = item.body();
+            collector.visit_body(&body);
+            let span = item.span();
+            collector.spans.insert(span);
+            let fn_abi = match item.kind() {
+                ItemKind::Fn => {
+                    Instance::try_from(item).and_then(|instance| instance.fn_abi()).ok()
+                }
+                _ => None,
+            };
+            for arg in fn_abi.iter().flat_map(|abi| abi.args.iter().chain([&abi.ret])) {
+                let _ = arg.ty.visit(&mut collector);
+            }
+            items.push(ItemSnapshot {
+                item,
+                name: item.name(),
+                kind: item.kind(),
+                span,
+                body,
+                fn_abi,
+            });
+        }
+
+        let mut types: Vec<_> = collector
+            .types
+            .into_iter()
+            .map(|ty| TySnapshot {
+                ty,
+                name: pretty_ty(ty.kind()),
+                kind: ty.kind(),
+                layout: ty.layout().ok().map(|layout| layout.shape()),
+            })
+            .collect();
+        types.sort_by_key(|ty| ty.ty.to_index());
+        let mut defs = collector.defs;
+        defs.sort_by_key(|def| def.def.to_index());
+        let mut allocs = collector.allocs;
+        allocs.sort_by_key(|alloc| alloc.id.to_index());
+        let mut instances: Vec<_> = collector
+            .instances
+            .into_iter()
+            .map(|instance| InstanceSnapshot {
+                instance,
+                name: instance.name(),
+                mangled_name: instance.mangled_name(),
+            })
+            .collect();
+        instances.sort_by_key(|instance| instance.instance.def.to_index());
+        let mut spans: Vec<_> = collector
+            .spans
+            .into_iter()
+            .map(|span| SpanSnapshot { span, file: span.get_filename(), lines: span.get_lines() })
+            .collect();
+        spans.sort_by_key(|span| span.span.to_index());
+
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            krate: crate::local_crate(),
+            machine: MachineInfo::target(),
+            entry_fn: crate::entry_fn(),
+            items,
+            types,
+            defs,
+            allocs,
+            instances,
+            spans,
+        }
+    }
+
+    /// Reads a snapshot that was written by [Snapshot::write]. Fails if the snapshot was written
+    /// with another [SNAPSHOT_VERSION].
+    pub fn read<R: io::Read>(mut reader: R) -> Result<Snapshot, Error> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let mut json = String::new();
+        reader.read_to_string(&mut json)?;
+        let Version { version } = serde_json::from_str(&json).map_err(json_error)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(error!(
+                "Expected a snapshot of version {SNAPSHOT_VERSION}, but found version {version}"
+            ));
+        }
+        serde_json::from_str(&json).map_err(json_error)
+    }
+
+    /// Writes the snapshot as JSON.
+    pub fn write<W: io::Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, self).map_err(json_error)
+    }
+
+    pub fn ty(&self, ty: Ty) -> Option<&TySnapshot> {
+        let index = self.types.binary_search_by_key(&ty.to_index(), |ty| ty.ty.to_index()).ok()?;
+        Some(&self.types[index])
+    }
+
+    pub fn def(&self, def: DefId) -> Option<&DefSnapshot> {
+        let index =
+            self.defs.binary_search_by_key(&def.to_index(), |other| other.def.to_index()).ok()?;
+        Some(&self.defs[index])
+    }
+
+    pub fn alloc(&self, id: AllocId) -> Option<&GlobalAlloc> {
+        let index =
+            self.allocs.binary_search_by_key(&id.to_index(), |alloc| alloc.id.to_index()).ok()?;
+        Some(&self.allocs[index].alloc)
+    }
+
+    pub fn instance(&self, instance: Instance) -> Option<&InstanceSnapshot> {
+        let index = self
+            .instances
+            .binary_search_by_key(&instance.def.to_index(), |other| other.instance.def.to_index())
+            .ok()?;
+        Some(&self.instances[index])
+    }
+
+    pub fn span(&self, span: Span) -> Option<&SpanSnapshot> {
+        let index = self
+            .spans
+            .binary_search_by_key(&span.to_index(), |other| other.span.to_index())
+            .ok()?;
+        Some(&self.spans[index])
+    }
+}
+
+fn json_error(error: serde_json::Error) -> Error {
+    error!("Invalid snapshot: {error}")
+}
+
+/// Collects the handles used by MIR bodies, including the ones nested in types, definitions and
+/// allocations.
+#[derive(Default)]
+struct Collector {
+    types: HashSet<Ty>,
+    spans: HashSet<Span>,
+    def_ids: HashSet<DefId>,
+    defs: Vec<DefSnapshot>,
+    alloc_ids: HashSet<AllocId>,
+    allocs: Vec<AllocSnapshot>,
+    instances: HashSet<Instance>,
+}
+
+impl Collector {
+    fn visit_allocation(&mut self, allocation: &Allocation) {
+        for (_, prov) in &allocation.provenance.ptrs {
+            let id = prov.0;
+            if !self.alloc_ids.insert(id) {
+                continue;
+            }
+            let alloc = GlobalAlloc::from(id);
+            match &alloc {
+                GlobalAlloc::Function(instance) => {
+                    self.instances.insert(*instance);
+                }
+                GlobalAlloc::VTable(ty, _) => {
+                    let _ = ty.visit(self);
+                }
+                GlobalAlloc::Static(def) => self.visit_def(*def, |this| {
+                    let initializer = def.eval_initializer().ok();
+                    if let Some(allocation) = &initializer {
+                        this.visit_allocation(allocation);
+                    }
+                    DefKind::Static { initializer }
+                }),
+                GlobalAlloc::Memory(allocation) => self.visit_allocation(allocation),
+            }
+            self.allocs.push(AllocSnapshot { id, alloc });
+        }
+    }
+
+    /// Adds `def` to the definitions, with the kind computed by `kind` the first time it is seen.
+    fn visit_def(&mut self, def: impl CrateDef, kind: impl FnOnce(&mut Self) -> DefKind) {
+        if !self.def_ids.insert(def.def_id()) {
+            return;
+        }
+        let kind = kind(self);
+        self.defs.push(DefSnapshot {
+            def: def.def_id(),
+            name: def.name(),
+            krate: def.krate(),
+            kind,
+        });
+    }
+
+    fn visit_ty_defs(&mut self, kind: &TyKind) {
+        match kind {
+            TyKind::RigidTy(RigidTy::Adt(def, _)) => self.visit_def(*def, |this| {
+                let variants = def
+                    .variants_iter()
+                    .map(|variant| VariantSnapshot {
+                        name: variant.name(),
+                        fields: variant
+                            .fields()
+                            .into_iter()
+                            .map(|field| {
+                                let ty = field.ty();
+                                let _ = ty.visit(this);
+                                FieldSnapshot { name: field.name, ty }
+                            })
+                            .collect(),
+                    })
+                    .collect();
+                DefKind::Adt { kind: def.kind(), variants }
+            }),
+            TyKind::RigidTy(RigidTy::Foreign(def)) => self.visit_def(*def, |_| DefKind::Foreign),
+            TyKind::RigidTy(RigidTy::FnDef(def, _)) => self.visit_def(*def, |_| DefKind::Fn),
+            TyKind::RigidTy(RigidTy::Closure(def, _)) => self.visit_def(*def, |_| DefKind::Closure),
+            TyKind::RigidTy(RigidTy::Coroutine(def, _, _)) => {
+                self.visit_def(*def, |_| DefKind::Coroutine)
+            }
+            TyKind::RigidTy(RigidTy::CoroutineWitness(def, _)) => {
+                self.visit_def(*def, |_| DefKind::CoroutineWitness)
+            }
+            TyKind::RigidTy(RigidTy::Dynamic(predicates, _, _)) => {
+                for predicate in predicates {
+                    let def = match &predicate.value {
+                        ExistentialPredicate::Trait(trait_ref) => trait_ref.def_id,
+                        ExistentialPredicate::Projection(projection) => projection.def_id,
+                        ExistentialPredicate::AutoTrait(def) => *def,
+                    };
+                    self.visit_def(def, |_| DefKind::Trait);
+                }
+            }
+            TyKind::Alias(_, alias) => self.visit_def(alias.def_id, |_| DefKind::Alias),
+            TyKind::RigidTy(_) | TyKind::Param(_) | TyKind::Bound(..) => {}
+        }
+    }
+}
+
+impl MirVisitor for Collector {
+    fn visit_ty(&mut self, ty: &Ty, _location: Location) {
+        let _ = ty.visit(self);
+    }
+
+    fn visit_const(&mut self, constant: &Const, _location: Location) {
+        let _ = constant.visit(self);
+    }
+
+    fn visit_args(&mut self, args: &GenericArgs, _location: Location) {
+        let _ = args.visit(self);
+    }
+
+    fn visit_span(&mut self, span: &Span) {
+        self.spans.insert(*span);
+    }
+}
+
+impl Visitor for Collector {
+    type Break = ();
+
+    fn visit_ty(&mut self, ty: &Ty) -> ControlFlow<()> {
+        if !self.types.insert(*ty) {
+            return ControlFlow::Continue(());
+        }
+        self.visit_ty_defs(&ty.kind());
+        ty.super_visit(self)
+    }
+
+    fn visit_const(&mut self, constant: &Const) -> ControlFlow<()> {
+        match constant.kind() {
+            ConstantKind::Allocated(allocation) => self.visit_allocation(allocation),
+            ConstantKind::Unevaluated(uv) => self.visit_def(uv.def, |_| DefKind::Const),
+            ConstantKind::Param(_) | ConstantKind::ZeroSized => {}
+        }
+        constant.super_visit(self)
+    }
+}
+
+#[cfg(test)]
+mod tests;
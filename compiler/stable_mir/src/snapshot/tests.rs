@@ -0,0 +1,394 @@
+use super::*;
+use crate::abi::{
+    ArgAbi, CallConvention, FieldsShape, IntegerLength, Layout, PassMode, Primitive, Scalar,
+    ValueAbi, VariantsShape, WrappingRange,
+};
+use crate::mir::mono::{InstanceDef, InstanceKind, StaticDef};
+use crate::mir::{
+    BasicBlock, Constant, LocalDecl, Mutability, Operand, Place, ProjectionElem, Rvalue, Statement,
+    StatementKind, Terminator, TerminatorKind, UnwindAction,
+};
+use crate::opaque;
+use crate::target::{Endian, MachineSize};
+use crate::ty::{
+    AdtDef, ConstId, FnDef, GenericArgs, Prov, ProvenanceMap, Region, RegionKind, RigidTy, UintTy,
+    VariantIdx,
+};
+use serde_json::json;
+
+/// A snapshot of a crate with a function that calls a function to get a struct, and then returns
+/// the value of a static. It has an entry in every table.
+fn snapshot() -> Snapshot {
+    let (u64_ty, ref_ty, fn_ty, adt_ty) =
+        (Ty::to_val(0), Ty::to_val(1), Ty::to_val(2), Ty::to_val(3));
+    let (item, fn_def, adt_def, static_def) = (DefId(4), DefId(5), DefId(6), DefId(7));
+    let span = Span::to_val(8);
+    let static_alloc = AllocId::to_val(9);
+    let instance = Instance { kind: InstanceKind::Item, def: InstanceDef::to_val(10) };
+
+    let constant = |kind, ty, id| {
+        Operand::Constant(Constant { span, user_ty: None, literal: Const::new(kind, ty, id) })
+    };
+    let answer_ref = ConstantKind::Allocated(Allocation {
+        bytes: vec![None; 8],
+        provenance: ProvenanceMap { ptrs: vec![(0, Prov(static_alloc))] },
+        align: 8,
+        mutability: Mutability::Not,
+    });
+    let body = Body::new(
+        vec![
+            BasicBlock {
+                statements: vec![],
+                terminator: Terminator {
+                    kind: TerminatorKind::Call {
+                        func: constant(ConstantKind::ZeroSized, fn_ty, ConstId::to_val(11)),
+                        args: vec![],
+                        destination: Place { local: 1, projection: vec![] },
+                        target: Some(1),
+                        unwind: UnwindAction::Continue,
+                    },
+                    span,
+                },
+            },
+            BasicBlock {
+                statements: vec![
+                    Statement {
+                        kind: StatementKind::Assign(
+                            Place { local: 2, projection: vec![] },
+                            Rvalue::Use(constant(answer_ref, ref_ty, ConstId::to_val(12))),
+                        ),
+                        span,
+                    },
+                    Statement {
+                        kind: StatementKind::Assign(
+                            Place { local: 0, projection: vec![] },
+                            Rvalue::Use(Operand::Copy(Place {
+                                local: 2,
+                                projection: vec![ProjectionElem::Deref],
+                            })),
+                        ),
+                        span,
+                    },
+                ],
+                terminator: Terminator { kind: TerminatorKind::Return, span },
+            },
+        ],
+        vec![
+            LocalDecl { ty: u64_ty, span, mutability: Mutability::Mut },
+            LocalDecl { ty: adt_ty, span, mutability: Mutability::Not },
+            LocalDecl { ty: ref_ty, span, mutability: Mutability::Not },
+        ],
+        0,
+        vec![],
+        None,
+        span,
+    );
+    let fn_abi = FnAbi {
+        args: vec![],
+        ret: ArgAbi { ty: u64_ty, layout: Layout::to_val(13), mode: PassMode::Direct(opaque(&1)) },
+        fixed_count: 0,
+        conv: CallConvention::Rust,
+        c_variadic: false,
+    };
+    let u64_layout = LayoutShape {
+        fields: FieldsShape::Primitive,
+        variants: VariantsShape::Single { index: VariantIdx::to_val(0) },
+        abi: ValueAbi::Scalar(Scalar::Initialized {
+            value: Primitive::Int { length: IntegerLength::I64, signed: false },
+            valid_range: WrappingRange { start: 0, end: u64::MAX.into() },
+        }),
+        abi_align: 8,
+        size: MachineSize::from_bits(64),
+    };
+    let krate = Crate { id: 0, name: "answer".to_string(), is_local: true };
+    let ty = |ty, name: &str, kind: RigidTy, layout| TySnapshot {
+        ty,
+        name: name.to_string(),
+        kind: TyKind::RigidTy(kind),
+        layout,
+    };
+    let def = |def, name: &str, kind| DefSnapshot {
+        def,
+        name: name.to_string(),
+        krate: krate.clone(),
+        kind,
+    };
+
+    Snapshot {
+        version: SNAPSHOT_VERSION,
+        krate: krate.clone(),
+        machine: MachineInfo { endian: Endian::Little, pointer_width: MachineSize::from_bits(64) },
+        entry_fn: None,
+        items: vec![ItemSnapshot {
+            item: CrateItem(item),
+            name: "answer".to_string(),
+            kind: ItemKind::Fn,
+            span,
+            body,
+            fn_abi: Some(fn_abi),
+        }],
+        types: vec![
+            ty(u64_ty, "u64", RigidTy::Uint(UintTy::U64), Some(u64_layout)),
+            ty(
+                ref_ty,
+                "&u64",
+                RigidTy::Ref(Region { kind: RegionKind::ReStatic }, u64_ty, Mutability::Not),
+                None,
+            ),
+            ty(fn_ty, "point", RigidTy::FnDef(FnDef(fn_def), GenericArgs(vec![])), None),
+            ty(adt_ty, "Point", RigidTy::Adt(AdtDef(adt_def), GenericArgs(vec![])), None),
+        ],
+        defs: vec![
+            def(fn_def, "point", DefKind::Fn),
+            def(
+                adt_def,
+                "Point",
+                DefKind::Adt {
+                    kind: AdtKind::Struct,
+                    variants: vec![VariantSnapshot {
+                        name: "Point".to_string(),
+                        fields: vec![FieldSnapshot { name: "x".to_string(), ty: u64_ty }],
+                    }],
+                },
+            ),
+            def(
+                static_def,
+                "ANSWER",
+                DefKind::Static {
+                    initializer: Some(Allocation {
+                        bytes: 42u64.to_le_bytes().map(Some).to_vec(),
+                        provenance: ProvenanceMap { ptrs: vec![] },
+                        align: 8,
+                        mutability: Mutability::Not,
+                    }),
+                },
+            ),
+        ],
+        allocs: vec![AllocSnapshot {
+            id: static_alloc,
+            alloc: GlobalAlloc::Static(StaticDef(static_def)),
+        }],
+        instances: vec![InstanceSnapshot {
+            instance,
+            name: "point".to_string(),
+            mangled_name: "_ZN6answer5point17h0123456789abcdefE".to_string(),
+        }],
+        spans: vec![SpanSnapshot {
+            span,
+            file: "answer.rs".to_string(),
+            lines: LineInfo { start_line: 1, start_col: 1, end_line: 3, end_col: 2 },
+        }],
+    }
+}
+
+/// Checks the serialization of all the tables. If this fails because the serialization of one
+/// of the stable MIR types changed, [SNAPSHOT_VERSION] has to be increased.
+#[test]
+fn serialization() {
+    let mut json = vec![];
+    snapshot().write(&mut json).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    let expected = json!({
+        "version": SNAPSHOT_VERSION,
+        "krate": {"id": 0, "name": "answer", "is_local": true},
+        "machine": {"endian": "Little", "pointer_width": {"num_bits": 64}},
+        "entry_fn": null,
+        "items": [
+            {
+                "item": 4,
+                "name": "answer",
+                "kind": "Fn",
+                "span": 8,
+                "body": {
+                    "blocks": [
+                        {
+                            "statements": [],
+                            "terminator": {
+                                "kind": {
+                                    "Call": {
+                                        "func": {
+                                            "Constant": {
+                                                "span": 8,
+                                                "user_ty": null,
+                                                "literal": {"kind": "ZeroSized", "ty": 2, "id": 11}
+                                            }
+                                        },
+                                        "args": [],
+                                        "destination": {"local": 1, "projection": []},
+                                        "target": 1,
+                                        "unwind": "Continue"
+                                    }
+                                },
+                                "span": 8
+                            }
+                        },
+                        {
+                            "statements": [
+                                {
+                                    "kind": {
+                                        "Assign": [
+                                            {"local": 2, "projection": []},
+                                            {
+                                                "Use": {
+                                                    "Constant": {
+                                                        "span": 8,
+                                                        "user_ty": null,
+                                                        "literal": {
+                                                            "kind": {
+                                                                "Allocated": {
+                                                                    "bytes": [
+                                                                        null,
+                                                                        null,
+                                                                        null,
+                                                                        null,
+                                                                        null,
+                                                                        null,
+                                                                        null,
+                                                                        null
+                                                                    ],
+                                                                    "provenance": {
+                                                                        "ptrs": [[0, 9]]
+                                                                    },
+                                                                    "align": 8,
+                                                                    "mutability": "Not"
+                                                                }
+                                                            },
+                                                            "ty": 1,
+                                                            "id": 12
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        ]
+                                    },
+                                    "span": 8
+                                },
+                                {
+                                    "kind": {
+                                        "Assign": [
+                                            {"local": 0, "projection": []},
+                                            {
+                                                "Use": {
+                                                    "Copy": {"local": 2, "projection": ["Deref"]}
+                                                }
+                                            }
+                                        ]
+                                    },
+                                    "span": 8
+                                }
+                            ],
+                            "terminator": {"kind": "Return", "span": 8}
+                        }
+                    ],
+                    "locals": [
+                        {"ty": 0, "span": 8, "mutability": "Mut"},
+                        {"ty": 3, "span": 8, "mutability": "Not"},
+                        {"ty": 1, "span": 8, "mutability": "Not"}
+                    ],
+                    "arg_count": 0,
+                    "var_debug_info": [],
+                    "spread_arg": null,
+                    "span": 8
+                },
+                "fn_abi": {
+                    "args": [],
+                    "ret": {"ty": 0, "layout": 13, "mode": {"Direct": "1"}},
+                    "fixed_count": 0,
+                    "conv": "Rust",
+                    "c_variadic": false
+                }
+            }
+        ],
+        "types": [
+            {
+                "ty": 0,
+                "name": "u64",
+                "kind": {"RigidTy": {"Uint": "U64"}},
+                "layout": {
+                    "fields": "Primitive",
+                    "variants": {"Single": {"index": 0}},
+                    "abi": {
+                        "Scalar": {
+                            "Initialized": {
+                                "value": {"Int": {"length": "I64", "signed": false}},
+                                "valid_range": {"start": 0, "end": u64::MAX}
+                            }
+                        }
+                    },
+                    "abi_align": 8,
+                    "size": {"num_bits": 64}
+                }
+            },
+            {
+                "ty": 1,
+                "name": "&u64",
+                "kind": {"RigidTy": {"Ref": [{"kind": "ReStatic"}, 0, "Not"]}},
+                "layout": null
+            },
+            {"ty": 2, "name": "point", "kind": {"RigidTy": {"FnDef": [5, []]}}, "layout": null},
+            {"ty": 3, "name": "Point", "kind": {"RigidTy": {"Adt": [6, []]}}, "layout": null}
+        ],
+        "defs": [
+            {
+                "def": 5,
+                "name": "point",
+                "krate": {"id": 0, "name": "answer", "is_local": true},
+                "kind": "Fn"
+            },
+            {
+                "def": 6,
+                "name": "Point",
+                "krate": {"id": 0, "name": "answer", "is_local": true},
+                "kind": {
+                    "Adt": {
+                        "kind": "Struct",
+                        "variants": [{"name": "Point", "fields": [{"name": "x", "ty": 0}]}]
+                    }
+                }
+            },
+            {
+                "def": 7,
+                "name": "ANSWER",
+                "krate": {"id": 0, "name": "answer", "is_local": true},
+                "kind": {
+                    "Static": {
+                        "initializer": {
+                            "bytes": [42, 0, 0, 0, 0, 0, 0, 0],
+                            "provenance": {"ptrs": []},
+                            "align": 8,
+                            "mutability": "Not"
+                        }
+                    }
+                }
+            }
+        ],
+        "allocs": [{"id": 9, "alloc": {"Static": 7}}],
+        "instances": [
+            {
+                "instance": {"kind": "Item", "def": 10},
+                "name": "point",
+                "mangled_name": "_ZN6answer5point17h0123456789abcdefE"
+            }
+        ],
+        "spans": [
+            {
+                "span": 8,
+                "file": "answer.rs",
+                "lines": {"start_line": 1, "start_col": 1, "end_line": 3, "end_col": 2}
+            }
+        ]
+    });
+    assert_eq!(value, expected);
+
+    // The snapshot doesn't change when it is read back and written again.
+    let snapshot = Snapshot::read(json.as_slice()).unwrap();
+    let mut rewritten = vec![];
+    snapshot.write(&mut rewritten).unwrap();
+    assert_eq!(String::from_utf8(json).unwrap(), String::from_utf8(rewritten).unwrap());
+
+    // The handles are looked up in the tables.
+    assert_eq!(snapshot.def(DefId(7)).unwrap().name, "ANSWER");
+    assert!(snapshot.def(DefId(4)).is_none());
+    assert_eq!(snapshot.ty(Ty::to_val(3)).unwrap().name, "Point");
+    assert!(matches!(snapshot.alloc(AllocId::to_val(9)), Some(GlobalAlloc::Static(_))));
+}
@@ -2,8 +2,9 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
+use super::options::{ColorConfig, Options, OutputFormat, RunIgnored, TestShard};
 use super::time::TestTimeOptions;
 use std::io::{self, IsTerminal};
 
@@ -23,9 +24,15 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub shuffle: bool,
     pub shuffle_seed: Option<u64>,
+    /// Run only the tests of this shard.
+    pub shard: Option<TestShard>,
+    /// Number of times a failing test is run again before it's reported as failed.
+    pub retries: usize,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
     pub time_options: Option<TestTimeOptions>,
+    /// Time limit after which a test is killed, unless the test sets its own.
+    pub test_timeout: Option<Duration>,
     /// Stop at first failing test.
     /// May run a few more tests due to threading, but will
     /// abort as soon as possible.
@@ -102,8 +109,9 @@ fn optgroups() -> getopts::Options {
             pretty = Print verbose output;
             terse  = Display one character per test;
             json   = Output a json document;
-            junit  = Output a JUnit document",
-            "pretty|terse|json|junit",
+            junit  = Output a JUnit document;
+            tap    = Output a TAP (Test Anything Protocol) stream",
+            "pretty|terse|json|junit|tap",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -148,6 +156,20 @@ fn optgroups() -> getopts::Options {
             "shuffle-seed",
             "Run tests in random order; seed the random number generator with SEED",
             "SEED",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Run only the tests of shard INDEX, which must be less than --shard-count",
+            "INDEX",
+        )
+        .optopt("", "shard-count", "Split the tests into COUNT shards", "COUNT")
+        .optopt("", "retries", "Run failing tests again up to N times", "N")
+        .optopt(
+            "",
+            "test-timeout",
+            "Kill tests that run for longer than SECONDS and report them as failed",
+            "SECONDS",
         );
     opts
 }
@@ -171,6 +193,22 @@ fn usage(binary: &str, options: &getopts::Options) {
 tests in the same order again. Note that --shuffle and --shuffle-seed do not
 affect whether the tests are run in parallel.
 
+To split the tests across several processes or machines, pass --shard-count
+with the number of shards to each of them, and --shard-index with the shard to
+run, from 0 to the number of shards minus one. The shard of a test is derived
+from its name, so it only changes when the test is renamed.
+
+Failing tests can be run again with --retries, in a new thread or process like
+the first attempt. Tests that pass on a later attempt are reported as flaky, and
+the output of all their attempts is kept. Tests with a dynamic test function,
+e.g., doctests, are not retried.
+
+With --test-timeout, tests that run for longer than the given number of seconds
+are killed and reported as failed, and the remaining tests keep running. Tests
+can set their own limit with the #[test_timeout = "SECONDS"] attribute. To be
+able to kill them, tests with a time limit are run in a separate process.
+Tests with a dynamic test function, e.g., doctests, have no time limit.
+
 All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or setting RUST_TEST_NOCAPTURE
 environment variable to a value other than "0". Logging is not captured by default.
@@ -259,8 +297,11 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
     let shuffle = get_shuffle(&matches, allow_unstable)?;
     let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let shard = get_shard(&matches, allow_unstable)?;
+    let retries = get_retries(&matches, allow_unstable)?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -296,9 +337,12 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         format,
         shuffle,
         shuffle_seed,
+        shard,
+        retries,
         test_threads,
         skip,
         time_options,
+        test_timeout,
         options,
         fail_fast: false,
     };
@@ -336,6 +380,27 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(0) => return Err("argument for --test-timeout must not be 0".to_string()),
+            Ok(n) => Some(Duration::from_secs(n)),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --test-timeout must be a number > 0 \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
 fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
     let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
     if !shuffle && allow_unstable {
@@ -375,6 +440,62 @@ fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPart
     Ok(shuffle_seed)
 }
 
+fn get_shard(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<TestShard>> {
+    let index = unstable_optopt!(matches, allow_unstable, "shard-index");
+    let count = unstable_optopt!(matches, allow_unstable, "shard-count");
+    let (index, count) = match (index, count) {
+        (Some(index), Some(count)) => (index, count),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err("the options --shard-index and --shard-count must be used together".into());
+        }
+    };
+
+    let count = match count.parse::<usize>() {
+        Ok(0) => return Err("argument for --shard-count must not be 0".to_string()),
+        Ok(n) => n,
+        Err(e) => {
+            return Err(format!(
+                "argument for --shard-count must be a number > 0 \
+                 (error: {e})"
+            ));
+        }
+    };
+    let index = match index.parse::<usize>() {
+        Ok(n) if n < count => n,
+        Ok(n) => {
+            return Err(format!(
+                "argument for --shard-index must be less than --shard-count (was {n})"
+            ));
+        }
+        Err(e) => {
+            return Err(format!(
+                "argument for --shard-index must be a number \
+                 (error: {e})"
+            ));
+        }
+    };
+
+    Ok(Some(TestShard { index, count }))
+}
+
+fn get_retries(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<usize> {
+    let retries = match unstable_optopt!(matches, allow_unstable, "retries") {
+        Some(n_str) => match n_str.parse::<usize>() {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(format!(
+                    "argument for --retries must be a number \
+                     (error: {e})"
+                ));
+            }
+        },
+        None => 0,
+    };
+
+    Ok(retries)
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -414,10 +535,16 @@ fn get_format(
             }
             OutputFormat::Junit
         }
+        Some("tap") => {
+            if !allow_unstable {
+                return Err("The \"tap\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Tap
+        }
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, json or junit (was \
-                 {v})"
+                "argument for --format must be pretty, terse, json, junit or tap \
+                 (was {v})"
             ));
         }
     };
@@ -10,7 +10,10 @@
     cli::TestOpts,
     event::{CompletedTest, TestEvent},
     filter_tests,
-    formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter},
+    formatters::{
+        JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TapFormatter,
+        TerseFormatter,
+    },
     helpers::{concurrency::get_concurrency, metrics::MetricMap},
     options::{Options, OutputFormat},
     run_tests, term,
@@ -95,6 +98,7 @@ pub struct ConsoleTestState {
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub ignores: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    pub flaky: Vec<(TestDesc, Vec<u8>)>,
     pub options: Options,
 }
 
@@ -119,6 +123,7 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             not_failures: Vec::new(),
             ignores: Vec::new(),
             time_failures: Vec::new(),
+            flaky: Vec::new(),
             options: opts.options,
         })
     }
@@ -161,6 +166,7 @@ pub fn write_log_result(
                     }
                     TestResult::TrBench(ref bs) => fmt_bench_samples(bs),
                     TestResult::TrTimedFail => "failed (time limit exceeded)".to_owned(),
+                    TestResult::TrFlaky(attempts) => format!("ok (flaky, {attempts} attempts)"),
                 },
                 name,
             )
@@ -184,7 +190,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
     };
 
     let mut out: Box<dyn OutputFormatter> = match opts.format {
-        OutputFormat::Pretty | OutputFormat::Junit => {
+        OutputFormat::Pretty | OutputFormat::Junit | OutputFormat::Tap => {
             Box::new(PrettyFormatter::new(output, false, 0, false, None))
         }
         OutputFormat::Terse => Box::new(TerseFormatter::new(output, false, 0, false)),
@@ -253,6 +259,10 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
             st.failed += 1;
             st.time_failures.push((test, stdout));
         }
+        TestResult::TrFlaky(_) => {
+            st.passed += 1;
+            st.flaky.push((test, stdout));
+        }
     }
 }
 
@@ -264,9 +274,9 @@ fn on_test_event(
     out: &mut dyn OutputFormatter,
 ) -> io::Result<()> {
     match (*event).clone() {
-        TestEvent::TeFiltered(filtered_tests, shuffle_seed) => {
+        TestEvent::TeFiltered(filtered_tests, shuffle_seed, shard) => {
             st.total = filtered_tests;
-            out.write_run_start(filtered_tests, shuffle_seed)?;
+            out.write_run_start(filtered_tests, shuffle_seed, shard)?;
         }
         TestEvent::TeFilteredOut(filtered_out) => {
             st.filtered_out = filtered_out;
@@ -317,6 +327,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
         OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
 
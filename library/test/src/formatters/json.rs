@@ -3,9 +3,10 @@
 use super::OutputFormatter;
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    options::{ShouldPanic, TestShard},
     test_result::TestResult,
     time,
-    types::TestDesc,
+    types::{TestDesc, TestType},
 };
 
 pub(crate) struct JsonFormatter<T> {
@@ -71,16 +72,32 @@ fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Res
             start_col,
             end_line,
             end_col,
+            should_panic,
+            compile_fail,
+            no_run,
+            test_type: kind,
             ..
         } = desc;
 
         let name = EscapedString(name.as_slice());
-        let ignore_message = ignore_message.unwrap_or("");
+        let ignore_message = EscapedString(ignore_message.unwrap_or(""));
         let source_path = EscapedString(source_file);
+        let (should_panic, should_panic_message) = match should_panic {
+            ShouldPanic::No => (false, ""),
+            ShouldPanic::Yes => (true, ""),
+            ShouldPanic::YesWithMessage(msg) => (true, *msg),
+        };
+        let should_panic_message = EscapedString(should_panic_message);
+        let kind = match kind {
+            TestType::UnitTest => "unit",
+            TestType::IntegrationTest => "integration",
+            TestType::DocTest => "doc",
+            TestType::Unknown => "unknown",
+        };
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col} }}{newline}"#
+            r#"{{ "type": "{test_type}", "event": "discovered", "name": "{name}", "ignore": {ignore}, "ignore_message": "{ignore_message}", "source_path": "{source_path}", "start_line": {start_line}, "start_col": {start_col}, "end_line": {end_line}, "end_col": {end_col}, "should_panic": {should_panic}, "should_panic_message": "{should_panic_message}", "compile_fail": {compile_fail}, "no_run": {no_run}, "test_type": "{kind}" }}{newline}"#
         ))
     }
 
@@ -94,15 +111,25 @@ fn write_discovery_finish(&mut self, state: &ConsoleTestDiscoveryState) -> io::R
             ))
     }
 
-    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
+    ) -> io::Result<()> {
         let shuffle_seed_json = if let Some(shuffle_seed) = shuffle_seed {
             format!(r#", "shuffle_seed": {shuffle_seed}"#)
         } else {
             String::new()
         };
+        let shard_json = if let Some(TestShard { index, count }) = shard {
+            format!(r#", "shard_index": {index}, "shard_count": {count}"#)
+        } else {
+            String::new()
+        };
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {test_count}{shuffle_seed_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "started", "test_count": {test_count}{shuffle_seed_json}{shard_json} }}{newline}"#
             ))
     }
 
@@ -155,6 +182,15 @@ fn write_result(
                 Some(&*format!(r#""message": "{}""#, EscapedString(m))),
             ),
 
+            TestResult::TrFlaky(attempts) => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "flaky",
+                exec_time,
+                stdout,
+                Some(&*format!(r#""attempts": {attempts}"#)),
+            ),
+
             TestResult::TrIgnored => self.write_event(
                 "test",
                 desc.name.as_slice(),
@@ -202,6 +238,11 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let ignored = state.ignored;
         let measured = state.measured;
         let filtered_out = state.filtered_out;
+        let flaky_json = if !state.flaky.is_empty() {
+            format!(r#", "flaky": {}"#, state.flaky.len())
+        } else {
+            String::new()
+        };
         let exec_time_json = if let Some(ref exec_time) = state.exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
         } else {
@@ -210,7 +251,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let newline = "\n";
 
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{exec_time_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "{event}", "passed": {passed}, "failed": {failed}, "ignored": {ignored}, "measured": {measured}, "filtered_out": {filtered_out}{flaky_json}{exec_time_json} }}{newline}"#
         ))?;
 
         Ok(state.failed == 0)
@@ -4,6 +4,7 @@
 use super::OutputFormatter;
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    options::TestShard,
     test_result::TestResult,
     time,
     types::{TestDesc, TestType},
@@ -12,11 +13,12 @@
 pub struct JunitFormatter<T> {
     out: OutputLocation<T>,
     results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+    shard: Option<TestShard>,
 }
 
 impl<T: Write> JunitFormatter<T> {
     pub fn new(out: OutputLocation<T>) -> Self {
-        Self { out, results: Vec::new() }
+        Self { out, results: Vec::new(), shard: None }
     }
 
     fn write_message(&mut self, s: &str) -> io::Result<()> {
@@ -55,7 +57,10 @@ fn write_run_start(
         &mut self,
         _test_count: usize,
         _shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
     ) -> io::Result<()> {
+        // The shard is recorded in the properties of the testsuite, which is written at the end
+        self.shard = shard;
         // We write xml header on run start
         self.write_message("<?xml version=\"1.0\" encoding=\"UTF-8\"?>")
     }
@@ -97,6 +102,14 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              >",
             state.failed, state.total, state.ignored
         ))?;
+        if let Some(TestShard { index, count }) = self.shard {
+            self.write_message(&format!(
+                "<properties>\
+                 <property name=\"shard_index\" value=\"{index}\"/>\
+                 <property name=\"shard_count\" value=\"{count}\"/>\
+                 </properties>"
+            ))?;
+        }
         for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
             let (class_name, test_name) = parse_class_name(&desc);
             match result {
@@ -147,6 +160,26 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                     self.write_message("</testcase>")?;
                 }
 
+                TestResult::TrFlaky(attempts) => {
+                    self.write_message(&format!(
+                        "<testcase classname=\"{}\" \
+                         name=\"{}\" time=\"{}\">",
+                        class_name,
+                        test_name,
+                        duration.as_secs_f64()
+                    ))?;
+                    // Each failed attempt is reported like surefire reports reruns of flaky tests
+                    for _ in 1..attempts {
+                        self.write_message("<flakyFailure type=\"assert\"/>")?;
+                    }
+                    if !stdout.is_empty() {
+                        self.write_message("<system-out>")?;
+                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                        self.write_message("</system-out>")?;
+                    }
+                    self.write_message("</testcase>")?;
+                }
+
                 TestResult::TrBench(ref b) => {
                     self.write_message(&format!(
                         "<testcase classname=\"benchmark::{}\" \
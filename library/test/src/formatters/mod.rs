@@ -2,6 +2,7 @@
 
 use crate::{
     console::{ConsoleTestDiscoveryState, ConsoleTestState},
+    options::TestShard,
     test_result::TestResult,
     time,
     types::{TestDesc, TestName},
@@ -10,11 +11,13 @@
 mod json;
 mod junit;
 mod pretty;
+mod tap;
 mod terse;
 
 pub(crate) use self::json::JsonFormatter;
 pub(crate) use self::junit::JunitFormatter;
 pub(crate) use self::pretty::PrettyFormatter;
+pub(crate) use self::tap::TapFormatter;
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
@@ -22,7 +25,12 @@ pub(crate) trait OutputFormatter {
     fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()>;
     fn write_discovery_finish(&mut self, state: &ConsoleTestDiscoveryState) -> io::Result<()>;
 
-    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()>;
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
+    ) -> io::Result<()>;
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()>;
     fn write_result(
@@ -44,3 +52,16 @@ pub(crate) fn write_stderr_delimiter(test_output: &mut Vec<u8>, test_name: &Test
     }
     writeln!(test_output, "---- {test_name} stderr ----").unwrap();
 }
+
+pub(crate) fn write_attempt_delimiter(
+    test_output: &mut Vec<u8>,
+    test_name: &TestName,
+    attempt: usize,
+) {
+    match test_output.last() {
+        Some(b'\n') => (),
+        Some(_) => test_output.push(b'\n'),
+        None => (),
+    }
+    writeln!(test_output, "---- {test_name} attempt {attempt} ----").unwrap();
+}
@@ -4,6 +4,7 @@
 use crate::{
     bench::fmt_bench_samples,
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    options::TestShard,
     term,
     test_result::TestResult,
     time,
@@ -53,6 +54,10 @@ pub fn write_ignored(&mut self, message: Option<&'static str>) -> io::Result<()>
         }
     }
 
+    pub fn write_flaky(&mut self, attempts: usize) -> io::Result<()> {
+        self.write_short_result(&format!("ok (flaky, {attempts} attempts)"), term::color::YELLOW)
+    }
+
     pub fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
@@ -168,6 +173,10 @@ pub fn write_time_failures(&mut self, state: &ConsoleTestState) -> io::Result<()
         self.write_results(&state.time_failures, "failures (time limit exceeded)")
     }
 
+    pub fn write_flaky_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_results(&state.flaky, "flaky")
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         if let Some(test_mode) = desc.test_mode() {
@@ -208,14 +217,24 @@ fn plural(count: usize, s: &str) -> String {
         ))
     }
 
-    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
+    ) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
         let shuffle_seed_msg = if let Some(shuffle_seed) = shuffle_seed {
             format!(" (shuffle seed: {shuffle_seed})")
         } else {
             String::new()
         };
-        self.write_plain(format!("\nrunning {test_count} {noun}{shuffle_seed_msg}\n"))
+        let shard_msg = if let Some(TestShard { index, count }) = shard {
+            format!(" (shard: {index}/{count})")
+        } else {
+            String::new()
+        };
+        self.write_plain(format!("\nrunning {test_count} {noun}{shuffle_seed_msg}{shard_msg}\n"))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -251,6 +270,7 @@ fn write_result(
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
+            TestResult::TrFlaky(attempts) => self.write_flaky(attempts)?,
         }
 
         self.write_time(desc, exec_time)?;
@@ -269,6 +289,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_successes(state)?;
         }
+        if !state.flaky.is_empty() {
+            self.write_flaky_tests(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             if !state.failures.is_empty() {
@@ -289,9 +312,19 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky_msg = if !state.flaky.is_empty() {
+            format!(" ({} flaky)", state.flaky.len())
+        } else {
+            String::new()
+        };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
-            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+            ". {} passed{}; {} failed; {} ignored; {} measured; {} filtered out",
+            state.passed,
+            flaky_msg,
+            state.failed,
+            state.ignored,
+            state.measured,
+            state.filtered_out
         );
 
         self.write_plain(s)?;
@@ -0,0 +1,190 @@
+use std::fmt::Display;
+use std::io::{self, prelude::Write};
+
+use super::OutputFormatter;
+use crate::{
+    bench::fmt_bench_samples,
+    console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    options::TestShard,
+    test_result::TestResult,
+    time,
+    types::TestDesc,
+};
+
+/// Writes the results in the format of TAP (the Test Anything Protocol) version 14, see
+/// <https://testanything.org/tap-version-14-specification.html>.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    /// Number of test points written so far
+    test_count: usize,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out, test_count: 0 }
+    }
+
+    fn write_plain<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        let s = s.as_ref();
+        self.out.write_all(s.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn write_test_point(
+        &mut self,
+        ok: bool,
+        desc: &TestDesc,
+        directive: Option<String>,
+    ) -> io::Result<()> {
+        self.test_count += 1;
+        let status = if ok { "ok" } else { "not ok" };
+        let number = self.test_count;
+        let name = escape_description(desc.name.as_slice());
+        let test_mode = desc.test_mode().map(|mode| format!(" - {mode}")).unwrap_or_default();
+        let directive = directive.map(|directive| format!(" # {directive}")).unwrap_or_default();
+        self.write_plain(format!("{status} {number} - {name}{test_mode}{directive}\n"))
+    }
+
+    /// Writes the YAML diagnostics block of the last test point, if there are any diagnostics.
+    fn write_diagnostics(&mut self, diagnostics: &str) -> io::Result<()> {
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+        self.write_plain(format!("  ---\n{diagnostics}  ...\n"))
+    }
+}
+
+/// Escapes the characters that have a meaning in the description of a test point.
+fn escape_description(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('#', "\\#")
+}
+
+/// Formats a field of a diagnostics block with a plain value.
+fn yaml_field(key: &str, value: impl Display) -> String {
+    format!("  {key}: {value}\n")
+}
+
+/// Formats a field of a diagnostics block with a value that can span multiple lines.
+fn yaml_block(key: &str, text: &str) -> String {
+    // The literal block scalar keeps the text as it is, and the indentation indicator allows the
+    // first line to start with spaces.
+    let mut block = format!("  {key}: |2\n");
+    for line in text.lines() {
+        // Control characters aren't allowed in YAML.
+        let line = line.replace(|c: char| c.is_control() && c != '\t', "\u{FFFD}");
+        block.push_str(&format!("    {line}\n"));
+    }
+    block
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_test_discovered(&mut self, _desc: &TestDesc, _test_type: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "Not yet implemented!"))
+    }
+
+    fn write_run_start(
+        &mut self,
+        _test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
+    ) -> io::Result<()> {
+        self.write_plain("TAP version 14\n")?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.write_plain(format!("# shuffle seed: {shuffle_seed}\n"))?;
+        }
+        if let Some(TestShard { index, count }) = shard {
+            self.write_plain(format!("# shard: {index}/{count}\n"))?;
+        }
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // We do not output anything on test start.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.write_plain(format!(
+            "# test {} has been running for over {} seconds\n",
+            desc.name,
+            time::TEST_WARN_TIMEOUT_S
+        ))
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let mut diagnostics = String::new();
+        match *result {
+            TestResult::TrOk => self.write_test_point(true, desc, None)?,
+            TestResult::TrIgnored => {
+                let directive = match desc.ignore_message {
+                    Some(msg) => format!("SKIP {msg}"),
+                    None => "SKIP".to_owned(),
+                };
+                self.write_test_point(true, desc, Some(directive))?;
+            }
+            TestResult::TrFailed => {
+                self.write_test_point(false, desc, None)?;
+                diagnostics += &yaml_field("severity", "fail");
+            }
+            TestResult::TrFailedMsg(ref msg) => {
+                self.write_test_point(false, desc, None)?;
+                diagnostics += &yaml_block("message", msg);
+                diagnostics += &yaml_field("severity", "fail");
+            }
+            TestResult::TrTimedFail => {
+                self.write_test_point(false, desc, None)?;
+                diagnostics += &yaml_field("message", "time limit exceeded");
+                diagnostics += &yaml_field("severity", "fail");
+            }
+            TestResult::TrFlaky(attempts) => {
+                self.write_test_point(true, desc, None)?;
+                diagnostics += &yaml_field("message", "flaky");
+                diagnostics += &yaml_field("attempts", attempts);
+            }
+            TestResult::TrBench(ref bs) => {
+                self.write_test_point(true, desc, None)?;
+                diagnostics += &yaml_block("bench", fmt_bench_samples(bs).trim());
+            }
+        }
+
+        if let Some(exec_time) = exec_time {
+            diagnostics += &yaml_field("duration_ms", exec_time.0.as_secs_f64() * 1000.0);
+        }
+        // Like the other formatters, only show the output of successful tests if requested.
+        let display_output = match *result {
+            TestResult::TrOk | TestResult::TrBench(_) => state.options.display_output,
+            TestResult::TrIgnored => false,
+            _ => true,
+        };
+        if display_output && !stdout.is_empty() {
+            diagnostics += &yaml_block("output", &String::from_utf8_lossy(stdout));
+        }
+        self.write_diagnostics(&diagnostics)
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        // The plan comes last, so that it's also right when the run stops early with --fail-fast.
+        self.write_plain(format!("1..{}\n", self.test_count))?;
+        self.write_plain(format!(
+            "# {} passed; {} failed; {} ignored; {} measured; {} filtered out\n",
+            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+        ))?;
+
+        Ok(state.failed == 0)
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{
     bench::fmt_bench_samples,
     console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation},
+    options::TestShard,
     term,
     test_result::TestResult,
     time,
@@ -51,6 +52,10 @@ pub fn write_failed(&mut self) -> io::Result<()> {
         self.write_short_result("F", term::color::RED)
     }
 
+    pub fn write_flaky(&mut self) -> io::Result<()> {
+        self.write_short_result("f", term::color::YELLOW)
+    }
+
     pub fn write_ignored(&mut self) -> io::Result<()> {
         self.write_short_result("i", term::color::YELLOW)
     }
@@ -154,6 +159,32 @@ pub fn write_failures(&mut self, state: &ConsoleTestState) -> io::Result<()> {
         Ok(())
     }
 
+    pub fn write_flaky_tests(&mut self, state: &ConsoleTestState) -> io::Result<()> {
+        self.write_plain("\nflaky:\n")?;
+        let mut flaky = Vec::new();
+        let mut flaky_out = String::new();
+        for (f, stdout) in &state.flaky {
+            flaky.push(f.name.to_string());
+            if !stdout.is_empty() {
+                flaky_out.push_str(&format!("---- {} stdout ----\n", f.name));
+                let output = String::from_utf8_lossy(stdout);
+                flaky_out.push_str(&output);
+                flaky_out.push('\n');
+            }
+        }
+        if !flaky_out.is_empty() {
+            self.write_plain("\n")?;
+            self.write_plain(&flaky_out)?;
+        }
+
+        self.write_plain("\nflaky:\n")?;
+        flaky.sort();
+        for name in &flaky {
+            self.write_plain(&format!("    {name}\n"))?;
+        }
+        Ok(())
+    }
+
     fn write_test_name(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = desc.padded_name(self.max_name_len, desc.name.padding());
         if let Some(test_mode) = desc.test_mode() {
@@ -179,7 +210,12 @@ fn write_discovery_finish(&mut self, _state: &ConsoleTestDiscoveryState) -> io::
         Ok(())
     }
 
-    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+        shard: Option<TestShard>,
+    ) -> io::Result<()> {
         self.total_test_count = test_count;
         let noun = if test_count != 1 { "tests" } else { "test" };
         let shuffle_seed_msg = if let Some(shuffle_seed) = shuffle_seed {
@@ -187,7 +223,12 @@ fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> i
         } else {
             String::new()
         };
-        self.write_plain(format!("\nrunning {test_count} {noun}{shuffle_seed_msg}\n"))
+        let shard_msg = if let Some(TestShard { index, count }) = shard {
+            format!(" (shard: {index}/{count})")
+        } else {
+            String::new()
+        };
+        self.write_plain(format!("\nrunning {test_count} {noun}{shuffle_seed_msg}{shard_msg}\n"))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -216,6 +257,7 @@ fn write_result(
                 self.write_failed()
             }
             TestResult::TrIgnored => self.write_ignored(),
+            TestResult::TrFlaky(_) => self.write_flaky(),
             TestResult::TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
@@ -238,6 +280,9 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_outputs(state)?;
         }
+        if !state.flaky.is_empty() {
+            self.write_flaky_tests(state)?;
+        }
         let success = state.failed == 0;
         if !success {
             self.write_failures(state)?;
@@ -252,9 +297,19 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             self.write_pretty("FAILED", term::color::RED)?;
         }
 
+        let flaky_msg = if !state.flaky.is_empty() {
+            format!(" ({} flaky)", state.flaky.len())
+        } else {
+            String::new()
+        };
         let s = format!(
-            ". {} passed; {} failed; {} ignored; {} measured; {} filtered out",
-            state.passed, state.failed, state.ignored, state.measured, state.filtered_out
+            ". {} passed{}; {} failed; {} ignored; {} measured; {} filtered out",
+            state.passed,
+            flaky_msg,
+            state.failed,
+            state.ignored,
+            state.measured,
+            state.filtered_out
         );
 
         self.write_plain(s)?;
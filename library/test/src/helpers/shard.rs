@@ -0,0 +1,14 @@
+use crate::options::TestShard;
+use crate::types::TestName;
+
+/// Whether the test named `name` belongs to `shard`.
+pub fn is_in_shard(shard: TestShard, name: &TestName) -> bool {
+    (name_hash(name.as_slice()) % shard.count as u64) as usize == shard.index
+}
+
+// The 64-bit FNV-1a hash. Unlike `DefaultHasher`, its output is fixed, so every machine that
+// runs a shard of the same test binary agrees on the shards.
+fn name_hash(name: &str) -> u64 {
+    name.bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3))
+}
@@ -29,7 +29,7 @@
 // Public reexports
 pub use self::bench::{black_box, Bencher};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic, TestShard};
 pub use self::types::TestName::*;
 pub use self::types::*;
 pub use self::ColorConfig::*;
@@ -43,7 +43,7 @@ pub mod test {
         cli::{parse_opts, TestOpts},
         filter_tests,
         helpers::metrics::{Metric, MetricMap},
-        options::{Options, RunIgnored, RunStrategy, ShouldPanic},
+        options::{Options, RunIgnored, RunStrategy, ShouldPanic, TestShard},
         run_test, test_main, test_main_static,
         test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk},
         time::{TestExecTime, TestTimeOptions},
@@ -61,7 +61,7 @@ pub mod test {
     mem::ManuallyDrop,
     panic::{self, catch_unwind, AssertUnwindSafe, PanicInfo},
     process::{self, Command, Termination},
-    sync::mpsc::{channel, Sender},
+    sync::mpsc::{channel, Receiver, Sender},
     sync::{Arc, Mutex},
     thread,
     time::{Duration, Instant},
@@ -86,6 +86,7 @@ pub mod test {
 use core::any::Any;
 use event::{CompletedTest, TestEvent};
 use helpers::concurrency::get_concurrency;
+use helpers::shard::is_in_shard;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
 use test_result::*;
@@ -100,6 +101,13 @@ pub mod test {
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Option<Options>) {
+    // If we're being run in SpawnedSecondary mode, run the test here. run_test
+    // will then exit the process.
+    if let Ok(name) = env::var(SECONDARY_TEST_INVOKER_VAR) {
+        env::remove_var(SECONDARY_TEST_INVOKER_VAR);
+        run_spawned_test(&name, tests);
+    }
+
     let mut opts = match cli::parse_opts(args) {
         Some(Ok(o)) => o,
         Some(Err(msg)) => {
@@ -171,40 +179,37 @@ pub fn test_main_static(tests: &[&TestDescAndFn]) {
 /// This is the entry point for the main function generated by `rustc --test`
 /// when panic=abort.
 pub fn test_main_static_abort(tests: &[&TestDescAndFn]) {
-    // If we're being run in SpawnedSecondary mode, run the test here. run_test
-    // will then exit the process.
-    if let Ok(name) = env::var(SECONDARY_TEST_INVOKER_VAR) {
-        env::remove_var(SECONDARY_TEST_INVOKER_VAR);
+    let args = env::args().collect::<Vec<_>>();
+    let owned_tests: Vec<_> = tests.iter().map(make_owned_test).collect();
+    test_main(&args, owned_tests, Some(Options::new().panic_abort(true)))
+}
 
-        // Convert benchmarks to tests if we're not benchmarking.
-        let mut tests = tests.iter().map(make_owned_test).collect::<Vec<_>>();
-        if env::var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR).is_ok() {
-            env::remove_var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR);
-        } else {
-            tests = convert_benchmarks_to_tests(tests);
-        };
+/// Runs the test with the given name in a subprocess spawned by `spawn_test_subprocess`, and
+/// exits the process with the result.
+fn run_spawned_test(name: &str, mut tests: Vec<TestDescAndFn>) -> ! {
+    // Convert benchmarks to tests if we're not benchmarking.
+    if env::var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR).is_ok() {
+        env::remove_var(SECONDARY_TEST_BENCH_BENCHMARKS_VAR);
+    } else {
+        tests = convert_benchmarks_to_tests(tests);
+    };
 
-        let test = tests
-            .into_iter()
-            .find(|test| test.desc.name.as_slice() == name)
-            .unwrap_or_else(|| panic!("couldn't find a test with the provided name '{name}'"));
-        let TestDescAndFn { desc, testfn } = test;
-        match testfn.into_runnable() {
-            Runnable::Test(runnable_test) => {
-                if runnable_test.is_dynamic() {
-                    panic!("only static tests are supported");
-                }
-                run_test_in_spawned_subprocess(desc, runnable_test);
-            }
-            Runnable::Bench(_) => {
-                panic!("benchmarks should not be executed into child processes")
+    let test = tests
+        .into_iter()
+        .find(|test| test.desc.name.as_slice() == name)
+        .unwrap_or_else(|| panic!("couldn't find a test with the provided name '{name}'"));
+    let TestDescAndFn { desc, testfn } = test;
+    match testfn.into_runnable() {
+        Runnable::Test(runnable_test) => {
+            if runnable_test.is_dynamic() {
+                panic!("only static tests are supported");
             }
+            run_test_in_spawned_subprocess(desc, runnable_test);
+        }
+        Runnable::Bench(_) => {
+            panic!("benchmarks should not be executed into child processes")
         }
     }
-
-    let args = env::args().collect::<Vec<_>>();
-    let owned_tests: Vec<_> = tests.iter().map(make_owned_test).collect();
-    test_main(&args, owned_tests, Some(Options::new().panic_abort(true)))
 }
 
 /// Clones static values for putting into a dynamic vector, which test_main()
@@ -294,6 +299,15 @@ struct TimeoutEntry {
         timeout: Instant,
     }
 
+    /// A test that is run again when it fails, see `--retries`.
+    struct RetriedTest {
+        desc: TestDesc,
+        testfn: TestFn,
+        failures: Vec<CompletedTest>,
+    }
+
+    type RetryMap = HashMap<TestId, RetriedTest, BuildHasherDefault<DefaultHasher>>;
+
     let tests_len = tests.len();
 
     let mut filtered = FilteredTests { tests: Vec::new(), benches: Vec::new(), next_id: 0 };
@@ -323,7 +337,7 @@ struct TimeoutEntry {
 
     let shuffle_seed = get_shuffle_seed(opts);
 
-    let event = TestEvent::TeFiltered(filtered.total_len(), shuffle_seed);
+    let event = TestEvent::TeFiltered(filtered.total_len(), shuffle_seed, opts.shard);
     notify_about_test_event(event)?;
 
     let concurrency = opts.test_threads.unwrap_or_else(get_concurrency);
@@ -346,6 +360,7 @@ struct TimeoutEntry {
 
     let mut running_tests: TestMap = HashMap::default();
     let mut timeout_queue: VecDeque<TimeoutEntry> = VecDeque::new();
+    let mut retried_tests: RetryMap = HashMap::default();
 
     fn get_timed_out_tests(
         running_tests: &TestMap,
@@ -372,18 +387,91 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
         })
     }
 
+    // Returns whether this is the first attempt to run the test, and keeps a copy of the test to
+    // run it again if it fails.
+    fn start_attempt(
+        opts: &TestOpts,
+        retried_tests: &mut RetryMap,
+        id: TestId,
+        test: &TestDescAndFn,
+    ) -> bool {
+        if retried_tests.contains_key(&id) {
+            return false;
+        }
+        if opts.retries > 0 {
+            if let Some(testfn) = test.testfn.try_clone() {
+                let desc = test.desc.clone();
+                retried_tests.insert(id, RetriedTest { desc, testfn, failures: Vec::new() });
+            }
+        }
+        true
+    }
+
+    // Returns the result of the test, or `None` if it failed and has been queued to run again.
+    fn finish_attempt(
+        opts: &TestOpts,
+        retried_tests: &mut RetryMap,
+        remaining: &mut VecDeque<(TestId, TestDescAndFn)>,
+        mut completed_test: CompletedTest,
+    ) -> Option<CompletedTest> {
+        let id = completed_test.id;
+        let Some(retried_test) = retried_tests.get_mut(&id) else {
+            return Some(completed_test);
+        };
+        let failed = matches!(completed_test.result, TrFailed | TrFailedMsg(_) | TrTimedFail);
+        if failed && retried_test.failures.len() < opts.retries {
+            let desc = retried_test.desc.clone();
+            let testfn = retried_test.testfn.try_clone().unwrap();
+            remaining.push_front((id, TestDescAndFn { desc, testfn }));
+            retried_test.failures.push(completed_test);
+            return None;
+        }
+
+        let RetriedTest { failures, .. } = retried_tests.remove(&id).unwrap();
+        if failures.is_empty() {
+            return Some(completed_test);
+        }
+
+        // Keep the output of every attempt.
+        let attempts = failures.len() + 1;
+        let name = &completed_test.desc.name;
+        let mut stdout = Vec::new();
+        for (attempt, failure) in failures.iter().enumerate() {
+            formatters::write_attempt_delimiter(&mut stdout, name, attempt + 1);
+            stdout.extend_from_slice(&failure.stdout);
+            if let TrFailedMsg(msg) = &failure.result {
+                stdout.extend_from_slice(format!("note: {msg}").as_bytes());
+            }
+        }
+        formatters::write_attempt_delimiter(&mut stdout, name, attempts);
+        stdout.extend_from_slice(&completed_test.stdout);
+        completed_test.stdout = stdout;
+
+        if !failed {
+            completed_test.result = TrFlaky(attempts);
+        }
+        Some(completed_test)
+    }
+
     if concurrency == 1 {
         while !remaining.is_empty() {
             let (id, test) = remaining.pop_front().unwrap();
-            let event = TestEvent::TeWait(test.desc.clone());
-            notify_about_test_event(event)?;
+            if start_attempt(opts, &mut retried_tests, id, &test) {
+                let event = TestEvent::TeWait(test.desc.clone());
+                notify_about_test_event(event)?;
+            }
             let join_handle = run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
             // Wait for the test to complete.
             let mut completed_test = rx.recv().unwrap();
             RunningTest { join_handle }.join(&mut completed_test);
+            let Some(completed_test) =
+                finish_attempt(opts, &mut retried_tests, &mut remaining, completed_test)
+            else {
+                continue;
+            };
 
             let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
+                TrIgnored | TrOk | TrBench(_) | TrFlaky(_) => false,
                 TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
             };
 
@@ -401,8 +489,10 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
                 let timeout = time::get_default_test_timeout();
                 let desc = test.desc.clone();
 
-                let event = TestEvent::TeWait(desc.clone());
-                notify_about_test_event(event)?; //here no pad
+                if start_attempt(opts, &mut retried_tests, id, &test) {
+                    let event = TestEvent::TeWait(desc.clone());
+                    notify_about_test_event(event)?; //here no pad
+                }
                 let join_handle =
                     run_test(opts, !opts.run_tests, id, test, run_strategy, tx.clone());
                 running_tests.insert(id, RunningTest { join_handle });
@@ -437,15 +527,20 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
             let mut completed_test = res.unwrap();
             let running_test = running_tests.remove(&completed_test.id).unwrap();
             running_test.join(&mut completed_test);
+            pending -= 1;
+            let Some(completed_test) =
+                finish_attempt(opts, &mut retried_tests, &mut remaining, completed_test)
+            else {
+                continue;
+            };
 
             let fail_fast = match completed_test.result {
-                TrIgnored | TrOk | TrBench(_) => false,
+                TrIgnored | TrOk | TrBench(_) | TrFlaky(_) => false,
                 TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
             notify_about_test_event(event)?;
-            pending -= 1;
 
             if fail_fast {
                 // Prevent remaining test threads from panicking
@@ -498,6 +593,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         filtered.retain(|test| test.desc.should_panic == ShouldPanic::No);
     }
 
+    // Keep the tests of the selected shard
+    if let Some(shard) = opts.shard {
+        filtered.retain(|test| is_in_shard(shard, &test.desc.name));
+    }
+
     // maybe unignore tests
     match opts.run_ignored {
         RunIgnored::Yes => {
@@ -558,6 +658,22 @@ pub fn run_test(
                 };
             }
 
+            // Tests with a time limit are run in a subprocess, which can be killed when the
+            // limit is exceeded.
+            let timeout = desc.timeout.map(Duration::from_secs).or(opts.test_timeout);
+            let supports_processes = !cfg!(target_family = "wasm") && !cfg!(target_os = "zkvm");
+            let strategy = match strategy {
+                RunStrategy::InProcess
+                    if timeout.is_some()
+                        && supports_processes
+                        && !runnable_test.is_dynamic()
+                        && !opts.force_run_in_process =>
+                {
+                    RunStrategy::SpawnPrimary
+                }
+                strategy => strategy,
+            };
+
             let name = desc.name.clone();
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
@@ -581,6 +697,7 @@ pub fn run_test(
                     monitor_ch,
                     time_options,
                     bench_benchmarks,
+                    timeout,
                 ),
             };
 
@@ -679,6 +796,7 @@ fn spawn_test_subprocess(
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
     bench_benchmarks: bool,
+    timeout: Option<Duration>,
 ) {
     let (result, test_output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
@@ -692,10 +810,17 @@ fn spawn_test_subprocess(
         if nocapture {
             command.stdout(process::Stdio::inherit());
             command.stderr(process::Stdio::inherit());
+        } else {
+            command.stdout(process::Stdio::piped());
+            command.stderr(process::Stdio::piped());
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = match timeout {
+            Some(timeout) => output_with_timeout(&mut command, timeout),
+            None => command.output().map(|output| (output, false)),
+        };
+        let (output, timed_out) = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -712,7 +837,13 @@ fn spawn_test_subprocess(
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
-        let result = get_result_from_exit_code(&desc, status, &time_opts, &exec_time);
+        let result = if let Some(timeout) = timeout.filter(|_| timed_out) {
+            let timeout = timeout.as_secs();
+            writeln!(test_output, "note: test was killed after running for {timeout}s").unwrap();
+            TrTimedFail
+        } else {
+            get_result_from_exit_code(&desc, status, &time_opts, &exec_time)
+        };
         (result, test_output, exec_time)
     })();
 
@@ -720,6 +851,68 @@ fn spawn_test_subprocess(
     monitor_ch.send(message).unwrap();
 }
 
+/// Like `Command::output`, but kills the process if it's still running after `timeout`. Also
+/// returns whether the process was killed.
+fn output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> io::Result<(process::Output, bool)> {
+    fn read_pipe<R: io::Read + Send + 'static>(pipe: Option<R>) -> Receiver<Vec<u8>> {
+        let (tx, rx) = channel();
+        if let Some(mut pipe) = pipe {
+            thread::spawn(move || {
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                    if tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        rx
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut child = command.stdin(process::Stdio::null()).spawn()?;
+    // Read the output while waiting, so that the child doesn't block on a full pipe.
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            timed_out = true;
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // Processes spawned by a killed test can keep its pipes open, so only wait a moment for the
+    // rest of its output.
+    let grace_deadline = Instant::now() + Duration::from_secs(1);
+    let collect = |pipe: Receiver<Vec<u8>>| {
+        let mut output = Vec::new();
+        loop {
+            let chunk = if timed_out {
+                pipe.recv_timeout(grace_deadline.saturating_duration_since(Instant::now())).ok()
+            } else {
+                pipe.recv().ok()
+            };
+            match chunk {
+                Some(chunk) => output.extend_from_slice(&chunk),
+                None => break output,
+            }
+        }
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+    Ok((process::Output { status, stdout, stderr }, timed_out))
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicInfo<'_>>| {
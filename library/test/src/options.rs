@@ -36,6 +36,20 @@ pub enum OutputFormat {
     Json,
     /// JUnit output
     Junit,
+    /// TAP (Test Anything Protocol) output
+    Tap,
+}
+
+/// A part of the tests, to split a test run across several processes or machines
+///
+/// Every test belongs to exactly one of the `count` shards, which is derived from a hash of
+/// its name. The shard of a test therefore doesn't depend on filters or on the other tests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TestShard {
+    /// The shard to run, in `0..count`
+    pub index: usize,
+    /// The number of shards
+    pub count: usize,
 }
 
 /// Whether ignored test should be run or not
@@ -29,6 +29,8 @@ pub enum TestResult {
     TrIgnored,
     TrBench(BenchSamples),
     TrTimedFail,
+    /// The test failed, and passed when it was run again. Holds the number of attempts.
+    TrFlaky(usize),
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
@@ -30,9 +30,12 @@ fn new() -> TestOpts {
             format: OutputFormat::Pretty,
             shuffle: false,
             shuffle_seed: None,
+            shard: None,
+            retries: 0,
             test_threads: None,
             skip: vec![],
             time_options: None,
+            test_timeout: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -54,6 +57,7 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -71,6 +75,7 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -96,6 +101,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -124,6 +130,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -155,6 +162,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::Yes,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -186,6 +194,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage("error message"),
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -222,6 +231,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage(expected),
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -262,6 +272,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::YesWithMessage(expected),
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -296,6 +307,7 @@ fn f() -> Result<(), String> {
                 should_panic,
                 compile_fail: false,
                 no_run: false,
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(f)),
@@ -329,6 +341,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -371,6 +384,7 @@ fn f() -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type,
         },
         testfn: DynTestFn(Box::new(f)),
@@ -415,6 +429,7 @@ fn typed_test_desc(test_type: TestType) -> TestDesc {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type,
     }
 }
@@ -532,6 +547,7 @@ pub fn exclude_should_panic_option() {
             should_panic: ShouldPanic::Yes,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -561,6 +577,7 @@ fn tests() -> Vec<TestDescAndFn> {
                     should_panic: ShouldPanic::No,
                     compile_fail: false,
                     no_run: false,
+                    timeout: None,
                     test_type: TestType::Unknown,
                 },
                 testfn: DynTestFn(Box::new(move || Ok(()))),
@@ -657,6 +674,7 @@ fn testfn() -> Result<(), String> {
                 should_panic: ShouldPanic::No,
                 compile_fail: false,
                 no_run: false,
+                timeout: None,
                 test_type: TestType::Unknown,
             },
             testfn: DynTestFn(Box::new(testfn)),
@@ -731,6 +749,54 @@ pub fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+#[test]
+pub fn shard_tests() {
+    let names = |tests: Vec<TestDescAndFn>| {
+        tests.into_iter().map(|test| test.desc.name.to_string()).collect::<Vec<_>>()
+    };
+    let shards = (0..3)
+        .map(|index| {
+            let opts = TestOpts { shard: Some(TestShard { index, count: 3 }), ..TestOpts::new() };
+            names(filter_tests(&opts, sample_tests()))
+        })
+        .collect::<Vec<_>>();
+
+    // Every test is run by exactly one shard.
+    let mut sharded = shards.concat();
+    sharded.sort();
+    let mut all = names(sample_tests());
+    all.sort();
+    assert_eq!(sharded, all);
+
+    // The shard of a test doesn't depend on the other tests.
+    let opts = TestOpts {
+        filters: vec!["isize".into()],
+        shard: Some(TestShard { index: 1, count: 3 }),
+        ..TestOpts::new()
+    };
+    for name in names(filter_tests(&opts, sample_tests())) {
+        assert!(shards[1].contains(&name));
+    }
+}
+
+#[test]
+fn parse_shard_options() {
+    let parse = |shard_args: &[&str]| {
+        let mut args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        args.extend(shard_args.iter().map(|arg| arg.to_string()));
+        parse_opts(&args).unwrap().map(|opts| opts.shard)
+    };
+
+    assert_eq!(parse(&[]), Ok(None));
+    assert_eq!(
+        parse(&["--shard-index=2", "--shard-count=4"]),
+        Ok(Some(TestShard { index: 2, count: 4 }))
+    );
+    assert!(parse(&["--shard-index=4", "--shard-count=4"]).is_err());
+    assert!(parse(&["--shard-index=0", "--shard-count=0"]).is_err());
+    assert!(parse(&["--shard-index=0"]).is_err());
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -791,6 +857,7 @@ fn f(_: &mut Bencher) -> Result<(), String> {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -819,6 +886,7 @@ fn f(b: &mut Bencher) -> Result<(), String> {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -840,6 +908,7 @@ fn should_sort_failures_before_printing_them() {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -855,6 +924,7 @@ fn should_sort_failures_before_printing_them() {
         should_panic: ShouldPanic::No,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type: TestType::Unknown,
     };
 
@@ -875,6 +945,7 @@ fn should_sort_failures_before_printing_them() {
         not_failures: Vec::new(),
         ignores: Vec::new(),
         time_failures: Vec::new(),
+        flaky: Vec::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -907,6 +978,7 @@ fn f(_: &mut Bencher) -> Result<(), String> {
             should_panic: ShouldPanic::No,
             compile_fail: false,
             no_run: false,
+            timeout: None,
             test_type: TestType::Unknown,
         },
         testfn: DynBenchFn(Box::new(f)),
@@ -922,3 +994,110 @@ fn f(_: &mut Bencher) -> Result<(), String> {
     let result = rx.recv().unwrap().result;
     assert_eq!(result, TrFailed);
 }
+
+#[test]
+#[cfg(not(target_os = "emscripten"))]
+fn retry_failing_tests() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static FLAKY_RUNS: AtomicUsize = AtomicUsize::new(0);
+    static FAILING_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    fn flaky() -> Result<(), String> {
+        let run = FLAKY_RUNS.fetch_add(1, Ordering::SeqCst);
+        print!("run {run}");
+        if run < 2 { Err("not yet".into()) } else { Ok(()) }
+    }
+    fn failing() -> Result<(), String> {
+        FAILING_RUNS.fetch_add(1, Ordering::SeqCst);
+        Err("never".into())
+    }
+    fn dyn_failing() -> Result<(), String> {
+        Err("never".into())
+    }
+    let test = |name, testfn| TestDescAndFn {
+        desc: TestDesc {
+            name: StaticTestName(name),
+            ignore: false,
+            ignore_message: None,
+            source_file: "",
+            start_line: 0,
+            start_col: 0,
+            end_line: 0,
+            end_col: 0,
+            should_panic: ShouldPanic::No,
+            compile_fail: false,
+            no_run: false,
+            timeout: None,
+            test_type: TestType::Unknown,
+        },
+        testfn,
+    };
+    let tests = vec![
+        test("flaky", StaticTestFn(flaky)),
+        test("failing", StaticTestFn(failing)),
+        test("dyn_failing", DynTestFn(Box::new(dyn_failing))),
+    ];
+
+    let (tx, rx) = channel();
+    let notify = move |event: TestEvent| {
+        if let TestEvent::TeResult(result) = event {
+            tx.send(result).unwrap();
+        }
+        Ok(())
+    };
+    let opts = TestOpts { run_tests: true, retries: 3, ..TestOpts::new() };
+    run_tests(&opts, tests, notify).unwrap();
+
+    // Every test is reported once, after its last attempt.
+    let mut results = rx.iter().map(|test| (test.desc.name.to_string(), test)).collect::<Vec<_>>();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    let [(_, dyn_failing), (_, failing), (_, flaky)] = &results[..] else {
+        panic!("unexpected results: {results:?}")
+    };
+
+    assert_eq!(flaky.result, TrFlaky(3));
+    let stdout = String::from_utf8_lossy(&flaky.stdout);
+    assert!(stdout.contains("---- flaky attempt 1 ----\nrun 0\n---- flaky attempt 2"), "{stdout}");
+    assert!(stdout.contains("---- flaky attempt 3 ----\nrun 2"), "{stdout}");
+
+    assert_eq!(failing.result, TrFailed);
+    assert_eq!(FAILING_RUNS.load(Ordering::SeqCst), 4);
+    assert_eq!(dyn_failing.result, TrFailed);
+}
+
+#[test]
+fn parse_retries_option() {
+    let args = vec!["progname".to_string(), "-Zunstable-options".to_string(), "--retries=2".into()];
+    assert_eq!(parse_opts(&args).unwrap().unwrap().retries, 2);
+
+    let args = vec!["progname".to_string(), "--retries=2".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+fn parse_test_timeout_option() {
+    let args = vec!["progname".to_string(), "-Zunstable-options".into(), "--test-timeout=5".into()];
+    assert_eq!(parse_opts(&args).unwrap().unwrap().test_timeout, Some(Duration::from_secs(5)));
+
+    let args = vec!["progname".to_string(), "-Zunstable-options".into(), "--test-timeout=0".into()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn kill_process_after_timeout() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "echo started; sleep 10"]).stdout(process::Stdio::piped());
+    let (output, timed_out) =
+        output_with_timeout(&mut command, Duration::from_millis(200)).unwrap();
+    assert!(timed_out);
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"started\n");
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "exit 3"]);
+    let (output, timed_out) = output_with_timeout(&mut command, Duration::from_secs(10)).unwrap();
+    assert!(!timed_out);
+    assert_eq!(output.status.code(), Some(3));
+}
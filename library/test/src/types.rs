@@ -103,6 +103,16 @@ pub fn padding(&self) -> NamePadding {
         }
     }
 
+    /// Returns a copy of the test function, if it can be run more than once.
+    pub(crate) fn try_clone(&self) -> Option<TestFn> {
+        match *self {
+            StaticTestFn(f) => Some(StaticTestFn(f)),
+            StaticBenchFn(f) => Some(StaticBenchFn(f)),
+            StaticBenchAsTestFn(f) => Some(StaticBenchAsTestFn(f)),
+            DynTestFn(..) | DynBenchFn(..) | DynBenchAsTestFn(..) => None,
+        }
+    }
+
     pub(crate) fn into_runnable(self) -> Runnable {
         match self {
             StaticTestFn(f) => Runnable::Test(RunnableTest::Static(f)),
@@ -207,6 +217,8 @@ pub struct TestDesc {
     pub should_panic: options::ShouldPanic,
     pub compile_fail: bool,
     pub no_run: bool,
+    /// Time limit in seconds, after which the test is killed, overriding `--test-timeout`.
+    pub timeout: Option<u64>,
     pub test_type: TestType,
 }
 
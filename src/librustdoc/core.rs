@@ -2,7 +2,9 @@
 use rustc_data_structures::sync::Lrc;
 use rustc_data_structures::unord::UnordSet;
 use rustc_errors::emitter::{stderr_destination, DynEmitter, HumanEmitter};
+use rustc_errors::html::HtmlEmitter;
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_errors::{codes::*, ErrorGuaranteed, TerminalUrl};
 use rustc_feature::UnstableFeatures;
 use rustc_hir::def::Res;
@@ -14,6 +16,7 @@
 use rustc_middle::hir::nested_filter;
 use rustc_middle::ty::{ParamEnv, Ty, TyCtxt};
 use rustc_session::config::{self, CrateType, ErrorOutputType, ResolveDocLinks};
+use rustc_session::filesearch::sysroot_candidates;
 use rustc_session::lint;
 use rustc_session::Session;
 use rustc_span::symbol::sym;
@@ -126,8 +129,8 @@ pub(crate) fn as_local_hir_id(tcx: TyCtxt<'_>, item_id: ItemId) -> Option<HirId>
 
 /// Creates a new `DiagCtxt` that can be used to emit warnings and errors.
 ///
-/// If the given `error_format` is `ErrorOutputType::Json` and no `SourceMap` is given, a new one
-/// will be created for the `DiagCtxt`.
+/// If the given `error_format` is not `ErrorOutputType::HumanReadable` and no `SourceMap` is
+/// given, a new one will be created for the `DiagCtxt`.
 pub(crate) fn new_dcx(
     error_format: ErrorOutputType,
     source_map: Option<Lrc<source_map::SourceMap>>,
@@ -169,6 +172,47 @@ pub(crate) fn new_dcx(
                 .terminal_url(TerminalUrl::No),
             )
         }
+        ErrorOutputType::Sarif { pretty } => {
+            let source_map = source_map.unwrap_or_else(|| {
+                Lrc::new(source_map::SourceMap::new(source_map::FilePathMapping::empty()))
+            });
+            // Load the same translations as the session, as the log contains the rendered
+            // messages. Failures are reported when the session loads them. Unlike the session's
+            // emitter, this one doesn't write an empty log, so that a run without diagnostics
+            // only writes the session's log.
+            let bundle = rustc_errors::fluent_bundle(
+                None,
+                sysroot_candidates().to_vec(),
+                unstable_opts.translate_lang.clone(),
+                unstable_opts.translate_additional_ftl.as_deref(),
+                unstable_opts.translate_directionality_markers,
+            )
+            .unwrap_or(None);
+            Box::new(
+                SarifEmitter::new(
+                    Box::new(io::BufWriter::new(io::stderr())),
+                    source_map,
+                    fallback_bundle,
+                    pretty,
+                )
+                .registry(Some(rustc_driver::diagnostics_registry()))
+                .fluent_bundle(bundle),
+            )
+        }
+        ErrorOutputType::Html => {
+            let source_map = source_map.unwrap_or_else(|| {
+                Lrc::new(source_map::SourceMap::new(source_map::FilePathMapping::empty()))
+            });
+            Box::new(
+                HtmlEmitter::new(
+                    Box::new(io::BufWriter::new(io::stderr())),
+                    source_map,
+                    fallback_bundle,
+                )
+                .ui_testing(unstable_opts.ui_testing)
+                .diagnostic_width(diagnostic_width),
+            )
+        }
     };
 
     rustc_errors::DiagCtxt::new(emitter).with_flags(unstable_opts.dcx_flags(true))
@@ -452,7 +452,7 @@ fn opts() -> Vec<RustcOptGroup> {
                 "",
                 "error-format",
                 "How errors and other messages are produced",
-                "human|json|short",
+                "human|json|short|sarif|html",
             )
         }),
         stable("diagnostic-width", |o| {
@@ -481,6 +481,9 @@ fn opts() -> Vec<RustcOptGroup> {
                 "LEVEL",
             )
         }),
+        unstable("lint-config", |o| {
+            o.optopt("", "lint-config", "Read lint levels from a TOML or JSON file", "PATH")
+        }),
         unstable("index-page", |o| {
             o.optopt("", "index-page", "Markdown file to be used as index page", "PATH")
         }),
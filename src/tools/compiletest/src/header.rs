@@ -1323,6 +1323,7 @@ macro_rules! decision {
         should_panic,
         compile_fail: false,
         no_run: false,
+        timeout: None,
         test_type: test::TestType::Unknown,
     }
 }
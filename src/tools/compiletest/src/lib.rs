@@ -537,11 +537,14 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         color: config.color,
         shuffle: false,
         shuffle_seed: None,
+        shard: None,
+        retries: 0,
         test_threads: None,
         skip: config.skip.clone(),
         list: false,
         options: test::Options::new(),
         time_options: None,
+        test_timeout: None,
         force_run_in_process: false,
         fail_fast: std::env::var_os("RUSTC_TEST_FAIL_FAST").is_some(),
     }
@@ -0,0 +1,5 @@
+fn main() {
+    let x = 100
+    let y = 200
+    println!("{} {}", x, y);
+}
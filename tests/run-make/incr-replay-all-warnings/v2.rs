@@ -0,0 +1,16 @@
+pub fn used() -> u32 {
+    let x = 1;
+    2
+}
+
+fn unused() {}
+
+#[inline]
+pub fn generic<T: Default>() -> T {
+    let y = 3;
+    T::default()
+}
+
+pub fn added() -> u32 {
+    used() + 1
+}
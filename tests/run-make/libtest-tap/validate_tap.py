@@ -0,0 +1,35 @@
+#!/usr/bin/env python
+
+import re
+import sys
+
+# Check that the stream starts with the version, that every test point is numbered in order, that
+# the YAML blocks are closed, and that the plan matches the number of test points.
+lines = sys.stdin.read().splitlines()
+assert lines[0] == "TAP version 14", "Invalid version line: %r" % lines[0]
+
+test_points = 0
+plan = None
+in_yaml = False
+for line in lines[1:]:
+    if in_yaml:
+        if line == "  ...":
+            in_yaml = False
+        else:
+            assert line.startswith("  "), "Invalid YAML line: %r" % line
+    elif line == "  ---":
+        assert test_points > 0, "YAML block without a test point"
+        in_yaml = True
+    elif line.startswith("#"):
+        pass
+    elif re.match(r"^1\.\.\d+$", line):
+        assert plan is None, "Duplicate plan: %r" % line
+        plan = int(line[3:])
+    else:
+        match = re.match(r"^(not )?ok (\d+) - ", line)
+        assert match, "Invalid line: %r" % line
+        test_points += 1
+        assert int(match.group(2)) == test_points, "Invalid test point number: %r" % line
+
+assert not in_yaml, "Unterminated YAML block"
+assert plan == test_points, "The plan doesn't match the %d test points" % test_points
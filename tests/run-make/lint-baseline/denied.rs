@@ -0,0 +1,10 @@
+#![deny(unsafe_op_in_unsafe_fn)]
+
+pub unsafe fn first(p: *const u8) -> u8 {
+    p.read()
+}
+
+pub unsafe fn second(p: *const u8) -> u8 {
+    let unused = 1;
+    p.read()
+}
@@ -0,0 +1,5 @@
+pub fn bar() {}
+
+// Changes the crate hash, so that crates built against the other version reject this one.
+#[cfg(changed)]
+pub fn baz() {}
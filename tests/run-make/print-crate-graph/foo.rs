@@ -0,0 +1,5 @@
+extern crate bar;
+
+pub fn foo() {
+    bar::bar();
+}
@@ -0,0 +1,8 @@
+pub struct Pair {
+    pub first: u8,
+    pub second: u32,
+}
+
+pub fn pair() -> Pair {
+    Pair { first: 1, second: 2 }
+}
@@ -0,0 +1,29 @@
+#!/usr/bin/env python
+
+import json
+import sys
+
+report = json.load(sys.stdin)
+assert report["version"] == 1
+assert report["crate_name"] == "lib"
+
+vtables = report["vtables"]
+assert [vtable["trait_name"] for vtable in vtables] == [
+    "Upcast", "MarkerWithSuper", "Methods", "Super"
+], vtables
+
+# The entries are numbers, unlike in the text output.
+assert vtables[0] == {
+    "trait_name": "Upcast",
+    "entries": 6,
+    "entries_ignoring_upcasting": 4,
+    "entries_for_upcasting": 2,
+    "upcasting_cost_percent": 50.0,
+}, vtables[0]
+assert vtables[2] == {
+    "trait_name": "Methods",
+    "entries": 5,
+    "entries_ignoring_upcasting": 5,
+    "entries_for_upcasting": 0,
+    "upcasting_cost_percent": 0.0,
+}, vtables[2]
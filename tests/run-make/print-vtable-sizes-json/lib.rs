@@ -0,0 +1,13 @@
+pub trait Super {
+    fn f(&self);
+}
+
+pub trait MarkerWithSuper: Super {}
+
+// Upcasting to `Super` needs its own vtable entries, because of `Super::f`.
+pub trait Upcast: MarkerWithSuper + Send + Sync {}
+
+pub trait Methods {
+    fn a(&self);
+    fn b(&self);
+}
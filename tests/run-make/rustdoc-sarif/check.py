@@ -0,0 +1,15 @@
+#!/usr/bin/env python
+
+import json
+import sys
+
+# `json.load` fails with "Extra data" if stderr contains more than one document.
+log = json.load(sys.stdin)
+assert log["version"] == "2.1.0", log
+assert len(log["runs"]) == 1, log
+
+results = log["runs"][0]["results"]
+assert len(results) == int(sys.argv[1]), results
+for result in results:
+    assert result["ruleId"] == "rustdoc::broken_intra_doc_links", result
+    assert result["level"] == "warning", result
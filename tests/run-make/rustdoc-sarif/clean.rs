@@ -0,0 +1,2 @@
+/// Links to [Documented].
+pub struct Documented;
@@ -0,0 +1,74 @@
+#!/usr/bin/env python
+
+import json
+import sys
+
+mir = json.load(sys.stdin)
+assert mir["version"] == 2
+assert mir["crate_name"] == "lib"
+
+types = {ty["id"]: ty for ty in mir["types"]}
+spans = {span["id"]: span for span in mir["spans"]}
+defs = {def_["id"]: def_ for def_ in mir["defs"]}
+allocs = {alloc["id"]: alloc["alloc"] for alloc in mir["allocs"]}
+items = {item["name"]: item for item in mir["items"]}
+assert sorted(items) == ["ANSWER", "add_one", "answer", "call"], sorted(items)
+for item in items.values():
+    assert defs[item["id"]]["path"] == item["name"], item["name"]
+    assert defs[item["id"]]["krate"] == "lib", item["name"]
+
+add_one = items["add_one"]
+assert add_one["kind"] == "Fn"
+body = add_one["body"]
+assert body["arg_count"] == 1
+
+# All types and spans used by the body are in the tables.
+for local in body["locals"]:
+    assert local["ty"] in types, local
+    assert local["span"] in spans, local
+assert [types[local["ty"]]["name"] for local in body["locals"][:2]] == ["i32", "i32"]
+assert types[body["locals"][0]["ty"]]["kind"] == {"RigidTy": {"Int": "I32"}}
+
+fn_span = spans[add_one["span"]]
+assert fn_span["file"].endswith("lib.rs"), fn_span
+assert (fn_span["start_line"], fn_span["end_line"]) == (1, 1), fn_span
+
+# The addition is overflow checked, and the function returns.
+terminators = [block["terminator"] for block in body["blocks"]]
+for terminator in terminators:
+    assert terminator["span"] in spans, terminator
+assert any("Assert" in t["kind"] for t in terminators if isinstance(t["kind"], dict))
+assert "Return" in [t["kind"] for t in terminators]
+
+# The user variable `y` is described in the debug info, in the outermost scope or a child of it.
+y = next(info for info in body["var_debug_info"] if info["name"] == "y")
+scopes = add_one["source_scopes"]
+assert scopes[0]["parent_scope"] is None
+assert y["source_info"]["scope"] < len(scopes)
+
+answer = items["ANSWER"]
+assert answer["kind"] == "Static"
+assert types[answer["body"]["locals"][0]["ty"]]["name"] == "u64"
+
+# The callee of `call` is resolved by its type to the definition of `add_one`.
+calls = [
+    block["terminator"]["kind"]["Call"]
+    for block in items["call"]["body"]["blocks"]
+    if isinstance(block["terminator"]["kind"], dict) and "Call" in block["terminator"]["kind"]
+]
+assert len(calls) == 1, calls
+callee = types[calls[0]["func"]["Constant"]["literal"]["ty"]]
+assert callee["name"].endswith("add_one"), callee
+callee_def, _ = callee["kind"]["RigidTy"]["FnDef"]
+assert defs[callee_def] == {"id": items["add_one"]["id"], "path": "add_one", "kind": "function",
+                            "krate": "lib"}, defs[callee_def]
+
+# The reference to `ANSWER` points to an allocation of the static.
+[literal] = [
+    statement["kind"]["Assign"][1]["Use"]["Constant"]["literal"]
+    for block in items["answer"]["body"]["blocks"]
+    for statement in block["statements"]
+    if "Assign" in statement["kind"] and "Constant" in statement["kind"]["Assign"][1].get("Use", {})
+]
+[(_, alloc_id)] = literal["kind"]["Allocated"]["provenance"]["ptrs"]
+assert defs[allocs[alloc_id]["Static"]["def"]]["path"] == "ANSWER", allocs[alloc_id]
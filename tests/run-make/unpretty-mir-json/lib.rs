@@ -0,0 +1,14 @@
+pub fn add_one(x: i32) -> i32 {
+    let y = x + 1;
+    y
+}
+
+pub static ANSWER: u64 = 42;
+
+pub fn call() -> i32 {
+    add_one(1)
+}
+
+pub fn answer() -> &'static u64 {
+    &ANSWER
+}
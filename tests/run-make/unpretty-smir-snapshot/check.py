@@ -0,0 +1,40 @@
+#!/usr/bin/env python
+
+import json
+import sys
+
+snapshot = json.load(sys.stdin)
+assert snapshot["version"] == 2
+assert snapshot["krate"]["name"] == "lib"
+assert snapshot["entry_fn"] is None
+
+# All tables are sorted by handle.
+for table, key in [("types", "ty"), ("defs", "def"), ("allocs", "id"), ("spans", "span")]:
+    handles = [entry[key] for entry in snapshot[table]]
+    assert handles == sorted(handles), table
+
+types = {ty["ty"]: ty for ty in snapshot["types"]}
+spans = {span["span"]: span for span in snapshot["spans"]}
+allocs = {alloc["id"]: alloc["alloc"] for alloc in snapshot["allocs"]}
+items = {item["name"]: item for item in snapshot["items"]}
+assert sorted(items) == ["add_one", "greeting", "identity"], sorted(items)
+
+for item in items.values():
+    assert item["kind"] == "Fn"
+    assert item["span"] in spans, item
+    for local in item["body"]["locals"]:
+        assert local["ty"] in types, local
+
+# The layouts and ABIs of generic functions are unknown.
+add_one = items["add_one"]
+i32_ty = types[add_one["body"]["locals"][0]["ty"]]
+assert i32_ty["name"] == "i32"
+assert i32_ty["layout"]["size"] == {"num_bits": 32}, i32_ty
+assert add_one["fn_abi"]["ret"]["ty"] == i32_ty["ty"]
+identity = items["identity"]
+assert identity["fn_abi"] is None
+assert types[identity["body"]["locals"][0]["ty"]]["layout"] is None
+
+# The string literal points to an allocation with its bytes.
+strings = [alloc["Memory"] for alloc in allocs.values() if "Memory" in alloc]
+assert [104, 101, 108, 108, 111] in [alloc["bytes"] for alloc in strings], strings
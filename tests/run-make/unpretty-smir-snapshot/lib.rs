@@ -0,0 +1,11 @@
+pub fn add_one(x: i32) -> i32 {
+    x + 1
+}
+
+pub fn greeting() -> &'static str {
+    "hello"
+}
+
+pub fn identity<T>(x: T) -> T {
+    x
+}
@@ -0,0 +1,152 @@
+//@ run-pass
+//! Test that users are able to retrieve the attributes, docs, visibility and parent module of
+//! items with the stable mir APIs.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::crate_def::Visibility;
+use stable_mir::ty::RigidTy;
+use stable_mir::{CrateDef, CrateItem};
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_attrs() -> ControlFlow<()> {
+    let add_one = get_item("add_one");
+    let proof = add_one.attrs_by_path(&["kani".to_string(), "proof".to_string()]);
+    assert_eq!(proof.len(), 1);
+    assert_eq!(proof[0].value, "#[kani::proof]");
+    let inline = add_one.attrs_by_path(&["inline".to_string()]);
+    assert_eq!(inline.len(), 1);
+    assert_eq!(inline[0].value, "#[inline(always)]");
+    assert!(add_one.attrs_by_path(&["kani".to_string()]).is_empty());
+
+    let all_attrs: Vec<_> = add_one.all_attrs().into_iter().map(|attr| attr.value).collect();
+    assert_eq!(
+        all_attrs,
+        ["/// Adds one.", "///", "/// Returns the result.", "#[inline(always)]", "#[kani::proof]"]
+    );
+    assert_eq!(add_one.doc_comment().unwrap(), "Adds one.\n\nReturns the result.");
+    assert_eq!(add_one.visibility(), Some(Visibility::Public));
+
+    let root = add_one.parent_module().unwrap();
+    assert_eq!(root.parent_module(), None);
+
+    let helper = get_item("nested::helper");
+    let skip = helper.attrs_by_path(&["rustfmt".to_string(), "skip".to_string()]);
+    assert_eq!(skip.len(), 1);
+    assert_eq!(skip[0].value, "#[rustfmt::skip]");
+    assert_eq!(helper.doc_comment(), None);
+    assert_eq!(helper.visibility(), Some(Visibility::Restricted(root)));
+    let nested = helper.parent_module().unwrap();
+    assert_eq!(nested.name(), "nested");
+    assert_eq!(nested.parent_module(), Some(root));
+
+    let private = get_item("nested::private");
+    assert_eq!(private.doc_comment().unwrap(), "Generated docs.");
+    assert_eq!(private.visibility(), Some(Visibility::Restricted(nested)));
+
+    // Items in a function body are still in the module of the function.
+    let inner = get_item("nested::private::inner");
+    assert_eq!(inner.parent_module(), Some(nested));
+
+    // Impls and closures don't have a visibility.
+    let impls = stable_mir::local_crate().trait_impls();
+    assert_eq!(impls.len(), 1);
+    assert_eq!(impls[0].visibility(), None);
+    let closure = get_item("with_closure")
+        .body()
+        .locals()
+        .iter()
+        .find_map(|local| match local.ty.kind().rigid() {
+            Some(RigidTy::Closure(def, _)) => Some(*def),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(closure.visibility(), None);
+    ControlFlow::Continue(())
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "attrs_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_attrs).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    #![feature(register_tool)]
+    #![register_tool(kani)]
+    #![allow(dead_code)]
+
+    /// Adds one.
+    ///
+    /// Returns the result.
+    #[inline(always)]
+    #[kani::proof]
+    pub fn add_one(x: u32) -> u32 {{
+        x + 1
+    }}
+
+    pub mod nested {{
+        #[rustfmt::skip]
+        pub(crate) fn helper() {{}}
+
+        #[doc = "Generated docs."]
+        fn private() {{
+            fn inner() {{}}
+            inner()
+        }}
+    }}
+
+    pub struct Unit;
+
+    impl Default for Unit {{
+        fn default() -> Self {{
+            Unit
+        }}
+    }}
+
+    pub fn with_closure() -> u32 {{
+        let closure = || 1;
+        closure()
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//@ run-pass
+//! Test that users are able to retrieve the borrow checker facts of a body.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::borrowck::{BorrowckFacts, PointKind, TwoPhaseActivation};
+use stable_mir::mir::{BorrowKind, MutBorrowKind};
+use stable_mir::ty::{RegionKind, RigidTy, TyKind};
+use stable_mir::{CompilerError, CrateDef, CrateItem};
+use std::assert_matches::assert_matches;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_borrowck_facts() -> ControlFlow<()> {
+    // The receiver of `push` is a two-phase borrow that is activated by the call, after the
+    // shared borrow of `len`.
+    let facts = get_facts("two_phase");
+    let two_phase = facts
+        .loans
+        .iter()
+        .find(|loan| loan.kind == BorrowKind::Mut { kind: MutBorrowKind::TwoPhaseBorrow })
+        .unwrap();
+    assert_matches!(two_phase.activation, TwoPhaseActivation::ActivatedAt(location)
+        if location > two_phase.reserve_location);
+    assert!(facts.loans.iter().any(|loan| loan.kind == BorrowKind::Shared));
+
+    // Assigning to `x` kills the loan of `x`.
+    let x = 1;
+    let facts = get_facts("killed");
+    let (loan, _) =
+        facts.loans.iter().enumerate().find(|(_, loan)| loan.borrowed_place.local == x).unwrap();
+    assert_eq!(facts.loans[loan].activation, TwoPhaseActivation::NotTwoPhase);
+    assert!(facts.loan_issued_at.iter().any(|(region, issued, point)| {
+        *issued == loan && *region == facts.loans[loan].region && point.kind == PointKind::Mid
+    }));
+    assert!(facts.loan_killed_at.iter().any(|(killed, _)| *killed == loan));
+    check_cfg(&facts);
+
+    // The regions of the body are region variables. The region of the argument `a` has to be
+    // equal to the universal region of the lifetime parameter.
+    let facts = get_facts("longest");
+    let TyKind::RigidTy(RigidTy::Ref(region, _, _)) = facts.body.local_decl(1).unwrap().ty.kind()
+    else {
+        panic!("Expected a reference")
+    };
+    let RegionKind::ReVar(region) = region.kind else { panic!("Expected a region variable") };
+    let outlives = |sup, sub| facts.subset_base.iter().any(|&(a, b, _)| a == sup && b == sub);
+    assert!(facts.universal_region.iter().any(|&universal| {
+        universal == region || (outlives(universal, region) && outlives(region, universal))
+    }));
+    assert!(!facts.known_placeholder_subset.is_empty());
+
+    // The body of a loop has false unwinds, which are only part of the control flow graph of the
+    // facts.
+    let facts = get_facts("count");
+    check_cfg(&facts);
+    ControlFlow::Continue(())
+}
+
+/// Checks that the facts are not collected by a session that didn't ask for them.
+fn test_no_facts() -> ControlFlow<()> {
+    assert!(get_item("killed").borrowck_facts().is_err());
+    ControlFlow::Continue(())
+}
+
+fn get_facts(name: &str) -> BorrowckFacts {
+    get_item(name).borrowck_facts().unwrap()
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+/// Checks that the control flow graph only contains points of the body.
+fn check_cfg(facts: &BorrowckFacts) {
+    assert!(!facts.cfg_edge.is_empty());
+    for point in facts.cfg_edge.iter().flat_map(|(from, to)| [from, to]) {
+        let block = &facts.body.blocks[point.location.block];
+        assert!(point.location.statement_index <= block.statements.len());
+    }
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "borrowck_facts_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    rustc_internal::borrowck::collect_borrowck_facts();
+    run!(args.clone(), test_borrowck_facts).unwrap();
+
+    // The facts were only collected by the previous session.
+    run!(args.clone(), test_no_facts).unwrap();
+
+    // Collecting the facts is refused with parallel frontend threads.
+    let mut parallel_args = args.clone();
+    parallel_args.push("-Zthreads=2".to_string());
+    rustc_internal::borrowck::collect_borrowck_facts();
+    let result = run!(parallel_args, || ControlFlow::<()>::Continue(()));
+    assert_eq!(result, Err(CompilerError::Failed));
+
+    // A body with a type error is tainted, so it isn't borrow checked while the facts are
+    // collected either, and the compilation fails with the type error.
+    let error_path = "borrowck_facts_error.rs";
+    std::fs::write(error_path, "pub fn tainted(v: &mut Vec<u32>) -> u32 { let r = &v; r }\n")
+        .unwrap();
+    let mut error_args = args;
+    *error_args.last_mut().unwrap() = error_path.to_string();
+    rustc_internal::borrowck::collect_borrowck_facts();
+    let result = run!(error_args, || ControlFlow::<()>::Continue(()));
+    assert_eq!(result, Err(CompilerError::Failed));
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub fn two_phase(v: &mut Vec<usize>) {{
+        v.push(v.len());
+    }}
+
+    pub fn killed(mut x: u32) -> u32 {{
+        let r = &x;
+        let y = *r;
+        x = 1;
+        x + y
+    }}
+
+    pub fn longest<'a>(a: &'a str, b: &'a str) -> &'a str {{
+        if a.len() > b.len() {{ a }} else {{ b }}
+    }}
+
+    pub fn count(n: u32) -> u32 {{
+        let mut i = 0;
+        while i < n {{
+            i += 1;
+        }}
+        i
+    }}
+    "#
+    )?;
+    Ok(())
+}
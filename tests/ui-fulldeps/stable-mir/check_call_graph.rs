@@ -0,0 +1,194 @@
+//@ run-pass
+//! Test that users are able to build the call graph of a crate with the stable mir APIs.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::call_graph::{CallGraph, CallKind};
+use stable_mir::mir::mono::Instance;
+use stable_mir::{CrateDef, CrateItem};
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_call_graph() -> ControlFlow<()> {
+    let entry = Instance::try_from(get_item("entry")).unwrap();
+    let graph = CallGraph::new([entry]);
+    assert_eq!(graph.nodes()[0], entry);
+
+    let callees = callee_names(&graph, entry);
+    assert!(has_edge(&callees, "input::area", CallKind::Direct));
+    assert!(has_edge(&callees, "input::apply", CallKind::Direct));
+    // `double` is only called through a function pointer.
+    assert!(has_edge(&callees, "input::double", CallKind::FnPtr));
+    assert!(has_edge(&callees, "drop_in_place::<Guard>", CallKind::DropGlue));
+
+    // Both types that are unsized to `dyn Shape` by `entry` may be called, but not the one that is
+    // only unsized by `unused`.
+    let area = graph.nodes().iter().find(|node| node.name() == "input::area").unwrap();
+    let callees = callee_names(&graph, *area);
+    assert_eq!(callees.len(), 2, "{callees:?}");
+    assert!(has_edge(&callees, "<Square as Shape>::area", CallKind::Virtual));
+    assert!(has_edge(&callees, "<Circle as Shape>::area", CallKind::Virtual));
+    assert!(graph.nodes().iter().all(|node| !node.name().contains("Unused")));
+    assert!(graph.nodes().iter().all(|node| !node.name().contains("Triangle")));
+
+    // Dropping the box drops the trait object, which calls the drop glue of `Circle` through its
+    // vtable. `Square` has no drop glue.
+    let dyn_drops: Vec<_> = graph
+        .edges()
+        .iter()
+        .filter(|edge| edge.kind == CallKind::DropGlue && edge.caller.name().contains("dyn Shape"))
+        .map(|edge| edge.callee.name())
+        .collect();
+    assert_eq!(dyn_drops.len(), 1, "{dyn_drops:?}");
+    assert!(dyn_drops[0].contains("drop_in_place::<Circle>"));
+
+    let mut dot = Vec::new();
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph call_graph {"));
+    assert!(dot.contains("[label=\"Virtual\", style=dashed]"));
+
+    let mut json = Vec::new();
+    graph.write_json(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.starts_with("{\"nodes\":[{\"name\":\"input::entry\""));
+    assert!(json.contains("\"kind\":\"FnPtr\""));
+
+    // The vtable and the function pointer that `from_static` only reaches through statics.
+    let from_static = Instance::try_from(get_item("from_static")).unwrap();
+    let graph = CallGraph::new([from_static]);
+    let callees = callee_names(&graph, from_static);
+    assert!(has_edge(&callees, "input::area", CallKind::Direct));
+    assert!(has_edge(&callees, "input::halve", CallKind::FnPtr));
+
+    let area = graph.nodes().iter().find(|node| node.name() == "input::area").unwrap();
+    let callees = callee_names(&graph, *area);
+    assert_eq!(callees.len(), 1, "{callees:?}");
+    assert!(has_edge(&callees, "<Triangle as Shape>::area", CallKind::Virtual));
+    ControlFlow::Continue(())
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+fn callee_names(graph: &CallGraph, caller: Instance) -> Vec<(String, CallKind)> {
+    graph.callees(caller).map(|edge| (edge.callee.name(), edge.kind)).collect()
+}
+
+fn has_edge(callees: &[(String, CallKind)], name: &str, kind: CallKind) -> bool {
+    callees.iter().any(|(callee, callee_kind)| callee.contains(name) && *callee_kind == kind)
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "call_graph_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_call_graph).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub trait Shape {{
+        fn area(&self) -> u32;
+    }}
+
+    pub struct Square(u32);
+    pub struct Circle(u32);
+    pub struct Unused;
+
+    impl Shape for Square {{
+        fn area(&self) -> u32 {{ self.0 * self.0 }}
+    }}
+
+    impl Shape for Circle {{
+        fn area(&self) -> u32 {{ 3 * self.0 * self.0 }}
+    }}
+
+    impl Drop for Circle {{
+        fn drop(&mut self) {{}}
+    }}
+
+    impl Shape for Unused {{
+        fn area(&self) -> u32 {{ 0 }}
+    }}
+
+    pub struct Guard;
+
+    impl Drop for Guard {{
+        fn drop(&mut self) {{}}
+    }}
+
+    fn area(shape: &dyn Shape) -> u32 {{
+        shape.area()
+    }}
+
+    fn double(x: u32) -> u32 {{
+        x * 2
+    }}
+
+    fn apply(f: fn(u32) -> u32, x: u32) -> u32 {{
+        f(x)
+    }}
+
+    pub fn entry(big: bool) -> u32 {{
+        let _guard = Guard;
+        let shape: Box<dyn Shape> = if big {{ Box::new(Square(2)) }} else {{ Box::new(Circle(1)) }};
+        apply(double, area(&*shape))
+    }}
+
+    pub fn unused() -> Box<dyn Shape> {{
+        Box::new(Unused)
+    }}
+
+    pub struct Triangle(u32);
+
+    impl Shape for Triangle {{
+        fn area(&self) -> u32 {{ self.0 * self.0 / 2 }}
+    }}
+
+    fn halve(x: u32) -> u32 {{
+        x / 2
+    }}
+
+    static TRIANGLE: &(dyn Shape + Sync) = &Triangle(2);
+    static HALVE: fn(u32) -> u32 = halve;
+
+    pub fn from_static() -> u32 {{
+        HALVE(area(TRIANGLE))
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//@ run-pass
+//! Test that users are able to retrieve the codegen units of a crate and the mono items in them.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use stable_mir::mir::mono::{Linkage, MonoItem, MonoItemData};
+use stable_mir::CrateDef;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_stable_mir() -> ControlFlow<()> {
+    let cgus = stable_mir::codegen_units();
+    assert!(!cgus.is_empty());
+    assert!(cgus.iter().any(|cgu| cgu.is_primary));
+
+    let items: Vec<(MonoItem, MonoItemData)> =
+        cgus.iter().flat_map(|cgu| cgu.items.iter().cloned()).collect();
+    for cgu in &cgus {
+        let size: usize = cgu.items.iter().map(|(_, data)| data.size_estimate).sum();
+        assert_eq!(cgu.size_estimate, size, "Unexpected size of {}", cgu.name);
+    }
+
+    let find_fn = |name: &str| {
+        items
+            .iter()
+            .find_map(|(item, data)| match item {
+                MonoItem::Fn(instance) if instance.name() == name => Some((instance, data)),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("Expected mono item for `{name}`"))
+    };
+
+    let (_, data) = find_fn("input::entry");
+    assert_eq!(data.linkage, Linkage::External);
+    assert!(!data.inlined);
+
+    // The generic function is only instantiated with `u8`.
+    let (instance, _) = find_fn("input::generic::<u8>");
+    assert_eq!(instance.args().0.len(), 1);
+    let generic = items.iter().filter(|(item, _)| match item {
+        MonoItem::Fn(instance) => instance.name().starts_with("input::generic"),
+        _ => false,
+    });
+    assert_eq!(generic.count(), 1);
+
+    let statics: Vec<_> = items
+        .iter()
+        .filter_map(|(item, _)| match item {
+            MonoItem::Static(def) => Some(def.name()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(statics, ["input::COUNTER"]);
+
+    ControlFlow::Continue(())
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "codegen_units_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "-Cpanic=abort".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_stable_mir).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub static COUNTER: u32 = 0;
+
+    fn generic<T: Copy>(t: T) -> T {{
+        t
+    }}
+
+    pub fn entry() -> u8 {{
+        generic(COUNTER as u8)
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//@ run-pass
+//! Test that users are able to run dataflow analyses on stable mir bodies.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::dataflow::{
+    Analysis, MaybeBorrowedLocals, MaybeInitializedLocals, MaybeLiveLocals, ProgramPoint,
+};
+use stable_mir::mir::{Body, TerminatorKind, RETURN_LOCAL};
+use stable_mir::{CrateDef, CrateItem};
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_dataflow() -> ControlFlow<()> {
+    // The locals of the arguments `v` and `cond`.
+    let (v, cond) = (1, 2);
+    let body = get_item("moves").body();
+    let ret = return_location(&body);
+
+    let mut live = MaybeLiveLocals.iterate_to_fixpoint(&body);
+    assert_eq!(live.state_at_block_start(&body, 0).iter().collect::<Vec<_>>(), [v, cond]);
+    assert_eq!(live.state_before(&body, ret).iter().collect::<Vec<_>>(), [RETURN_LOCAL]);
+    assert!(live.state_after(&body, ret).is_empty());
+
+    let mut init = MaybeInitializedLocals.iterate_to_fixpoint(&body);
+    assert_eq!(init.state_at_block_start(&body, 0).iter().collect::<Vec<_>>(), [v, cond]);
+    // `v` is moved into `drop` or dropped at the end of the function.
+    let at_return = init.state_before(&body, ret);
+    assert!(at_return.contains(RETURN_LOCAL));
+    assert!(!at_return.contains(v));
+
+    // `v` is borrowed by the call to `len`, `cond` is only copied.
+    let mut borrowed = MaybeBorrowedLocals.iterate_to_fixpoint(&body);
+    assert!(borrowed.state_at_block_start(&body, 0).is_empty());
+    let at_return = borrowed.state_before(&body, ret);
+    assert!(at_return.contains(v));
+    assert!(!at_return.contains(cond));
+
+    // The loop counter and the sum are written before they are read, even though the state at the
+    // end of the loop flows back into its start.
+    let body = get_item("sum").body();
+    let mut live = MaybeLiveLocals.iterate_to_fixpoint(&body);
+    assert_eq!(live.state_at_block_start(&body, 0).iter().collect::<Vec<_>>(), [1]);
+    ControlFlow::Continue(())
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+/// Returns the location of the only `return` terminator of `body`.
+fn return_location(body: &Body) -> ProgramPoint {
+    let (block, data) = body
+        .blocks
+        .iter()
+        .enumerate()
+        .find(|(_, data)| matches!(data.terminator.kind, TerminatorKind::Return))
+        .unwrap();
+    ProgramPoint { block, statement_index: data.statements.len() }
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "dataflow_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_dataflow).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub fn moves(v: Vec<u8>, cond: bool) -> usize {{
+        if cond {{
+            drop(v);
+            0
+        }} else {{
+            v.len()
+        }}
+    }}
+
+    pub fn sum(n: u32) -> u32 {{
+        let mut total = 0;
+        let mut i = 0;
+        while i < n {{
+            total += i;
+            i += 1;
+        }}
+        total
+    }}
+    "#
+    )?;
+    Ok(())
+}
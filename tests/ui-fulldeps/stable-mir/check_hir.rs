@@ -0,0 +1,177 @@
+//@ run-pass
+//! Test that users are able to inspect the HIR of the bodies of local items.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use stable_mir::hir::{
+    BinOp, BindingMode, CaptureKind, Closure, Expr, ExprKind, HirVisitor, LitKind, PatKind,
+    StmtKind,
+};
+use stable_mir::mir::{BorrowKind, MutBorrowKind, Mutability};
+use stable_mir::ty::{ClosureKind, IndexedVal};
+use stable_mir::{CrateDef, CrateItem, ItemKind};
+use std::assert_matches::assert_matches;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_hir() -> ControlFlow<()> {
+    test_match();
+    test_closure();
+
+    let ctor = stable_mir::all_local_items()
+        .into_iter()
+        .find(|item| matches!(item.kind(), ItemKind::Ctor(_)))
+        .unwrap();
+    assert!(ctor.hir_body().is_err());
+    ControlFlow::Continue(())
+}
+
+fn test_match() {
+    let item = get_item("classify");
+    let body = item.hir_body().unwrap();
+    assert_matches!(
+        &body.params[0].pat.kind,
+        PatKind::Binding { name, mode: BindingMode::ByValue(Mutability::Not), .. } if name == "v"
+    );
+
+    // The types are the ones of the MIR body.
+    let ExprKind::Block(block, None) = &body.value.kind else { panic!("Expected a block") };
+    let value = block.expr.as_ref().unwrap();
+    assert_eq!(value.ty, item.body().ret_local().ty);
+    let ExprKind::Match { scrutinee, arms, .. } = &value.kind else { panic!("Expected a match") };
+    assert_eq!(scrutinee.ty, body.params[0].pat.ty);
+    assert_eq!(arms.len(), 3);
+
+    // `Some(0) | None`
+    let PatKind::Or(pats) = &arms[0].pat.kind else { panic!("Expected an or-pattern") };
+    let PatKind::TupleStruct { variant, fields, rest: false } = &pats[0].kind else {
+        panic!("Expected a tuple struct pattern")
+    };
+    assert_eq!(variant.to_index(), 1);
+    assert_eq!(fields[0].field, 0);
+    assert_matches!(&fields[0].pat.kind,
+        PatKind::Lit(lit) if lit.kind == ExprKind::Lit(LitKind::Int(0)));
+    assert_matches!(pats[1].kind, PatKind::Path(_));
+
+    // `Some(n) if n > 10`, where `n` is bound by the pattern.
+    let PatKind::TupleStruct { fields, .. } = &arms[1].pat.kind else {
+        panic!("Expected a tuple struct pattern")
+    };
+    let PatKind::Binding { id, .. } = fields[0].pat.kind else { panic!("Expected a binding") };
+    let guard = arms[1].guard.as_ref().unwrap();
+    let ExprKind::Binary(BinOp::Gt, lhs, _) = &guard.kind else { panic!("Expected a comparison") };
+    assert_eq!(lhs.kind, ExprKind::Local(id));
+}
+
+fn test_closure() {
+    let body = get_item("apply").hir_body().unwrap();
+    let ExprKind::Block(block, _) = &body.value.kind else { panic!("Expected a block") };
+    let StmtKind::Let { pat, .. } = &block.stmts[0].kind else { panic!("Expected a let") };
+    let PatKind::Binding { id: total, mode: BindingMode::ByValue(Mutability::Mut), .. } = pat.kind
+    else {
+        panic!("Expected a mutable binding")
+    };
+    let PatKind::Binding { id: offset, .. } = body.params[1].pat.kind else {
+        panic!("Expected a binding")
+    };
+
+    // `values.iter().for_each(..)`
+    let StmtKind::Semi(call) = &block.stmts[1].kind else { panic!("Expected a statement") };
+    let ExprKind::MethodCall { name, receiver, .. } = &call.kind else {
+        panic!("Expected a method call")
+    };
+    assert_eq!(name, "for_each");
+    assert_matches!(&receiver.kind, ExprKind::MethodCall { name, .. } if name == "iter");
+
+    let mut closures = Closures(vec![]);
+    closures.visit_body(&body);
+    let [closure] = &closures.0[..] else { panic!("Expected one closure") };
+    assert_eq!(closure.kind, ClosureKind::FnMut);
+    assert!(!closure.by_move);
+    assert_eq!(closure.params.len(), 1);
+    let mut captures: Vec<_> =
+        closure.captures.iter().map(|capture| (capture.var, capture.kind)).collect();
+    captures.sort_by_key(|(var, _)| *var);
+    assert_eq!(
+        captures,
+        [
+            (offset, CaptureKind::ByRef(BorrowKind::Shared)),
+            (total, CaptureKind::ByRef(BorrowKind::Mut { kind: MutBorrowKind::Default })),
+        ]
+    );
+}
+
+/// Collects the closures of a body.
+struct Closures(Vec<Closure>);
+
+impl HirVisitor for Closures {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Closure(closure) = &expr.kind {
+            self.0.push(closure.clone());
+        }
+        self.super_expr(expr)
+    }
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "hir_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_hir).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub struct Wrapper(pub u32);
+
+    pub fn classify(v: Option<u32>) -> u32 {{
+        match v {{
+            Some(0) | None => 0,
+            Some(n) if n > 10 => n * 2,
+            Some(n) => n,
+        }}
+    }}
+
+    pub fn apply(values: &mut Vec<u32>, offset: u32) -> u32 {{
+        let mut total = 0;
+        values.iter().for_each(|v| total += v + offset);
+        total
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//@ run-pass
+//! Test that users are able to execute instances with the interpreter.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use stable_mir::mir::interpret::{DefaultMachine, Machine, MemoryAccess};
+use stable_mir::mir::mono::Instance;
+use stable_mir::ty::{Allocation, Ty};
+use stable_mir::{CrateDef, Error};
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_interpret() -> ControlFlow<()> {
+    test_loop();
+    test_slice_iter();
+    test_overflow();
+    test_foreign_call();
+    test_memory_writes();
+    test_heap();
+    ControlFlow::Continue(())
+}
+
+fn test_loop() {
+    let sum = get_instance("sum");
+    let arg = Allocation::from_uint(arg_ty(sum, 0), 4).unwrap();
+    let result = sum.interpret(&[arg], &mut DefaultMachine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 6);
+
+    // The number of arguments is checked.
+    assert!(sum.interpret(&[], &mut DefaultMachine).is_err());
+}
+
+fn test_slice_iter() {
+    // Iterating over a slice offsets and compares pointers into the array.
+    let sum_slice = get_instance("sum_slice");
+    let arg = Allocation::from_uint(arg_ty(sum_slice, 0), 2).unwrap();
+    let result = sum_slice.interpret(&[arg], &mut DefaultMachine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 12);
+}
+
+fn test_overflow() {
+    let double = get_instance("double");
+    let arg = Allocation::from_uint(arg_ty(double, 0), 100).unwrap();
+    let result = double.interpret(&[arg], &mut DefaultMachine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 200);
+
+    let arg = Allocation::from_uint(arg_ty(double, 0), 200).unwrap();
+    let error = double.interpret(&[arg], &mut DefaultMachine).unwrap_err();
+    assert!(error.to_string().contains("overflow"), "{error}");
+}
+
+fn test_foreign_call() {
+    let lookup = get_instance("lookup");
+    let arg = Allocation::from_uint(arg_ty(lookup, 0), 4).unwrap();
+    let error = lookup.interpret(&[arg.clone()], &mut DefaultMachine).unwrap_err();
+    assert!(error.to_string().contains("host_value"), "{error}");
+
+    let mut machine = TestMachine::default();
+    let result = lookup.interpret(&[arg], &mut machine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 41);
+    assert_eq!(machine.foreign_calls, ["host_value"]);
+}
+
+fn test_memory_writes() {
+    let fill = get_instance("fill");
+    let arg = Allocation::from_uint(arg_ty(fill, 0), 7).unwrap();
+    let mut machine = TestMachine::default();
+    let result = fill.interpret(&[arg], &mut machine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 8);
+
+    // `buf[2] = v` writes one byte in the middle of the array.
+    assert!(machine.writes.iter().any(|access| access.offset == 2 && access.size == 1));
+}
+
+fn test_heap() {
+    // The calls of the global allocator are emulated, they aren't foreign calls of the machine.
+    let boxed = get_instance("boxed");
+    let arg = Allocation::from_uint(arg_ty(boxed, 0), 41).unwrap();
+    let mut machine = TestMachine::default();
+    let result = boxed.interpret(&[arg], &mut machine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 42);
+    assert!(machine.foreign_calls.is_empty(), "{:?}", machine.foreign_calls);
+
+    // Pushing to a vector reallocates its buffer.
+    let sum_vec = get_instance("sum_vec");
+    let arg = Allocation::from_uint(arg_ty(sum_vec, 0), 10).unwrap();
+    let mut machine = TestMachine::default();
+    let result = sum_vec.interpret(&[arg], &mut machine).unwrap();
+    assert_eq!(result.read_uint().unwrap(), 45);
+    assert!(machine.foreign_calls.is_empty(), "{:?}", machine.foreign_calls);
+}
+
+/// A machine that provides `host_value`, and that records the memory writes.
+#[derive(Default)]
+struct TestMachine {
+    foreign_calls: Vec<String>,
+    writes: Vec<MemoryAccess>,
+}
+
+impl Machine for TestMachine {
+    fn call_foreign(
+        &mut self,
+        instance: Instance,
+        args: &[Allocation],
+    ) -> Result<Allocation, Error> {
+        self.foreign_calls.push(instance.name());
+        let key = args[0].read_uint()?;
+        Allocation::from_uint(instance.fn_abi()?.ret.ty, key * 10)
+    }
+
+    fn before_memory_write(&mut self, access: MemoryAccess) -> Result<(), Error> {
+        self.writes.push(access);
+        Ok(())
+    }
+}
+
+fn get_instance(name: &str) -> Instance {
+    let item = stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap();
+    Instance::try_from(item).unwrap()
+}
+
+fn arg_ty(instance: Instance, idx: usize) -> Ty {
+    instance.body().unwrap().arg_locals()[idx].ty
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "interpret_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_interpret).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    extern "C" {{
+        fn host_value(key: u32) -> u32;
+    }}
+
+    pub fn sum(n: u32) -> u64 {{
+        let mut total = 0;
+        let mut i = 0;
+        while i < n {{
+            total += i as u64;
+            i += 1;
+        }}
+        total
+    }}
+
+    pub fn sum_slice(n: u32) -> u32 {{
+        let values = [n, 2 * n, 3 * n];
+        let mut total = 0;
+        for value in &values {{
+            total += *value;
+        }}
+        total
+    }}
+
+    pub fn double(v: u8) -> u8 {{
+        v * 2
+    }}
+
+    pub fn lookup(key: u32) -> u32 {{
+        unsafe {{ host_value(key) }} + 1
+    }}
+
+    pub fn fill(v: u8) -> u32 {{
+        let mut buf = [1u8; 4];
+        buf[2] = v;
+        buf[0] as u32 + buf[2] as u32
+    }}
+
+    pub fn boxed(v: u32) -> u32 {{
+        let b = Box::new(v);
+        *b + 1
+    }}
+
+    pub fn sum_vec(n: u32) -> u32 {{
+        let mut values = Vec::new();
+        for i in 0..n {{
+            values.push(i);
+        }}
+        values.iter().sum()
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+//@ run-pass
+//! Test that users are able to write a snapshot of a crate with the stable mir APIs, and read it
+//! back.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::mir::alloc::GlobalAlloc;
+use stable_mir::mir::{Operand, TerminatorKind};
+use stable_mir::snapshot::{DefKind, ItemSnapshot, Snapshot, SNAPSHOT_VERSION};
+use stable_mir::ty::{AdtKind, RigidTy, TyKind, UintTy};
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_snapshot() -> ControlFlow<()> {
+    let mut json = Vec::new();
+    Snapshot::take().write(&mut json).unwrap();
+
+    // Only the snapshot is used from here on, like an analysis running without the compiler would.
+    let snapshot = Snapshot::read(json.as_slice()).unwrap();
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    assert_eq!(snapshot.krate.name, CRATE_NAME);
+    let main = get_item(&snapshot, "main");
+    assert_eq!(snapshot.entry_fn, Some(main.item));
+    assert!(main.fn_abi.is_some());
+    let span = snapshot.span(main.span).unwrap();
+    assert!(span.file.ends_with("snapshot_input.rs"), "{}", span.file);
+    assert_eq!((span.lines.start_line, span.lines.end_line), (2, 2));
+
+    // The `u64` static has a layout.
+    let answer = get_item(&snapshot, "ANSWER");
+    let ty = snapshot.ty(answer.body.locals()[0].ty).unwrap();
+    assert_eq!(ty.name, "u64");
+    assert_eq!(ty.kind, TyKind::RigidTy(RigidTy::Uint(UintTy::U64)));
+    assert_eq!(ty.layout.as_ref().unwrap().size.bytes(), 8);
+
+    // The constants in the bodies point to the static and to the bytes of the string literal.
+    let allocs: Vec<_> = snapshot.allocs.iter().map(|alloc| &alloc.alloc).collect();
+    let static_def = allocs
+        .iter()
+        .find_map(|alloc| if let GlobalAlloc::Static(def) = alloc { Some(def) } else { None })
+        .unwrap();
+    let hello = b"hello".map(Some);
+    assert!(allocs.iter().any(|alloc| matches!(alloc, GlobalAlloc::Memory(a) if a.bytes == hello)));
+
+    // The static is resolved with its initial value.
+    let answer = snapshot.def(static_def.0).unwrap();
+    assert_eq!(answer.name, "ANSWER");
+    assert_eq!(answer.krate.name, CRATE_NAME);
+    let DefKind::Static { initializer: Some(initializer) } = &answer.kind else {
+        panic!("Expected the initializer of `ANSWER`");
+    };
+    assert_eq!(initializer.bytes, 42u64.to_ne_bytes().map(Some));
+
+    // The functions called by `main` are resolved by the types of the called constants.
+    let callees: Vec<_> = main
+        .body
+        .blocks
+        .iter()
+        .filter_map(|block| match &block.terminator.kind {
+            TerminatorKind::Call { func: Operand::Constant(constant), .. } => {
+                Some(constant.literal.ty())
+            }
+            _ => None,
+        })
+        .map(|ty| match snapshot.ty(ty).unwrap().kind {
+            TyKind::RigidTy(RigidTy::FnDef(def, _)) => snapshot.def(def.0).unwrap().name.clone(),
+            ref kind => panic!("Expected a function definition, but found {kind:?}"),
+        })
+        .collect();
+    for callee in ["greeting", "len", "answer"] {
+        assert!(callees.iter().any(|name| name == callee), "{callee} in {callees:?}");
+    }
+
+    // The struct is resolved with its fields, and the types of the fields are in the types table.
+    let len = get_item(&snapshot, "len");
+    let TyKind::RigidTy(RigidTy::Adt(def, _)) =
+        snapshot.ty(len.body.arg_locals()[0].ty).unwrap().kind
+    else {
+        panic!("Expected the argument of `len` to be a struct");
+    };
+    let greeting = snapshot.def(def.0).unwrap();
+    assert_eq!(greeting.name, "Greeting");
+    let DefKind::Adt { kind: AdtKind::Struct, variants } = &greeting.kind else {
+        panic!("Expected `Greeting` to be a struct");
+    };
+    let [variant] = &variants[..] else { panic!("Expected a single variant") };
+    let [field] = &variant.fields[..] else { panic!("Expected a single field") };
+    assert_eq!(field.name, "text");
+    let TyKind::RigidTy(RigidTy::Ref(_, str_ty, _)) = snapshot.ty(field.ty).unwrap().kind else {
+        panic!("Expected `text` to be a reference");
+    };
+    assert_eq!(snapshot.ty(str_ty).unwrap().name, "str");
+
+    // Snapshots of other versions are rejected.
+    let json = String::from_utf8(json).unwrap();
+    let prefix = format!("{{\"version\":{SNAPSHOT_VERSION},");
+    let old = json.replacen(&prefix, "{\"version\":0,", 1);
+    assert_ne!(json, old);
+    assert!(Snapshot::read(old.as_bytes()).is_err());
+    ControlFlow::Continue(())
+}
+
+fn get_item<'a>(snapshot: &'a Snapshot, name: &str) -> &'a ItemSnapshot {
+    snapshot.items.iter().find(|item| item.name == name).unwrap()
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "snapshot_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=bin".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_snapshot).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+fn main() {{
+    let _ = len(Greeting {{ text: greeting() }}) as u64 + *answer();
+}}
+
+struct Greeting {{
+    text: &'static str,
+}}
+
+fn len(greeting: Greeting) -> usize {{
+    greeting.text.len()
+}}
+
+static ANSWER: u64 = 42;
+
+fn answer() -> &'static u64 {{
+    &ANSWER
+}}
+
+fn greeting() -> &'static str {{
+    "hello"
+}}
+"#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,181 @@
+//@ run-pass
+//! Test that users are able to ask the trait solver whether a type implements a trait, which
+//! implementation is used, and to normalize associated types.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use stable_mir::ty::{
+    BuiltinImplSource, GenericArgKind, GenericArgs, ImplSource, ParamEnv, RigidTy, TraitDef,
+    TraitRef, Ty, UintTy,
+};
+use stable_mir::{CrateDef, CrateItem};
+use std::assert_matches::assert_matches;
+use std::io::Write;
+use std::ops::ControlFlow;
+
+const CRATE_NAME: &str = "input";
+
+/// This function uses the Stable MIR APIs to get information about the test crate.
+fn test_trait_solving() -> ControlFlow<()> {
+    let copy = get_trait("std::marker::Copy");
+    let clone = get_trait("std::clone::Clone");
+    let send = get_trait("std::marker::Send");
+    let no_args = GenericArgs(vec![]);
+
+    // Primitives implement `Copy` in `core`, tuples are handled by the compiler.
+    let u32_ty = Ty::unsigned_ty(UintTy::U32);
+    let copy_u32 = TraitRef::new(copy, u32_ty, &no_args);
+    assert!(copy_u32.is_implemented(ParamEnv::RevealAll));
+    assert_matches!(copy_u32.select(ParamEnv::RevealAll), Ok(ImplSource::UserDefined { .. }));
+    let tuple = Ty::new_tuple(&[u32_ty, u32_ty]);
+    assert_eq!(
+        TraitRef::new(copy, tuple, &no_args).select(ParamEnv::RevealAll),
+        Ok(ImplSource::Builtin(BuiltinImplSource::Misc))
+    );
+
+    let RigidTy::Tuple(tys) =
+        get_item("make").body().ret_local().ty.kind().rigid().unwrap().clone()
+    else {
+        unreachable!()
+    };
+    let [square, point, wrapped_square, wrapped_point] = tys[..] else { unreachable!() };
+
+    // User defined implementations.
+    assert!(!square.implements(copy, &no_args, ParamEnv::RevealAll));
+    assert!(point.implements(copy, &no_args, ParamEnv::RevealAll));
+    let Ok(ImplSource::UserDefined { impl_def, args }) =
+        TraitRef::new(clone, wrapped_point, &no_args).select(ParamEnv::RevealAll)
+    else {
+        unreachable!()
+    };
+    assert_eq!(impl_def.trait_impl().value.def_id, clone);
+    assert_eq!(args.0, [GenericArgKind::Type(point)]);
+    assert!(TraitRef::new(clone, wrapped_square, &no_args).select(ParamEnv::RevealAll).is_err());
+
+    // Auto traits.
+    assert_eq!(
+        TraitRef::new(send, wrapped_square, &no_args).select(ParamEnv::RevealAll),
+        Ok(ImplSource::Builtin(BuiltinImplSource::Misc))
+    );
+
+    // Where clauses.
+    let generic = get_item("generic");
+    let param = generic.body().arg_locals()[0].ty;
+    let copy_param = TraitRef::new(copy, param, &no_args);
+    assert!(copy_param.is_implemented(ParamEnv::of(generic)));
+    assert!(param.implements(clone, &no_args, ParamEnv::of(generic)));
+    assert!(!copy_param.is_implemented(ParamEnv::RevealAll));
+    assert!(!param.implements(send, &no_args, ParamEnv::of(generic)));
+    // Implementations are only selected for monomorphic code.
+    assert!(copy_param.select(ParamEnv::of(generic)).is_err());
+    assert!(copy_param.select(ParamEnv::RevealAll).is_err());
+    assert!(TraitRef::new(copy, u32_ty, &no_args).select(ParamEnv::of(generic)).is_err());
+
+    // Trait objects.
+    let dynamic = get_item("dynamic");
+    let RigidTy::Ref(_, object, _) =
+        dynamic.body().arg_locals()[0].ty.kind().rigid().unwrap().clone()
+    else {
+        unreachable!()
+    };
+    assert_matches!(
+        TraitRef::new(get_trait("Shape"), object, &no_args).select(ParamEnv::RevealAll),
+        Ok(ImplSource::Builtin(BuiltinImplSource::Object { .. }))
+    );
+
+    // Associated types.
+    let sig = get_item("area_of").ty().kind().fn_sig().unwrap().skip_binder();
+    assert_matches!(sig.output().kind().rigid(), None);
+    assert_eq!(sig.output().normalize(ParamEnv::RevealAll), Ok(Ty::unsigned_ty(UintTy::U64)));
+    ControlFlow::Continue(())
+}
+
+fn get_item(name: &str) -> CrateItem {
+    stable_mir::all_local_items().into_iter().find(|item| item.name() == name).unwrap()
+}
+
+fn get_trait(name: &str) -> TraitDef {
+    stable_mir::all_trait_decls().into_iter().find(|def| def.name() == name).unwrap()
+}
+
+/// This test will generate and analyze a dummy crate using the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will create a `StableMir` using custom arguments and then
+/// it will run the compiler.
+fn main() {
+    let path = "trait_solving_input.rs";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "--crate-type=lib".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        path.to_string(),
+    ];
+    run!(args, test_trait_solving).unwrap();
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    pub trait Shape {{
+        type Area;
+        fn area(&self) -> Self::Area;
+    }}
+
+    pub struct Square(pub u32);
+
+    impl Shape for Square {{
+        type Area = u64;
+        fn area(&self) -> u64 {{
+            self.0 as u64 * self.0 as u64
+        }}
+    }}
+
+    #[derive(Clone, Copy)]
+    pub struct Point;
+
+    pub struct Wrapper<T>(pub T);
+
+    impl<T: Clone> Clone for Wrapper<T> {{
+        fn clone(&self) -> Self {{
+            Wrapper(self.0.clone())
+        }}
+    }}
+
+    pub fn make() -> (Square, Point, Wrapper<Square>, Wrapper<Point>) {{
+        (Square(1), Point, Wrapper(Square(2)), Wrapper(Point))
+    }}
+
+    pub fn generic<T: Copy + Shape>(t: T) -> T::Area {{
+        t.area()
+    }}
+
+    pub fn dynamic(shape: &dyn Shape<Area = u64>) -> u64 {{
+        shape.area()
+    }}
+
+    pub fn area_of(square: Square) -> <Square as Shape>::Area {{
+        square.area()
+    }}
+    "#
+    )?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//@ run-pass
+//! Test that users are able to transform MIR bodies with the stable mir APIs, and that the
+//! transformed bodies are the ones that get code generated. Also test that transformations are
+//! only applied by the session they were registered for, and that invalid ones are reported.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+//@ ignore-windows-gnu mingw has troubles with linking https://github.com/rust-lang/rust/pull/116837
+//@ edition: 2021
+
+#![feature(rustc_private)]
+#![feature(assert_matches)]
+#![feature(control_flow_enum)]
+
+#[macro_use]
+extern crate rustc_smir;
+extern crate rustc_driver;
+extern crate rustc_interface;
+extern crate stable_mir;
+
+use rustc_smir::rustc_internal;
+use rustc_smir::rustc_internal::transform::BodyTransform;
+use stable_mir::mir::visit::Location;
+use stable_mir::mir::{
+    BinOp, Body, MirVisitor, MutMirVisitor, Place, Rvalue, Statement, StatementKind,
+    TerminatorKind, UserTypeProjection, Variance,
+};
+use stable_mir::{opaque, CompilerError, CrateDef, CrateItem};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::process::Command;
+
+const CRATE_NAME: &str = "input";
+
+/// Replaces every `|` by the given operation.
+struct ReplaceOr(BinOp);
+
+impl MutMirVisitor for ReplaceOr {
+    fn visit_rvalue(&mut self, rvalue: &mut Rvalue, location: Location) {
+        if let Rvalue::BinaryOp(op @ BinOp::BitOr, ..) = rvalue {
+            *op = self.0;
+        }
+        self.super_rvalue(rvalue, location)
+    }
+}
+
+fn or_to_and(item: CrateItem, body: &mut Body) {
+    if item.name() == "combine" {
+        ReplaceOr(BinOp::BitAnd).visit_body(body);
+    }
+}
+
+/// Replaces `|` by `==`, whose `bool` result is assigned to an `u8`.
+fn ill_typed(item: CrateItem, body: &mut Body) {
+    if item.name() == "combine" {
+        ReplaceOr(BinOp::Eq).visit_body(body);
+    }
+}
+
+/// Marks a local that isn't declared as live.
+fn missing_local(item: CrateItem, body: &mut Body) {
+    if item.name() == "combine" {
+        let span = body.blocks[0].terminator.span;
+        let kind = StatementKind::StorageLive(body.locals().len());
+        body.blocks[0].statements.push(Statement { kind, span });
+    }
+}
+
+/// Jumps to a block that doesn't exist.
+fn missing_block(item: CrateItem, body: &mut Body) {
+    if item.name() == "combine" {
+        body.blocks[0].terminator.kind = TerminatorKind::Goto { target: body.blocks.len() };
+    }
+}
+
+/// Adds a user type ascription, which can't be part of an optimized body.
+fn ascribe_user_type(item: CrateItem, body: &mut Body) {
+    if item.name() == "combine" {
+        let span = body.blocks[0].terminator.span;
+        let kind = StatementKind::AscribeUserType {
+            place: Place::from(0),
+            projections: UserTypeProjection { base: 0, projection: opaque(&()) },
+            variance: Variance::Invariant,
+        };
+        body.blocks[0].statements.push(Statement { kind, span });
+    }
+}
+
+/// Finds the binary operations of a body.
+#[derive(Default)]
+struct BinOps(Vec<BinOp>);
+
+impl MirVisitor for BinOps {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue, location: Location) {
+        if let Rvalue::BinaryOp(op, ..) = rvalue {
+            self.0.push(*op);
+        }
+        self.super_rvalue(rvalue, location)
+    }
+}
+
+/// This function uses the Stable MIR APIs to check that the body was transformed.
+fn test_stable_mir() -> ControlFlow<()> {
+    let items = stable_mir::all_local_items();
+    let combine = items.iter().find(|item| item.name() == "combine").unwrap();
+    let mut bin_ops = BinOps::default();
+    bin_ops.visit_body(&combine.body());
+    assert_eq!(bin_ops.0, [BinOp::BitAnd]);
+    ControlFlow::Continue(())
+}
+
+/// This test will generate a dummy binary and transform it with the stable mir.
+/// For that, it will first write the dummy crate into a file.
+/// Then it will register the transformation and run the compiler.
+/// Finally, it runs the generated binary, which exits with the result of the transformed code.
+fn main() {
+    let path = "transform_input.rs";
+    let output = "transform_output";
+    generate_input(&path).unwrap();
+    let args = vec![
+        "rustc".to_string(),
+        "-Cpanic=abort".to_string(),
+        "--crate-type=bin".to_string(),
+        "--crate-name".to_string(),
+        CRATE_NAME.to_string(),
+        "-o".to_string(),
+        output.to_string(),
+        path.to_string(),
+    ];
+    rustc_internal::transform::register_body_transform(or_to_and);
+    run!(args.clone(), test_stable_mir).unwrap();
+
+    let status = Command::new(format!("./{output}")).status().unwrap();
+    assert_eq!(status.code(), Some(6 & 3));
+
+    // The transformation was only registered for the previous session.
+    run!(args.clone(), || ControlFlow::<()>::Continue(())).unwrap();
+    let status = Command::new(format!("./{output}")).status().unwrap();
+    assert_eq!(status.code(), Some(6 | 3));
+
+    // Invalid transformed bodies are reported instead of being code generated.
+    let invalid: [BodyTransform; 4] = [ascribe_user_type, ill_typed, missing_local, missing_block];
+    for transform in invalid {
+        rustc_internal::transform::register_body_transform(transform);
+        let result = run!(args.clone(), || ControlFlow::<()>::Continue(()));
+        assert_eq!(result, Err(CompilerError::Failed));
+    }
+}
+
+fn generate_input(path: &str) -> std::io::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+    write!(
+        file,
+        r#"
+    #[inline(never)]
+    fn combine(a: u8, b: u8) -> u8 {{
+        a | b
+    }}
+
+    fn main() {{
+        let value = combine(std::hint::black_box(6), 3);
+        std::process::exit(value as i32);
+    }}
+    "#
+    )?;
+    Ok(())
+}
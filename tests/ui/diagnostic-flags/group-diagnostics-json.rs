@@ -0,0 +1,16 @@
+//@ check-pass
+//@ compile-flags: -Z group-diagnostics --error-format=json
+
+#![warn(unused_variables)]
+
+macro_rules! unused {
+    () => {
+        let x = 0;
+    };
+}
+
+fn main() {
+    unused!();
+    unused!();
+    unused!();
+}
@@ -0,0 +1,20 @@
+//@ check-pass
+//@ compile-flags: -Z group-diagnostics
+
+#![warn(unused_variables)]
+
+macro_rules! unused {
+    () => {
+        let x = 0;
+        //~^ WARNING unused variable: `x`
+        // The same warning at another place in the macro is grouped separately.
+        let x = 0;
+        //~^ WARNING unused variable: `x`
+    };
+}
+
+fn main() {
+    unused!();
+    unused!();
+    unused!();
+}
@@ -0,0 +1,15 @@
+// Check that lint levels are read from the file passed with `--lint-config`, and that
+// lint flags on the command line take precedence over them.
+
+//@ compile-flags: -Zunstable-options --lint-config {{src-base}}/lint/lint-config/lint-config.toml
+//@ compile-flags: -A unused-imports
+
+use std::fmt;
+
+fn unused_function() {}
+
+fn main() {
+    let x = 1; //~ ERROR unused variable: `x`
+    let mut y = 2; //~ WARN variable does not need to be mutable
+    drop(y);
+}
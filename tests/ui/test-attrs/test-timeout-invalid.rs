@@ -0,0 +1,11 @@
+//@ compile-flags: --test
+
+#![feature(test_timeout)]
+
+#[test]
+#[test_timeout = "soon"] //~ ERROR `#[test_timeout]` must be a number of seconds greater than 0
+fn not_a_number() {}
+
+#[test]
+#[test_timeout = "0"] //~ ERROR `#[test_timeout]` must be a number of seconds greater than 0
+fn zero() {}
@@ -0,0 +1,35 @@
+//@ no-prefer-dynamic
+//@ compile-flags: --test
+//@ run-flags: --test-threads=1 -Zunstable-options --test-timeout=60
+//@ run-fail
+//@ check-run-results
+//@ exec-env:RUST_BACKTRACE=0
+//@ normalize-stdout-test "finished in \d+\.\d+s" -> "finished in $$TIME"
+
+//@ ignore-android #120567
+//@ ignore-wasm no subprocess support
+//@ ignore-emscripten no subprocess support
+//@ ignore-sgx no subprocess support
+
+#![cfg(test)]
+#![feature(test_timeout)]
+
+#[test]
+fn it_works() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[test]
+#[should_panic]
+fn it_panics() {
+    assert_eq!(1 + 1, 4);
+}
+
+#[test]
+#[test_timeout = "1"]
+fn it_hangs() {
+    println!("waiting");
+    loop {
+        std::thread::park();
+    }
+}
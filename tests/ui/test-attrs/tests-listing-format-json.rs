@@ -19,3 +19,9 @@ fn z_test() {}
 
 #[test]
 fn a_test() {}
+
+#[test]
+#[should_panic(expected = "\"quoted\" message")]
+fn p_test() {
+    panic!("\"quoted\" message");
+}